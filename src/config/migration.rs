@@ -0,0 +1,636 @@
+//! # Config Versioning and Migrations
+//!
+//! A configuration declares its format version in a key such as
+//! `config_version = 3`. The application registers one step per version
+//! (`N` to `N + 1`) and [`Config::migrate_to`] applies the steps in order,
+//! editing the parsed document in place so comments and formatting survive
+//! a round trip through [`Config::save`].
+//!
+//! ```rust
+//! use noml::{Config, Migrations, Value};
+//!
+//! let migrations = Migrations::new()
+//!     .step(1, |step| step.rename_key("db_host", "database.host"))
+//!     .step(2, |step| {
+//!         step.transform_value("timeout", |value| Ok(Value::Integer(value.as_integer()? * 1000)))
+//!             .remove_key("legacy_mode")
+//!     });
+//!
+//! let mut config = Config::from_string(r#"
+//! config_version = 1
+//! db_host = "localhost"  # primary database
+//! timeout = 30
+//! legacy_mode = true
+//! "#)?;
+//!
+//! assert_eq!(config.migrate_to(&migrations, migrations.latest())?, 2);
+//! assert_eq!(config.get("config_version").unwrap().as_integer()?, 3);
+//! assert_eq!(config.get("database.host").unwrap().as_string()?, "localhost");
+//! assert_eq!(config.get("timeout").unwrap().as_integer()?, 30000);
+//! assert!(config.get("legacy_mode").is_none());
+//! assert!(config.to_noml_string()?.contains("# primary database"));
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+//!
+//! Migrations can also be declared in a NOML file (see
+//! [`Migrations::from_value`]), which is what `noml migrate` uses.
+
+use super::Config;
+use crate::error::{NomlError, Result};
+use crate::parser::Document;
+use crate::resolver::builtin_natives;
+use crate::value::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Default key holding the configuration format version
+pub const DEFAULT_VERSION_KEY: &str = "config_version";
+
+type TransformFn = Arc<dyn Fn(Value) -> Result<Value> + Send + Sync>;
+type CustomFn = Arc<dyn Fn(&mut MigrationContext<'_>) -> Result<()> + Send + Sync>;
+
+/// A registry of migration steps keyed by the version they upgrade from
+#[derive(Clone)]
+pub struct Migrations {
+    version_key: String,
+    default_version: Option<u32>,
+    steps: BTreeMap<u32, MigrationStep>,
+}
+
+/// The operations that upgrade a configuration by one version
+#[derive(Clone, Default)]
+pub struct MigrationStep {
+    ops: Vec<Operation>,
+}
+
+#[derive(Clone)]
+enum Operation {
+    RenameKey { from: String, to: String },
+    MoveTable { from: String, to: String },
+    TransformValue { key: String, transform: TransformFn },
+    RemoveKey(String),
+    SetValue { key: String, value: Value },
+    Custom(CustomFn),
+}
+
+/// Editing access to a configuration while a migration step runs
+///
+/// Every change is applied to both the resolved values and the source
+/// document, so the migrated file keeps its comments.
+pub struct MigrationContext<'a> {
+    values: &'a mut Value,
+    document: &'a mut Document,
+}
+
+impl Migrations {
+    /// Create an empty registry using the `config_version` key
+    pub fn new() -> Self {
+        Self {
+            version_key: DEFAULT_VERSION_KEY.to_string(),
+            default_version: None,
+            steps: BTreeMap::new(),
+        }
+    }
+
+    /// Use a different key to store the configuration version
+    pub fn version_key(mut self, key: impl Into<String>) -> Self {
+        self.version_key = key.into();
+        self
+    }
+
+    /// Treat configurations without a version key as this version
+    ///
+    /// Without a default, migrating an unversioned configuration is an error.
+    pub fn default_version(mut self, version: u32) -> Self {
+        self.default_version = Some(version);
+        self
+    }
+
+    /// Register the step that upgrades version `from` to `from + 1`
+    pub fn step<F>(mut self, from: u32, build: F) -> Self
+    where
+        F: FnOnce(MigrationStep) -> MigrationStep,
+    {
+        self.steps.insert(from, build(MigrationStep::default()));
+        self
+    }
+
+    /// The version reached after applying every registered step
+    pub fn latest(&self) -> u32 {
+        self.steps
+            .keys()
+            .next_back()
+            .map_or(self.default_version.unwrap_or(0), |from| from + 1)
+    }
+
+    /// The key holding the configuration version
+    pub fn key(&self) -> &str {
+        &self.version_key
+    }
+
+    /// Build migrations from a parsed migration file
+    ///
+    /// ```noml
+    /// version_key = "config_version"  # optional
+    /// default_version = 1             # optional
+    ///
+    /// [[migrations]]
+    /// from = 1
+    /// ops = [
+    ///     { kind = "rename_key", from = "db_host", to = "database.host" },
+    ///     { kind = "move_table", from = "cache", to = "services.cache" },
+    ///     { kind = "remove_key", key = "legacy_mode" },
+    ///     { kind = "set_value", key = "database.pool", value = 10 },
+    /// ]
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Migrations> {
+        let mut migrations = Migrations::new();
+
+        if let Some(key) = value.get("version_key") {
+            migrations.version_key = key
+                .as_string()
+                .map_err(|_| NomlError::validation_at("Expected a string", "version_key"))?
+                .to_string();
+        }
+        if let Some(version) = value.get("default_version") {
+            migrations.default_version = Some(as_version(version, "default_version")?);
+        }
+
+        let Some(entries) = value.get("migrations") else {
+            return Ok(migrations);
+        };
+        let Value::Array(entries) = entries else {
+            return Err(NomlError::validation_at(
+                "Expected an array of migrations",
+                "migrations",
+            ));
+        };
+        for (i, entry) in entries.iter().enumerate() {
+            let path = format!("migrations[{i}]");
+            let from = entry
+                .get("from")
+                .ok_or_else(|| NomlError::validation_at("Missing 'from' version", &path))
+                .and_then(|from| as_version(from, &format!("{path}.from")))?;
+            if migrations.steps.contains_key(&from) {
+                return Err(NomlError::validation_at(
+                    format!("Duplicate migration from version {from}"),
+                    path,
+                ));
+            }
+
+            let mut step = MigrationStep::default();
+            match entry.get("ops") {
+                Some(Value::Array(ops)) => {
+                    for (j, op) in ops.iter().enumerate() {
+                        step.ops
+                            .push(op_from_value(op, &format!("{path}.ops[{j}]"))?);
+                    }
+                }
+                Some(_) => {
+                    return Err(NomlError::validation_at(
+                        "Expected an array of operations",
+                        format!("{path}.ops"),
+                    ))
+                }
+                None => {}
+            }
+            migrations.steps.insert(from, step);
+        }
+        Ok(migrations)
+    }
+
+    /// Load migrations from a NOML migration file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Migrations> {
+        Self::from_value(&crate::parse_from_file(path)?)
+    }
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("version_key", &self.version_key)
+            .field("default_version", &self.default_version)
+            .field("steps", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl MigrationStep {
+    /// Rename a key, keeping its value and comments
+    ///
+    /// Does nothing if the key is absent; fails if the new key already exists.
+    pub fn rename_key(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.ops.push(Operation::RenameKey {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Move a table (and everything in it) to a new path
+    ///
+    /// Does nothing if the table is absent; fails if the source is not a
+    /// table or the destination already exists.
+    pub fn move_table(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.ops.push(Operation::MoveTable {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Replace the value at `key` with the result of `transform`
+    ///
+    /// Does nothing if the key is absent.
+    pub fn transform_value<F>(mut self, key: impl Into<String>, transform: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.ops.push(Operation::TransformValue {
+            key: key.into(),
+            transform: Arc::new(transform),
+        });
+        self
+    }
+
+    /// Remove a key and everything nested under it
+    pub fn remove_key(mut self, key: impl Into<String>) -> Self {
+        self.ops.push(Operation::RemoveKey(key.into()));
+        self
+    }
+
+    /// Set a key, creating it if needed
+    pub fn set_value(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.ops.push(Operation::SetValue {
+            key: key.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Run arbitrary edits through a [`MigrationContext`]
+    pub fn custom<F>(mut self, migrate: F) -> Self
+    where
+        F: Fn(&mut MigrationContext<'_>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.ops.push(Operation::Custom(Arc::new(migrate)));
+        self
+    }
+
+    fn apply(&self, context: &mut MigrationContext<'_>) -> Result<()> {
+        for op in &self.ops {
+            match op {
+                Operation::RenameKey { from, to } => {
+                    context.rename(from, to)?;
+                }
+                Operation::MoveTable { from, to } => {
+                    if let Some(value) = context.get(from) {
+                        if !value.is_table() {
+                            return Err(NomlError::validation_at(
+                                format!(
+                                    "Cannot move '{from}': expected a table, found {}",
+                                    value.type_name()
+                                ),
+                                from.as_str(),
+                            ));
+                        }
+                        context.rename(from, to)?;
+                    }
+                }
+                Operation::TransformValue { key, transform } => {
+                    if let Some(value) = context.get(key).cloned() {
+                        context.set(key, transform(value)?)?;
+                    }
+                }
+                Operation::RemoveKey(key) => {
+                    context.remove(key)?;
+                }
+                Operation::SetValue { key, value } => context.set(key, value.clone())?,
+                Operation::Custom(migrate) => migrate(context)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MigrationContext<'_> {
+    /// Get a value by key path
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    /// Set a value by key path
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) -> Result<()> {
+        let value = value.into();
+        self.document
+            .set_native_value(key, &value, &builtin_natives())?;
+        self.values.set(key, value)
+    }
+
+    /// Remove a value by key path, returning it if it existed
+    pub fn remove(&mut self, key: &str) -> Result<Option<Value>> {
+        let removed = self.values.remove(key)?;
+        if removed.is_some() {
+            self.document.remove_path(key);
+        }
+        Ok(removed)
+    }
+
+    /// Move the value at `from` to `to`, keeping its comments
+    ///
+    /// Returns whether anything was moved. Fails if `from` has a value that
+    /// isn't written in this file, since the rename couldn't be saved.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<bool> {
+        if self.values.get(from).is_none() {
+            return Ok(false);
+        }
+        if self.values.get(to).is_some() {
+            return Err(NomlError::validation_at(
+                format!("Cannot rename '{from}' to '{to}': '{to}' already exists"),
+                to,
+            ));
+        }
+        if self.document.get_node(from).is_none() {
+            return Err(NomlError::validation_at(
+                format!(
+                    "Cannot rename '{from}': it is not written in this file \
+                     (it comes from an include or extends)"
+                ),
+                from,
+            ));
+        }
+        self.document.rename_path(from, to)?;
+        if let Some(value) = self.values.remove(from)? {
+            self.values.set(to, value)?;
+        }
+        Ok(true)
+    }
+}
+
+impl Config {
+    /// Migrate this configuration to version `target`
+    ///
+    /// Steps run in order from the current version, and the version key is
+    /// bumped after each one. The configuration is left untouched if any
+    /// step fails. Returns the number of steps applied.
+    pub fn migrate_to(&mut self, migrations: &Migrations, target: u32) -> Result<usize> {
+        self.resolve_all()?;
+        let key = migrations.version_key.as_str();
+        let current = match self.values.get(key) {
+            Some(version) => as_version(version, key)?,
+            None => migrations.default_version.ok_or_else(|| {
+                NomlError::validation_at(format!("Configuration has no '{key}' key"), key)
+            })?,
+        };
+        if current > target {
+            return Err(NomlError::validation_at(
+                format!("Cannot migrate configuration from version {current} down to {target}"),
+                key,
+            ));
+        }
+        if current == target {
+            return Ok(0);
+        }
+
+        let mut values = self.values.clone();
+        let mut document = self.document.clone();
+        let mut context = MigrationContext {
+            values: &mut values,
+            document: &mut document,
+        };
+        for version in current..target {
+            let step = migrations.steps.get(&version).ok_or_else(|| {
+                NomlError::validation(format!(
+                    "No migration registered from version {version} to {}",
+                    version + 1
+                ))
+            })?;
+            step.apply(&mut context).map_err(|e| {
+                NomlError::validation(format!(
+                    "Migration from version {version} to {} failed: {e}",
+                    version + 1
+                ))
+            })?;
+            context.set(key, Value::Integer(i64::from(version) + 1))?;
+        }
+
+        self.values = values;
+        self.document = document;
+        self.modified = true;
+        Ok((target - current) as usize)
+    }
+}
+
+fn as_version(value: &Value, path: &str) -> Result<u32> {
+    match value {
+        Value::Integer(n) => u32::try_from(*n).ok(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        NomlError::validation_at(
+            format!("Expected a non-negative integer version, found {value}"),
+            path,
+        )
+    })
+}
+
+fn op_from_value(op: &Value, path: &str) -> Result<Operation> {
+    let string = |key: &str| -> Result<String> {
+        op.get(key)
+            .and_then(|v| v.as_string().ok())
+            .map(str::to_string)
+            .ok_or_else(|| NomlError::validation_at("Expected a string", format!("{path}.{key}")))
+    };
+
+    match string("kind")?.as_str() {
+        "rename_key" => Ok(Operation::RenameKey {
+            from: string("from")?,
+            to: string("to")?,
+        }),
+        "move_table" => Ok(Operation::MoveTable {
+            from: string("from")?,
+            to: string("to")?,
+        }),
+        "remove_key" => Ok(Operation::RemoveKey(string("key")?)),
+        "set_value" => Ok(Operation::SetValue {
+            key: string("key")?,
+            value: op.get("value").cloned().ok_or_else(|| {
+                NomlError::validation_at("Missing value", format!("{path}.value"))
+            })?,
+        }),
+        other => Err(NomlError::validation_at(
+            format!("Unknown migration operation '{other}'"),
+            format!("{path}.kind"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"# Service configuration
+config_version = 1
+
+# Primary database host
+db_host = "localhost"
+timeout = 30 # seconds
+
+[cache]
+ttl = 60
+"#;
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .step(1, |step| {
+                step.rename_key("db_host", "database.host")
+                    .move_table("cache", "services.cache")
+            })
+            .step(2, |step| {
+                step.transform_value("timeout", |value| {
+                    Ok(Value::Integer(value.as_integer()? * 1000))
+                })
+                .custom(|ctx| {
+                    let ttl = ctx.get("services.cache.ttl").cloned();
+                    if let Some(ttl) = ttl {
+                        ctx.set("services.cache.ttl_ms", ttl.as_integer()? * 1000)?;
+                        ctx.remove("services.cache.ttl")?;
+                    }
+                    Ok(())
+                })
+            })
+    }
+
+    #[test]
+    fn test_migrate_preserves_comments() {
+        let mut config = Config::from_string(SOURCE).unwrap();
+        assert_eq!(config.migrate_to(&migrations(), 3).unwrap(), 2);
+        assert!(config.is_modified());
+
+        assert_eq!(
+            config.get("config_version").unwrap().as_integer().unwrap(),
+            3
+        );
+        assert_eq!(
+            config.get("database.host").unwrap().as_string().unwrap(),
+            "localhost"
+        );
+        assert_eq!(config.get("timeout").unwrap().as_integer().unwrap(), 30000);
+        assert_eq!(
+            config
+                .get("services.cache.ttl_ms")
+                .unwrap()
+                .as_integer()
+                .unwrap(),
+            60000
+        );
+        assert!(config.get("db_host").is_none());
+        assert!(config.get("cache").is_none());
+
+        let output = config.to_noml_string().unwrap();
+        assert!(output.starts_with("# Service configuration\n"));
+        assert!(output.contains("# Primary database host\ndatabase.host = \"localhost\""));
+        assert!(output.contains("timeout = 30000 # seconds"));
+
+        // The rewritten document parses back to the same values
+        let reparsed = Config::from_string(&output).unwrap();
+        assert_eq!(reparsed.as_value(), config.as_value());
+    }
+
+    #[test]
+    fn test_migrate_errors_leave_config_untouched() {
+        let mut config = Config::from_string(SOURCE).unwrap();
+        let err = config.migrate_to(&migrations(), 4).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No migration registered from version 3 to 4"));
+        assert_eq!(
+            config.get("config_version").unwrap().as_integer().unwrap(),
+            1
+        );
+        assert!(config.get("db_host").is_some());
+        assert!(!config.is_modified());
+
+        let err = config.migrate_to(&migrations(), 0).unwrap_err();
+        assert!(err.to_string().contains("down to 0"));
+
+        let conflicting = Migrations::new().step(1, |step| step.rename_key("db_host", "timeout"));
+        let err = config.migrate_to(&conflicting, 2).unwrap_err();
+        assert!(err.to_string().contains("'timeout' already exists"));
+        assert!(config.get("db_host").is_some());
+
+        let secret = Migrations::new().step(1, |step| step.set_value("token", Value::secret("x")));
+        let err = config.migrate_to(&secret, 2).unwrap_err();
+        assert!(err.to_string().contains("cannot be written"), "{err}");
+        assert!(config.get("token").is_none());
+
+        // A key that isn't in the file (here a default) can't be renamed
+        let mut defaulted = Config::builder()
+            .default_value("db_host", "localhost")
+            .build_from_string("config_version = 1")
+            .unwrap();
+        let rename = Migrations::new().step(1, |step| step.rename_key("db_host", "host"));
+        let err = defaulted.migrate_to(&rename, 2).unwrap_err();
+        assert!(
+            err.to_string().contains("not written in this file"),
+            "{err}"
+        );
+        assert!(defaulted.get("db_host").is_some());
+        assert!(defaulted.get("host").is_none());
+    }
+
+    #[test]
+    fn test_unversioned_config() {
+        let mut config = Config::from_string("name = \"svc\"").unwrap();
+        let err = config.migrate_to(&Migrations::new(), 1).unwrap_err();
+        assert!(err.to_string().contains("no 'config_version' key"));
+
+        let migrations = Migrations::new()
+            .version_key("schema")
+            .default_version(0)
+            .step(0, |step| step.set_value("port", 8080));
+        assert_eq!(config.migrate_to(&migrations, 1).unwrap(), 1);
+        assert_eq!(config.get("schema").unwrap().as_integer().unwrap(), 1);
+        assert_eq!(config.get("port").unwrap().as_integer().unwrap(), 8080);
+    }
+
+    #[test]
+    fn test_migrations_from_value() {
+        let definition = crate::parse(
+            r#"
+            [[migrations]]
+            from = 1
+            ops = [
+                { kind = "rename_key", from = "db_host", to = "database.host" },
+                { kind = "remove_key", key = "timeout" },
+                { kind = "set_value", key = "database.pool", value = 10 },
+            ]
+            "#,
+        )
+        .unwrap();
+        let migrations = Migrations::from_value(&definition).unwrap();
+        assert_eq!(migrations.latest(), 2);
+
+        let mut config = Config::from_string(SOURCE).unwrap();
+        config.migrate_to(&migrations, 2).unwrap();
+        assert_eq!(
+            config.get("database.pool").unwrap().as_integer().unwrap(),
+            10
+        );
+        assert!(config.get("timeout").is_none());
+
+        let bad = crate::parse("[[migrations]]\nfrom = 1\nops = [{ kind = \"explode\" }]").unwrap();
+        let err = Migrations::from_value(&bad).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown migration operation 'explode'"));
+    }
+}
@@ -81,14 +81,25 @@
 //! - **Type Safety**: Built-in type conversion and validation
 //! - **Async Support**: Non-blocking file operations (with feature flag)
 //! - **Merge Operations**: Combine multiple configurations
+//! - **Migrations**: Versioned upgrades that keep comments intact
+
+mod migration;
+
+pub use migration::{MigrationContext, MigrationStep, Migrations, DEFAULT_VERSION_KEY};
 
 use crate::error::{NomlError, Result};
-use crate::parser::{parse, parse_from_file, Document};
-use crate::schema::Schema;
+use crate::parser::{parse, parse_from_file, AstNode, Document};
+use crate::resolver::{
+    builtin_natives, sections, DotEnv, EnvPrecedence, IncludeCache, Origin, OriginKind, Provenance,
+    Resolver,
+};
+use crate::schema::{Schema, ValidationIssue};
+use crate::serializer::serialize_document;
 use crate::value::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// High-level configuration manager with change tracking and validation
 ///
@@ -141,6 +152,40 @@ pub struct Config {
     source_path: Option<PathBuf>,
     /// Whether the configuration has been modified
     modified: bool,
+    /// Non-fatal findings from schema checks, such as deprecated keys
+    warnings: Vec<ValidationIssue>,
+    /// Where each value came from
+    provenance: Provenance,
+    /// Profile laid over the document, if any
+    profile: Option<String>,
+    /// Profiles the document declares
+    profiles: Vec<String>,
+    /// Sections still to be resolved, when loaded lazily
+    lazy: Option<Box<Lazy>>,
+}
+
+/// The top-level sections of a lazily loaded configuration, each resolved
+/// the first time a key under it is read
+#[derive(Debug, Clone)]
+struct Lazy {
+    /// Environment `env()` reads, if not the process environment
+    env_vars: Option<HashMap<String, String>>,
+    /// Cache of included files the sections are resolved with
+    include_cache: Option<IncludeCache>,
+    /// The document resolved for each section, by top-level key, with its
+    /// origins; one section named `""` holds everything when the keys
+    /// aren't known
+    sections: BTreeMap<String, OnceLock<(Value, Provenance)>>,
+    /// Values for keys the document doesn't set
+    defaults: BTreeMap<String, Value>,
+    /// Origin of the defaults
+    default_origin: Origin,
+    /// Every section, once all of them resolved, for views of the whole
+    /// configuration
+    all: OnceLock<(Value, Provenance)>,
+    /// The sections that resolved, for views that can't report the errors
+    /// of the others
+    partial: OnceLock<(Value, Provenance)>,
 }
 
 /// Builder for creating configurations with specific options
@@ -152,8 +197,26 @@ pub struct ConfigBuilder {
     defaults: BTreeMap<String, Value>,
     /// Whether to validate the configuration
     validate: bool,
+    /// Schema to check the configuration against
+    schema: Option<Schema>,
+    /// Whether deprecated keys are read through their replacements
+    compat_mode: bool,
+    /// Profile to activate, overriding [`PROFILE_ENV`]
+    profile: Option<String>,
+    /// `.env` files whose variables `env()` sees, later ones winning
+    dotenv_files: Vec<PathBuf>,
+    /// Whether `.env` files or the process environment win
+    env_precedence: EnvPrecedence,
+    /// Whether sections are resolved on first access
+    lazy: bool,
+    /// Included files kept between builds
+    include_cache: Option<IncludeCache>,
 }
 
+/// Environment variable choosing the profile of configurations built by
+/// [`ConfigBuilder`] when none is set with [`ConfigBuilder::profile`]
+pub const PROFILE_ENV: &str = "NOML_PROFILE";
+
 impl Config {
     /// Create a new empty configuration
     pub fn new() -> Self {
@@ -171,36 +234,277 @@ impl Config {
             document,
             source_path: None,
             modified: false,
+            warnings: Vec::new(),
+            provenance: Provenance::new(),
+            profile: None,
+            profiles: Vec::new(),
+            lazy: None,
         }
     }
 
     /// Load configuration from a string
     pub fn from_string(content: &str) -> Result<Self> {
-        let document = parse(content)?;
-        let values = document.to_value()?;
+        Self::load(parse(content)?, None, None, None, None)
+    }
+
+    /// Load configuration from a file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Self::load(
+            parse_from_file(path)?,
+            Some(path.to_path_buf()),
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Resolve a parsed document, laying `profile` over it
+    fn load(
+        document: Document,
+        source_path: Option<PathBuf>,
+        profile: Option<String>,
+        env_vars: Option<HashMap<String, String>>,
+        include_cache: Option<IncludeCache>,
+    ) -> Result<Self> {
+        let mut resolver = Self::resolver(
+            source_path.as_deref(),
+            profile.as_deref(),
+            env_vars,
+            include_cache,
+        );
+        let (values, provenance) = resolver.resolve_with_provenance(&document)?;
+        let profiles = resolver.profiles().map(str::to_string).collect();
 
         Ok(Self {
             document,
             values,
-            source_path: None,
+            source_path,
             modified: false,
+            warnings: Vec::new(),
+            provenance,
+            profile,
+            profiles,
+            lazy: None,
         })
     }
 
-    /// Load configuration from a file
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
-        let document = parse_from_file(path)?;
-        let values = document.to_value()?;
+    /// Take a parsed document without resolving it; each top-level section
+    /// is resolved when a key under it is first read
+    fn load_lazy(
+        document: Document,
+        source_path: Option<PathBuf>,
+        profile: Option<String>,
+        env_vars: Option<HashMap<String, String>>,
+        include_cache: Option<IncludeCache>,
+    ) -> Result<Self> {
+        let declared = sections::profiles(&document);
+        let names = sections::names(&document, profile.as_deref());
+        if let (Some(profile), Some(_)) = (&profile, &names) {
+            if !declared.contains(profile) {
+                let declared: Vec<&str> = declared.iter().map(String::as_str).collect();
+                return Err(NomlError::validation(format!(
+                    "Unknown profile '{profile}' (declared: {})",
+                    if declared.is_empty() {
+                        "none".to_string()
+                    } else {
+                        declared.join(", ")
+                    }
+                )));
+            }
+        }
+        let sections = names
+            .unwrap_or_else(|| [String::new()].into())
+            .into_iter()
+            .map(|name| (name, OnceLock::new()))
+            .collect();
 
         Ok(Self {
             document,
-            values,
-            source_path: Some(path.to_path_buf()),
+            values: Value::empty_table(),
+            source_path,
             modified: false,
+            warnings: Vec::new(),
+            provenance: Provenance::new(),
+            profile,
+            profiles: declared.into_iter().collect(),
+            lazy: Some(Box::new(Lazy {
+                env_vars,
+                include_cache,
+                sections,
+                defaults: BTreeMap::new(),
+                default_origin: Origin::detached(OriginKind::Default),
+                all: OnceLock::new(),
+                partial: OnceLock::new(),
+            })),
         })
     }
 
+    /// A resolver for documents read from `source_path`
+    fn resolver(
+        source_path: Option<&Path>,
+        profile: Option<&str>,
+        env_vars: Option<HashMap<String, String>>,
+        include_cache: Option<IncludeCache>,
+    ) -> Resolver {
+        let mut resolver = Resolver::new();
+        if let Some(env_vars) = env_vars {
+            resolver = resolver.with_env_vars(env_vars);
+        }
+        if let Some(cache) = include_cache {
+            resolver = resolver.with_include_cache(cache);
+        }
+        if let Some(path) = source_path {
+            resolver = resolver.with_base_path(path.parent().unwrap_or_else(|| Path::new(".")));
+        }
+        if let Some(profile) = profile {
+            resolver = resolver.with_profile(profile);
+        }
+        resolver
+    }
+
+    /// The resolved section of a lazy configuration and its origins,
+    /// resolving it now if this is the first time it is read
+    fn section<'a>(&self, lazy: &'a Lazy, name: &str) -> Result<Option<&'a (Value, Provenance)>> {
+        let Some(cell) = lazy.sections.get(name) else {
+            return Ok(None);
+        };
+        if let Some(section) = cell.get() {
+            return Ok(Some(section));
+        }
+        let document = if name.is_empty() {
+            self.document.clone()
+        } else {
+            sections::section(&self.document, name, self.profile.as_deref())
+        };
+        let section = Self::resolver(
+            self.source_path.as_deref(),
+            self.profile.as_deref(),
+            lazy.env_vars.clone(),
+            lazy.include_cache.clone(),
+        )
+        .resolve_with_provenance(&document)?;
+        Ok(Some(cell.get_or_init(|| section)))
+    }
+
+    /// The section of a lazy configuration holding `key`
+    fn section_of<'a>(lazy: &Lazy, key: &'a str) -> &'a str {
+        if lazy.sections.contains_key("") {
+            ""
+        } else {
+            key.split(['.', '[']).next().unwrap_or_default()
+        }
+    }
+
+    /// The values of the whole configuration and their origins; a lazy
+    /// one has every section resolved, failing with the first error
+    fn whole(&self) -> Result<(&Value, &Provenance)> {
+        let Some(lazy) = &self.lazy else {
+            return Ok((&self.values, &self.provenance));
+        };
+        if let Some((values, provenance)) = lazy.all.get() {
+            return Ok((values, provenance));
+        }
+        let all = self.gather(lazy, false)?;
+        let (values, provenance) = lazy.all.get_or_init(|| all);
+        Ok((values, provenance))
+    }
+
+    /// The values and origins of every section of a lazy configuration,
+    /// failing with the first error, or with the sections that fail left
+    /// out when `skip_errors` is set
+    fn gather(&self, lazy: &Lazy, skip_errors: bool) -> Result<(Value, Provenance)> {
+        let mut all = Value::empty_table();
+        let mut origins = Provenance::new();
+        for name in lazy.sections.keys() {
+            let section = match self.section(lazy, name) {
+                Ok(section) => section,
+                Err(_) if skip_errors => continue,
+                Err(err) => return Err(NomlError::deferred(name.as_str(), err)),
+            };
+            match section {
+                Some((value, provenance)) if name.is_empty() => {
+                    all = value.clone();
+                    origins = provenance.clone();
+                }
+                Some((value, provenance)) => {
+                    if let Some(value) = value.get(name) {
+                        all.set(name, value.clone())?;
+                    }
+                    let prefix = format!("{name}.");
+                    for (key, origin) in provenance.iter() {
+                        if key == name || key.starts_with(&prefix) {
+                            origins.insert(key, origin.clone());
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+        for (key, value) in &lazy.defaults {
+            if !all.contains_key(key) {
+                all.set(key, value.clone())?;
+                origins.replace(key, lazy.default_origin.clone());
+            }
+        }
+        Ok((all, origins))
+    }
+
+    /// [`Config::whole`] for views that can't report errors; a lazy
+    /// configuration leaves out the sections that fail to resolve
+    fn view(&self) -> (&Value, &Provenance) {
+        let Some(lazy) = &self.lazy else {
+            return (&self.values, &self.provenance);
+        };
+        if let Ok(whole) = self.whole() {
+            return whole;
+        }
+        let (values, provenance) = lazy.partial.get_or_init(|| {
+            self.gather(lazy, true)
+                .unwrap_or_else(|_| (Value::empty_table(), Provenance::new()))
+        });
+        (values, provenance)
+    }
+
+    fn values(&self) -> &Value {
+        self.view().0
+    }
+
+    /// Resolve every section of a lazily loaded configuration now, so it
+    /// behaves like one loaded eagerly; fails with the first error
+    ///
+    /// Changing a lazy configuration does this first.
+    pub fn resolve_all(&mut self) -> Result<()> {
+        let Some(lazy) = self.lazy.take() else {
+            return Ok(());
+        };
+        let loaded = Self::load(
+            self.document.clone(),
+            self.source_path.clone(),
+            self.profile.clone(),
+            lazy.env_vars.clone(),
+            lazy.include_cache.clone(),
+        );
+        let loaded = match loaded {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.lazy = Some(lazy);
+                return Err(err);
+            }
+        };
+        self.values = loaded.values;
+        self.provenance = loaded.provenance;
+        self.profiles = loaded.profiles;
+        for (key, value) in lazy.defaults {
+            if !self.values.contains_key(&key) {
+                self.values.set(&key, value)?;
+                self.provenance
+                    .replace(&key, Origin::detached(OriginKind::Default));
+            }
+        }
+        Ok(())
+    }
+
     /// Create a configuration builder
     pub fn builder() -> ConfigBuilder {
         ConfigBuilder::default()
@@ -210,6 +514,10 @@ impl Config {
     ///
     /// Returns `None` if the key doesn't exist.
     ///
+    /// On a [lazy](ConfigBuilder::lazy) configuration, a key whose section
+    /// fails to resolve reads as missing; [`Config::try_get`] returns the
+    /// error instead.
+    ///
     /// # Example
     /// ```rust
     /// # use noml::{Config, Value};
@@ -228,7 +536,28 @@ impl Config {
     /// ```
     #[inline]
     pub fn get(&self, key: &str) -> Option<&Value> {
-        self.values.get(key)
+        match &self.lazy {
+            Some(_) => self.try_get(key).ok().flatten(),
+            None => self.values.get(key),
+        }
+    }
+
+    /// Get a value by key path, reporting why it couldn't be resolved
+    ///
+    /// For a configuration built with [`ConfigBuilder::lazy`], the
+    /// section holding the key is resolved the first time it is read, and
+    /// any error doing so comes back here as [`NomlError::Deferred`] naming
+    /// the key. Otherwise this is [`Config::get`].
+    pub fn try_get(&self, key: &str) -> Result<Option<&Value>> {
+        let Some(lazy) = &self.lazy else {
+            return Ok(self.values.get(key));
+        };
+        let section = self
+            .section(lazy, Self::section_of(lazy, key))
+            .map_err(|err| NomlError::deferred(key, err))?;
+        Ok(section
+            .and_then(|(section, _)| section.get(key))
+            .or_else(|| lazy.defaults.get(key)))
     }
 
     /// Get a value by key path with a default
@@ -268,6 +597,7 @@ impl Config {
     where
         T: Into<Value>,
     {
+        self.resolve_all()?;
         if !self.values.contains_key(key) {
             self.set(key, default.into())?;
         }
@@ -298,15 +628,24 @@ impl Config {
     where
         T: Into<Value>,
     {
-        self.values.set(key, value.into())?;
+        self.resolve_all()?;
+        let value = value.into();
+        self.document
+            .set_native_value(key, &value, &builtin_natives())?;
+        self.values.set(key, value)?;
+        self.provenance
+            .replace(key, Origin::detached(OriginKind::Assigned));
         self.modified = true;
         Ok(())
     }
 
     /// Remove a value by key path
     pub fn remove(&mut self, key: &str) -> Result<Option<Value>> {
+        self.resolve_all()?;
         let result = self.values.remove(key)?;
         if result.is_some() {
+            self.document.remove_path(key);
+            self.provenance.remove(key);
             self.modified = true;
         }
         Ok(result)
@@ -314,12 +653,12 @@ impl Config {
 
     /// Check if a key exists
     pub fn contains_key(&self, key: &str) -> bool {
-        self.values.contains_key(key)
+        self.get(key).is_some()
     }
 
     /// Get all keys at the root level
     pub fn keys(&self) -> Vec<String> {
-        self.values.keys()
+        self.values().keys()
     }
 
     /// Check if the configuration has been modified
@@ -337,6 +676,29 @@ impl Config {
         self.source_path.as_deref()
     }
 
+    /// The profile whose `[@profile.<name>]` sections were laid over the
+    /// document, if one was chosen
+    ///
+    /// # Example
+    /// ```rust
+    /// # use noml::Config;
+    /// let config = Config::builder()
+    ///     .profile("production")
+    ///     .build_from_string("port = 8080\n[@profile.production]\nport = 80")?;
+    ///
+    /// assert_eq!(config.active_profile(), Some("production"));
+    /// assert_eq!(config.get("port").unwrap().as_integer()?, 80);
+    /// # Ok::<(), noml::NomlError>(())
+    /// ```
+    pub fn active_profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Names of the profiles the document declares, in sorted order
+    pub fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
     /// Save the configuration to its source file
     ///
     /// Only works if the configuration was loaded from a file.
@@ -361,6 +723,9 @@ impl Config {
 
     /// Save the configuration to a specific file
     ///
+    /// The original document is written back with its comments and layout
+    /// intact; only entries changed through this `Config` are rewritten.
+    ///
     /// # Example
     /// ```rust,no_run
     /// # use noml::Config;
@@ -369,9 +734,7 @@ impl Config {
     /// # Ok::<(), noml::NomlError>(())
     /// ```
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        // TODO: Implement proper NOML serialization
-        // For now, we'll create a basic representation
-        let content = self.to_string_representation();
+        let content = self.to_noml_string()?;
         fs::write(path, content)
             .map_err(|e| NomlError::io("Failed to write configuration file".to_string(), e))?;
         Ok(())
@@ -379,12 +742,15 @@ impl Config {
 
     /// Get the underlying Value
     pub fn as_value(&self) -> &Value {
-        &self.values
+        self.values()
     }
 
     /// Convert to owned Value
     pub fn into_value(self) -> Value {
-        self.values
+        match &self.lazy {
+            Some(_) => self.values().clone(),
+            None => self.values,
+        }
     }
 
     /// Validate configuration against a schema
@@ -409,7 +775,85 @@ impl Config {
     /// # Ok::<(), noml::NomlError>(())
     /// ```
     pub fn validate_schema(&self, schema: &Schema) -> Result<()> {
-        schema.validate(&self.values)
+        schema.validate(self.whole()?.0)
+    }
+
+    /// Validate against a schema, keeping its warnings
+    ///
+    /// Warnings such as deprecated keys are added to [`Config::warnings`];
+    /// only errors make this fail.
+    ///
+    /// # Example
+    /// ```rust
+    /// use noml::{Config, SchemaBuilder};
+    ///
+    /// let mut config = Config::from_string("db_host = \"localhost\"")?;
+    /// let schema = SchemaBuilder::new()
+    ///     .optional_string("database.host")
+    ///     .deprecate("db_host", "moved into [database]", Some("database.host"))
+    ///     .build();
+    ///
+    /// config.check_schema(&schema)?;
+    /// assert_eq!(config.warnings().len(), 1);
+    /// # Ok::<(), noml::NomlError>(())
+    /// ```
+    pub fn check_schema(&mut self, schema: &Schema) -> Result<()> {
+        self.resolve_all()?;
+        let mut report = schema.validate_all(&self.values);
+        self.warnings.append(&mut report.warnings);
+        report.into_result()
+    }
+
+    /// Read deprecated keys through their replacements
+    ///
+    /// When a deprecated key with a replacement is set and the replacement is
+    /// not, the value is made available under the replacement path as well.
+    /// Only the in-memory values change; saving keeps the file as written.
+    pub fn apply_compat(&mut self, schema: &Schema) {
+        if self.resolve_all().is_err() {
+            return;
+        }
+        schema.apply_compat(&mut self.values);
+    }
+
+    /// Warnings collected while loading or checking this configuration
+    pub fn warnings(&self) -> &[ValidationIssue] {
+        &self.warnings
+    }
+
+    /// Where the value at `key` came from: its file, line and column, the
+    /// includes that led there, and whether it was a literal, an `env()`
+    /// lookup, an interpolation or a default
+    ///
+    /// # Example
+    /// ```rust
+    /// # use noml::{Config, OriginKind};
+    /// let config = Config::from_string("[server]\nhost = env(\"NOML_DOC_HOST\", \"0.0.0.0\")")?;
+    ///
+    /// let origin = config.origin("server.host").unwrap();
+    /// assert_eq!(origin.span.unwrap().start_line, 2);
+    /// assert!(matches!(&origin.kind, OriginKind::Env { var, found: false } if var == "NOML_DOC_HOST"));
+    /// # Ok::<(), noml::NomlError>(())
+    /// ```
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        // Provenance answers for a key with its nearest parent's origin
+        self.get(key)?;
+        let Some(lazy) = &self.lazy else {
+            return self.provenance.get(key);
+        };
+        let section = lazy
+            .sections
+            .get(Self::section_of(lazy, key))
+            .and_then(OnceLock::get);
+        match section {
+            Some((value, provenance)) if value.get(key).is_some() => provenance.get(key),
+            _ => Some(&lazy.default_origin),
+        }
+    }
+
+    /// Origins of every value, by key path
+    pub fn provenance(&self) -> &Provenance {
+        self.view().1
     }
 
     /// Get the underlying Document
@@ -417,23 +861,42 @@ impl Config {
         &self.document
     }
 
+    /// Render the configuration as NOML source, preserving comments and
+    /// formatting from the original document
+    pub fn to_noml_string(&self) -> Result<String> {
+        serialize_document(&self.document)
+    }
+
     /// Merge another configuration into this one
     ///
     /// Values from the other configuration will overwrite values in this one.
     pub fn merge(&mut self, other: &Config) -> Result<()> {
-        self.merge_value(&other.values)?;
+        self.resolve_all()?;
+        let (values, provenance) = other.whole()?;
+        let mut document = self.document.clone();
+        Self::merge_document(&mut document, "", values, &other.document)?;
+        self.merge_value(values)?;
+        self.document = document;
+        for (key, origin) in provenance.iter() {
+            if values.get(key).is_some_and(Value::is_table) {
+                self.provenance.insert(key, origin.clone());
+            } else {
+                self.provenance.replace(key, origin.clone());
+            }
+        }
         self.modified = true;
         Ok(())
     }
 
     /// Get configuration statistics
     pub fn stats(&self) -> ConfigStats {
+        let values = self.values();
         ConfigStats {
-            key_count: self.count_keys(&self.values),
-            depth: self.max_depth(&self.values, 0),
+            key_count: self.count_keys(values),
+            depth: self.max_depth(values, 0),
             comment_count: self.document.all_comments().len(),
-            has_arrays: self.has_arrays(&self.values),
-            has_nested_tables: self.has_nested_tables(&self.values),
+            has_arrays: self.has_arrays(values),
+            has_nested_tables: self.has_nested_tables(values),
         }
     }
 
@@ -462,6 +925,43 @@ impl Config {
         }
     }
 
+    /// Mirror a merge into the document by setting every leaf of `source`
+    ///
+    /// Values holding secrets can't be written from the value, so their
+    /// source node is copied from the document they were merged from.
+    fn merge_document(
+        document: &mut Document,
+        prefix: &str,
+        source: &Value,
+        source_document: &Document,
+    ) -> Result<()> {
+        match source {
+            Value::Table(table) if prefix.is_empty() || !table.is_empty() => {
+                for (key, value) in table {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    Self::merge_document(document, &path, value, source_document)?;
+                }
+                Ok(())
+            }
+            _ => match AstNode::from_value(source) {
+                Ok(node) => document.set_node(prefix, node),
+                Err(_) => match source_document.get_node(prefix) {
+                    Some(node) => document.set_node(prefix, node.clone()),
+                    None => Err(NomlError::validation_at(
+                        format!(
+                            "Cannot merge secret '{prefix}': it is not written in the merged file"
+                        ),
+                        prefix,
+                    )),
+                },
+            },
+        }
+    }
+
     /// Helper method to merge tables directly without creating temporary Config objects
     fn merge_tables(
         target: &mut BTreeMap<String, Value>,
@@ -537,81 +1037,6 @@ impl Config {
             _ => false,
         }
     }
-
-    fn to_string_representation(&self) -> String {
-        // Basic TOML-style output (will be improved in future iterations)
-        self.value_to_string(&self.values, 0, "")
-    }
-
-    fn value_to_string(&self, value: &Value, indent: usize, prefix: &str) -> String {
-        let indent_str = "  ".repeat(indent);
-
-        match value {
-            Value::Table(table) => {
-                let mut result = String::new();
-
-                // First output direct key-value pairs
-                for (key, val) in table {
-                    if !val.is_table() {
-                        result.push_str(&format!(
-                            "{}{} = {}\n",
-                            indent_str,
-                            key,
-                            self.value_to_literal_string(val)
-                        ));
-                    }
-                }
-
-                // Then output nested tables
-                for (key, val) in table {
-                    if val.is_table() {
-                        let full_key = if prefix.is_empty() {
-                            key.clone()
-                        } else {
-                            format!("{prefix}.{key}")
-                        };
-
-                        result.push('\n');
-                        result.push_str(&format!("{indent_str}[{full_key}]\n"));
-                        result.push_str(&self.value_to_string(val, indent, &full_key));
-                    }
-                }
-
-                result
-            }
-            _ => self.value_to_literal_string(value),
-        }
-    }
-
-    #[allow(clippy::only_used_in_recursion)]
-    fn value_to_literal_string(&self, value: &Value) -> String {
-        match value {
-            Value::Null => "null".to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::Integer(i) => i.to_string(),
-            Value::Float(f) => f.to_string(),
-            Value::String(s) => format!("\"{}\"", s.replace('"', "\\\"")),
-            Value::Array(arr) => {
-                let elements: Vec<String> = arr
-                    .iter()
-                    .map(|v| self.value_to_literal_string(v))
-                    .collect();
-                format!("[{}]", elements.join(", "))
-            }
-            Value::Table(table) => {
-                let entries: Vec<String> = table
-                    .iter()
-                    .map(|(k, v)| format!("{} = {}", k, self.value_to_literal_string(v)))
-                    .collect();
-                format!("{{ {} }}", entries.join(", "))
-            }
-            Value::Size(bytes) => format!("{bytes}B"),
-            Value::Duration(secs) => format!("{secs}s"),
-            Value::Binary(data) => format!("<{} bytes>", data.len()),
-            #[cfg(feature = "chrono")]
-            Value::DateTime(dt) => format!("\"{}\"", dt.format("%Y-%m-%dT%H:%M:%SZ")),
-        }
-    }
 }
 
 // Async methods (available with "async" feature)
@@ -641,13 +1066,19 @@ impl Config {
             Some(path.as_ref().to_string_lossy().to_string()),
         )?;
         let mut resolver = crate::resolver::Resolver::new();
-        let values = resolver.resolve(&document)?;
+        let (values, provenance) = resolver.resolve_with_provenance(&document)?;
+        let profiles = resolver.profiles().map(str::to_string).collect();
 
         Ok(Config {
             document,
             values,
             source_path: Some(path.as_ref().to_path_buf()),
             modified: false,
+            warnings: Vec::new(),
+            provenance,
+            profile: None,
+            profiles,
+            lazy: None,
         })
     }
 
@@ -667,7 +1098,7 @@ impl Config {
     /// }
     /// ```
     pub async fn save_async<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let content = self.to_string_representation();
+        let content = self.to_noml_string()?;
         tokio::fs::write(path.as_ref(), content)
             .await
             .map_err(|e| NomlError::io(path.as_ref().to_string_lossy().to_string(), e))?;
@@ -703,6 +1134,8 @@ impl Config {
                 let reloaded = Self::load_async(path).await?;
                 self.document = reloaded.document;
                 self.values = reloaded.values;
+                self.provenance = reloaded.provenance;
+                self.profiles = reloaded.profiles;
                 self.modified = false;
                 Ok(())
             }
@@ -751,66 +1184,227 @@ impl ConfigBuilder {
     }
 
     /// Enable or disable validation
+    ///
+    /// With a [`schema`](ConfigBuilder::schema), validation errors make the
+    /// build fail; without validation only the schema warnings are collected.
     pub fn validate(mut self, validate: bool) -> Self {
         self.validate = validate;
         self
     }
 
-    /// Build the configuration from a file
-    pub fn build_from_file<P: AsRef<Path>>(self, path: P) -> Result<Config> {
-        let path = path.as_ref();
+    /// Check the configuration against a schema when it is built
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
 
-        let mut config = if path.exists() {
-            Config::from_file(path)?
-        } else if self.allow_missing {
-            Config::new()
+    /// Read deprecated keys through their replacements (see [`Config::apply_compat`])
+    pub fn compat_mode(mut self, compat: bool) -> Self {
+        self.compat_mode = compat;
+        self
+    }
+
+    /// Lay the `[@profile.<name>]` sections of `profile` over the document
+    ///
+    /// Without this, the profile is read from the [`PROFILE_ENV`]
+    /// environment variable. Choosing a profile the document does not
+    /// declare fails the build. With a [`schema`](ConfigBuilder::schema) and
+    /// validation on, every declared profile is checked, not just this one.
+    pub fn profile<S: Into<String>>(mut self, profile: S) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Read variables for `env()` from a `.env` file, if it exists
+    ///
+    /// Files added later override earlier ones, so `.env.local` can follow
+    /// `.env`. Which of them and the process environment wins is set by
+    /// [`ConfigBuilder::env_precedence`]. A missing file is skipped, so the
+    /// same code runs where the environment is set another way.
+    pub fn dotenv<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.dotenv_files.push(path.into());
+        self
+    }
+
+    /// Choose whether `.env` files or the process environment win when both
+    /// set a variable; the process environment does by default
+    pub fn env_precedence(mut self, precedence: EnvPrecedence) -> Self {
+        self.env_precedence = precedence;
+        self
+    }
+
+    /// Resolve each top-level section the first time a key under it is read
+    ///
+    /// An `env()` or `include` in a section nothing reads is never
+    /// evaluated, so tools sharing one file only need what their own
+    /// sections use. Errors come back from [`Config::try_get`] with the
+    /// key that was read. A document that `extends` another is resolved
+    /// whole on first access, and a [`schema`](ConfigBuilder::schema)
+    /// resolves everything when the configuration is built.
+    ///
+    /// Views of the whole configuration resolve every section:
+    /// [`Config::validate_schema`] and [`Config::merge`] fail with the
+    /// first error, while [`Config::keys`], [`Config::as_value`] and the
+    /// other views that can't return one leave out the sections that fail,
+    /// just as [`Config::get`] reads their keys as missing. Call
+    /// [`Config::resolve_all`] first to handle errors.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Keep parsed included files in `cache` between builds
+    ///
+    /// Configurations rebuilt with clones of the same cache, such as on
+    /// every reload, only read again the included files that changed.
+    pub fn include_cache(mut self, cache: IncludeCache) -> Self {
+        self.include_cache = Some(cache);
+        self
+    }
+
+    /// Load a parsed document, lazily if asked to
+    fn load(&self, document: Document, source_path: Option<PathBuf>) -> Result<Config> {
+        let load = if self.lazy {
+            Config::load_lazy
         } else {
-            return Err(NomlError::io(
-                path.to_string_lossy().to_string(),
-                std::io::Error::new(std::io::ErrorKind::NotFound, "Configuration file not found"),
-            ));
+            Config::load
         };
+        load(
+            document,
+            source_path,
+            self.active_profile(),
+            self.env_vars()?,
+            self.include_cache.clone(),
+        )
+    }
 
-        // Apply defaults for missing keys
-        for (key, value) in self.defaults {
-            if !config.contains_key(&key) {
-                config.set(&key, value)?;
+    /// The environment `env()` reads, if `.env` files change it
+    fn env_vars(&self) -> Result<Option<HashMap<String, String>>> {
+        if self.dotenv_files.is_empty() {
+            return Ok(None);
+        }
+        let mut dotenv = DotEnv::new().with_process_env(true);
+        for path in self.dotenv_files.iter().filter(|path| path.exists()) {
+            dotenv.load_file(path)?;
+        }
+        let mut env_vars = std::env::vars().collect();
+        dotenv.overlay(&mut env_vars, self.env_precedence);
+        Ok(Some(env_vars))
+    }
+
+    /// The profile chosen with [`ConfigBuilder::profile`] or [`PROFILE_ENV`]
+    fn active_profile(&self) -> Option<String> {
+        self.profile.clone().or_else(|| {
+            std::env::var(PROFILE_ENV)
+                .ok()
+                .filter(|profile| !profile.is_empty())
+        })
+    }
+
+    /// Apply the defaults and compatibility mode to resolved values
+    fn prepare(&self, values: &mut Value) -> Result<()> {
+        for (key, value) in &self.defaults {
+            if !values.contains_key(key) {
+                values.set(key, value.clone())?;
             }
         }
+        if let (Some(schema), true) = (&self.schema, self.compat_mode) {
+            schema.apply_compat(values);
+        }
+        Ok(())
+    }
 
-        if self.validate {
-            // TODO: Add schema validation to builder tests in future
-            // config.validate_schema(&schema)?;
+    /// Apply defaults and the schema to a freshly loaded configuration
+    fn finish(self, mut config: Config) -> Result<Config> {
+        if let (Some(lazy), None) = (&mut config.lazy, &self.schema) {
+            lazy.defaults = self.defaults;
+            return Ok(config);
+        }
+        // A schema checks every value, so a lazy configuration is resolved
+        config.resolve_all()?;
+
+        // Apply defaults for missing keys, leaving them out of the document
+        // so that saving doesn't write them to the file
+        for (key, value) in &self.defaults {
+            if !config.values.contains_key(key) {
+                config.values.set(key, value.clone())?;
+                config
+                    .provenance
+                    .replace(key, Origin::detached(OriginKind::Default));
+            }
+        }
+
+        if let Some(schema) = &self.schema {
+            if self.compat_mode {
+                config.apply_compat(schema);
+            }
+            if self.validate {
+                config.check_schema(schema)?;
+                self.check_profiles(&config, schema)?;
+            } else {
+                let report = schema.validate_all(&config.values);
+                config.warnings.extend(report.warnings);
+            }
         }
 
         config.mark_clean(); // Don't consider defaults as modifications
         Ok(config)
     }
 
-    /// Build the configuration from a string
-    pub fn build_from_string(self, content: &str) -> Result<Config> {
-        let mut config = Config::from_string(content)?;
-
-        // Apply defaults for missing keys
-        for (key, value) in self.defaults {
-            if !config.contains_key(&key) {
-                config.set(&key, value)?;
+    /// Validate the document under every profile it declares besides the
+    /// active one
+    fn check_profiles(&self, config: &Config, schema: &Schema) -> Result<()> {
+        for profile in &config.profiles {
+            if config.active_profile() == Some(profile.as_str()) {
+                continue;
+            }
+            let mut values = Config::load(
+                config.document.clone(),
+                config.source_path.clone(),
+                Some(profile.clone()),
+                self.env_vars()?,
+                self.include_cache.clone(),
+            )?
+            .values;
+            self.prepare(&mut values)?;
+            let mut report = schema.validate_all(&values);
+            for issue in &mut report.errors {
+                issue.message = format!("in profile '{profile}': {}", issue.message);
             }
+            report.into_result()?;
         }
+        Ok(())
+    }
 
-        if self.validate {
-            // TODO: Add schema validation tests in future
-            // config.validate_schema(&schema)?;
-        }
+    /// Build the configuration from a file
+    pub fn build_from_file<P: AsRef<Path>>(self, path: P) -> Result<Config> {
+        let path = path.as_ref();
 
-        config.mark_clean();
-        Ok(config)
+        let config = if path.exists() {
+            self.load(parse_from_file(path)?, Some(path.to_path_buf()))?
+        } else if self.allow_missing {
+            Config::new()
+        } else {
+            return Err(NomlError::io(
+                path.to_string_lossy().to_string(),
+                std::io::Error::new(std::io::ErrorKind::NotFound, "Configuration file not found"),
+            ));
+        };
+
+        self.finish(config)
+    }
+
+    /// Build the configuration from a string
+    pub fn build_from_string(self, content: &str) -> Result<Config> {
+        let config = self.load(parse(content)?, None)?;
+        self.finish(config)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::FieldType;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -872,6 +1466,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn config_set_keeps_native_types() {
+        let mut config = Config::from_string(
+            "limit = @size(\"1MB\")\napi = @url(\"https://a.example\")\ntoken = @secret(\"x\")",
+        )
+        .unwrap();
+
+        config.set("limit", Value::Size(3 * 1024 * 1024)).unwrap();
+        config.set("api", "https://b.example").unwrap();
+        let saved = config.to_noml_string().unwrap();
+        assert!(saved.contains("limit = @size(\"3MB\")"), "{saved}");
+        assert!(
+            saved.contains("api = @url(\"https://b.example\")"),
+            "{saved}"
+        );
+
+        // The native type checks the value before it is written
+        assert!(config.set("api", "not a url").is_err());
+        assert_eq!(
+            config.get("api").unwrap().as_string().unwrap(),
+            "https://b.example"
+        );
+
+        // A plain string doesn't become a secret
+        config.set("token", "plain").unwrap();
+        let saved = config.to_noml_string().unwrap();
+        assert!(saved.contains("token = \"plain\""), "{saved}");
+    }
+
     #[test]
     fn config_removal() {
         let mut config = Config::from_string(
@@ -1016,6 +1639,98 @@ mod tests {
         assert_eq!(config.get("version").unwrap().as_string().unwrap(), "1.0");
 
         assert!(!config.is_modified()); // Defaults don't count as modifications
+
+        // ...and aren't written to the file
+        let saved = config.to_noml_string().unwrap();
+        assert!(!saved.contains("default_app") && !saved.contains("debug"));
+    }
+
+    #[test]
+    fn config_origins() {
+        let mut config = Config::builder()
+            .default_value("debug", false)
+            .build_from_string("name = \"app\"\n\n[server]\nport = 8080")
+            .unwrap();
+
+        let port = config.origin("server.port").unwrap();
+        assert_eq!(port.kind, OriginKind::Literal);
+        assert_eq!(port.to_string(), "line 4, column 8 (literal)");
+        assert_eq!(config.origin("debug").unwrap().kind, OriginKind::Default);
+        assert!(config.origin("missing").is_none());
+        assert!(config.origin("server.typo").is_none());
+
+        config.set("server.port", 9090).unwrap();
+        assert_eq!(
+            config.origin("server.port").unwrap().kind,
+            OriginKind::Assigned
+        );
+
+        let other = Config::from_string("\nname = \"other\"").unwrap();
+        config.merge(&other).unwrap();
+        assert_eq!(config.origin("name").unwrap().span.unwrap().start_line, 2);
+
+        config.remove("server").unwrap();
+        assert!(config.origin("server.port").is_none());
+    }
+
+    #[test]
+    fn config_builder_schema_warnings() {
+        let server = Schema::new().required_field("port", FieldType::Integer);
+        let schema = Schema::new()
+            .required_field("server", FieldType::Table(server))
+            .deprecate("port", "", Some("server.port"));
+
+        let config = Config::builder()
+            .schema(schema.clone())
+            .compat_mode(true)
+            .validate(true)
+            .build_from_string("port = 8080")
+            .unwrap();
+        assert_eq!(
+            config.get("server.port").unwrap().as_integer().unwrap(),
+            8080
+        );
+        assert_eq!(config.warnings().len(), 1);
+        assert_eq!(config.warnings()[0].path, "port");
+
+        // Without compatibility mode the new key is missing
+        let err = Config::builder()
+            .schema(schema)
+            .validate(true)
+            .build_from_string("port = 8080")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Required field 'server' is missing"));
+    }
+
+    #[test]
+    fn config_builder_profiles() {
+        let source =
+            "port = 8080\n[@profile.production]\nport = 80\n[@profile.staging]\nport = \"eighty\"";
+        let config = Config::builder()
+            .profile("production")
+            .build_from_string(source)
+            .unwrap();
+        assert_eq!(config.active_profile(), Some("production"));
+        assert_eq!(config.profiles(), ["production", "staging"]);
+        assert_eq!(config.get("port").unwrap().as_integer().unwrap(), 80);
+        assert!(config.get("@profile").is_none());
+
+        // Every profile is validated, not only the active one
+        let schema = Schema::new().required_field("port", FieldType::Integer);
+        let err = Config::builder()
+            .profile("production")
+            .schema(schema)
+            .validate(true)
+            .build_from_string(source)
+            .unwrap_err();
+        assert!(err.to_string().contains("in profile 'staging'"));
+
+        assert!(Config::builder()
+            .profile("qa")
+            .build_from_string(source)
+            .is_err());
     }
 
     #[test]
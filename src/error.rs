@@ -69,7 +69,7 @@ pub enum NomlError {
     },
 
     /// Variable interpolation errors
-    #[error("Interpolation error: {message}")]
+    #[error("Interpolation error{}: {message}", describe_location(.context, .location))]
     Interpolation {
         /// Description of the interpolation failure
         message: String,
@@ -77,6 +77,8 @@ pub enum NomlError {
         expression: String,
         /// Path in the document where this occurred
         context: Option<String>,
+        /// Line and column (1-indexed) of the value containing the reference
+        location: Option<(usize, usize)>,
     },
 
     /// Environment variable errors
@@ -117,6 +119,26 @@ pub enum NomlError {
         chain: String,
     },
 
+    /// Security policy violations - when a document reaches for a file,
+    /// URL or environment variable its policy forbids
+    #[error("Security policy violation: {message}")]
+    Security {
+        /// What was refused and why
+        message: String,
+        /// The include path, URL or variable name that was refused
+        subject: String,
+    },
+
+    /// Errors resolving a key of a lazily loaded configuration, reported
+    /// when the key is first read
+    #[error("Failed to resolve '{key}': {source}")]
+    Deferred {
+        /// The key that was read
+        key: String,
+        /// Why its section could not be resolved
+        source: Box<NomlError>,
+    },
+
     /// Internal errors - these should never happen in normal operation
     #[error("Internal error: {message}")]
     Internal {
@@ -212,7 +234,25 @@ impl NomlError {
             message: message.into(),
             expression: expression.into(),
             context: None,
+            location: None,
+        }
+    }
+
+    /// Attach the key and source position an interpolation error occurred
+    /// at, unless it already has them. Other errors are returned unchanged.
+    pub fn at_key(mut self, key: impl Into<String>, line: usize, column: usize) -> Self {
+        if let Self::Interpolation {
+            context, location, ..
+        } = &mut self
+        {
+            if context.is_none() {
+                *context = Some(key.into());
+            }
+            if location.is_none() {
+                *location = Some((line, column));
+            }
         }
+        self
     }
 
     /// Create an environment variable error
@@ -248,6 +288,23 @@ impl NomlError {
         }
     }
 
+    /// Create a security policy violation error
+    pub fn security(message: impl Into<String>, subject: impl Into<String>) -> Self {
+        Self::Security {
+            message: message.into(),
+            subject: subject.into(),
+        }
+    }
+
+    /// Create an error for a key whose section failed to resolve on first
+    /// access
+    pub fn deferred(key: impl Into<String>, source: NomlError) -> Self {
+        Self::Deferred {
+            key: key.into(),
+            source: Box::new(source),
+        }
+    }
+
     /// Create an internal error (should be used sparingly)
     pub fn internal(message: impl Into<String>) -> Self {
         Self::Internal {
@@ -369,6 +426,10 @@ impl NomlError {
             NomlError::Schema { .. } => true,
             // Circular references are not recoverable
             NomlError::CircularReference { .. } => false,
+            // Policy violations must not be worked around
+            NomlError::Security { .. } => false,
+            // As recoverable as what went wrong
+            NomlError::Deferred { source, .. } => source.is_recoverable(),
             // Internal errors are not recoverable
             NomlError::Internal { .. } => false,
         }
@@ -387,6 +448,8 @@ impl NomlError {
             NomlError::Import { .. } => "import",
             NomlError::Schema { .. } => "schema",
             NomlError::CircularReference { .. } => "circular_reference",
+            NomlError::Security { .. } => "security",
+            NomlError::Deferred { source, .. } => source.category(),
             NomlError::Internal { .. } => "internal",
         }
     }
@@ -424,11 +487,32 @@ impl NomlError {
                 }
                 msg
             }
+            NomlError::Security { message, .. } if message.contains("sha256") => format!(
+                "{self}\n\nTip: The contents differ from what was pinned; check why they changed, then update the `sha256` pin or run `noml lock --update`."
+            ),
+            NomlError::Security { .. } => format!(
+                "{self}\n\nTip: This document is resolved under a restricted security policy; move the file inside an allowed directory or ask for the policy to be relaxed."
+            ),
+            NomlError::Deferred { key, source } => {
+                format!("While resolving '{key}': {}", source.user_message())
+            }
             _ => self.to_string(),
         }
     }
 }
 
+/// Format where an interpolation error occurred, e.g. ` in 'db.url' at line 3, column 7`
+fn describe_location(context: &Option<String>, location: &Option<(usize, usize)>) -> String {
+    let mut text = String::new();
+    if let Some(key) = context {
+        text.push_str(&format!(" in '{key}'"));
+    }
+    if let Some((line, column)) = location {
+        text.push_str(&format!(" at line {line}, column {column}"));
+    }
+    text
+}
+
 /// Convert from std::io::Error to NomlError
 impl From<io::Error> for NomlError {
     fn from(error: io::Error) -> Self {
@@ -461,6 +545,10 @@ mod tests {
         let key_err = NomlError::key_not_found("test.key");
         assert_eq!(key_err.category(), "key_access");
         assert!(key_err.is_recoverable());
+
+        let security_err = NomlError::security("remote include is not allowed", "https://x");
+        assert_eq!(security_err.category(), "security");
+        assert!(!security_err.is_recoverable());
     }
 
     #[test]
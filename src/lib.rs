@@ -195,17 +195,32 @@ pub mod serializer;
 pub mod value;
 
 // Re-export main types for convenience
-pub use config::Config;
+pub use config::{Config, MigrationContext, MigrationStep, Migrations, PROFILE_ENV};
 pub use error::{NomlError, Result};
 pub use parser::ast::AstNode;
 pub use parser::{parse_file, parse_string, Document};
-pub use resolver::{NativeResolver, Resolver, ResolverConfig};
+#[cfg(feature = "async")]
+pub use resolver::HttpCache;
+#[cfg(feature = "encryption")]
+pub use resolver::{
+    decrypt_paths, encrypt_paths, EncryptionKey, EnvKey, KeyFile, KeyProvider, KEY_ENV,
+};
+pub use resolver::{
+    DotEnv, EmbeddedLoader, EnvAccess, EnvPrecedence, EnvSecretProvider, FileSecretProvider,
+    FsLoader, FunctionRegistry, InMemoryLoader, InMemorySecretProvider, IncludeCache,
+    IncludeLoader, Lockfile, Manifest, MergeStrategy, NativeResolver, NativeType, Origin,
+    OriginKind, Provenance, ResolveContext, Resolver, ResolverConfig, SecretProvider,
+    SecurityPolicy, LOCKFILE,
+};
 pub use serializer::{serialize_document, serialize_document_with_options, Serializer};
-pub use value::Value;
+pub use value::{Secret, Value};
 
 // Re-export macros (exported at crate root via #[macro_export])
 // pub use macros::noml_value; // Not needed - macro_export puts it at crate root
-pub use schema::{FieldSchema, FieldType, Schema, SchemaBuilder};
+pub use schema::{
+    CompareOp, Deprecation, DocFormat, FieldSchema, FieldType, Rule, Schema, SchemaBuilder,
+    ValidationIssue, ValidationReport,
+};
 
 use std::path::Path;
 
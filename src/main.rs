@@ -5,6 +5,7 @@
 
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 
 fn main() {
@@ -16,12 +17,56 @@ fn main() {
         eprintln!();
         eprintln!("Commands:");
         eprintln!("  validate <file>    Validate NOML syntax");
-        eprintln!("  parse <file>       Parse and display structure");
+        eprintln!("  parse <file> [--profile NAME]");
+        eprintln!("                     Parse and display structure");
+        eprintln!("  schema-doc <schema> [--format markdown|html|noml]");
+        eprintln!("                     Generate reference docs or a template from a schema");
+        eprintln!("  migrate <file> --migrations <file> [--to N] [--dry-run]");
+        eprintln!("                     Upgrade a config to a newer version, keeping comments");
+        eprintln!("  explain <file> <key> [--profile NAME]");
+        eprintln!("                     Show a key's value and where it came from");
+        eprintln!(
+            "  check <file> [--hermetic] [--root DIR] [--env-file PATH] [--env NAME=VALUE]..."
+        );
+        eprintln!("        [--offline] [--lockfile PATH]");
+        eprintln!("                     Resolve and list the variables and files it read");
+        eprintln!("  lock <file> [--lockfile PATH] [--update]");
+        eprintln!("                     Pin remote includes in noml.lock with vendored copies");
+        eprintln!("  env <file> [--keys KEY,...] [--prefix P] [--export] [--secrets]");
+        eprintln!("                     Write config values as a .env file");
+        eprintln!("  keygen             Generate a key for encrypted values");
+        eprintln!("  encrypt <file> --keys KEY,... [--key-file PATH]");
+        eprintln!("                     Encrypt the values of keys in place, keeping comments");
+        eprintln!("  decrypt <file> [--keys KEY,...] [--key-file PATH]");
+        eprintln!("                     Decrypt the values of keys, or all of them, in place");
         eprintln!("  version            Show version information");
         eprintln!();
+        eprintln!("The profile defaults to the NOML_PROFILE environment variable.");
+        eprintln!("The encryption key defaults to the NOML_ENCRYPTION_KEY environment variable.");
+        eprintln!();
         eprintln!("Examples:");
         eprintln!("  {} validate config.noml", args[0]);
         eprintln!("  {} parse app.noml", args[0]);
+        eprintln!("  {} schema-doc app.schema.noml --format html", args[0]);
+        eprintln!(
+            "  {} migrate app.noml --migrations migrations.noml",
+            args[0]
+        );
+        eprintln!("  {} explain app.noml database.url", args[0]);
+        eprintln!("  {} parse app.noml --profile production", args[0]);
+        eprintln!("  {} check app.noml --hermetic --env-file ci.env", args[0]);
+        eprintln!(
+            "  {} lock app.noml && {} check app.noml --offline",
+            args[0], args[0]
+        );
+        eprintln!(
+            "  {} env app.noml --keys database --prefix APP_ > .env",
+            args[0]
+        );
+        eprintln!(
+            "  {} encrypt app.noml --keys database.password --key-file noml.key",
+            args[0]
+        );
         process::exit(1);
     }
 
@@ -50,7 +95,139 @@ fn main() {
             }
 
             let file_path = &args[2];
-            parse_file(file_path);
+            parse_file(file_path, option_value(&args[3..], "--profile"));
+        }
+
+        "schema-doc" => {
+            if args.len() < 3 {
+                eprintln!("Error: schema-doc command requires a schema file path");
+                process::exit(1);
+            }
+
+            let format = option_value(&args[3..], "--format").unwrap_or("markdown");
+            schema_doc(&args[2], format);
+        }
+
+        "migrate" => {
+            if args.len() < 3 {
+                eprintln!("Error: migrate command requires a file path");
+                process::exit(1);
+            }
+
+            let options = &args[3..];
+            let Some(migrations) = option_value(options, "--migrations") else {
+                eprintln!("Error: migrate command requires --migrations <file>");
+                process::exit(1);
+            };
+            let target = option_value(options, "--to").map(|to| {
+                to.parse::<u32>().unwrap_or_else(|_| {
+                    eprintln!("Error: --to expects a version number, got '{to}'");
+                    process::exit(1);
+                })
+            });
+            let dry_run = options.iter().any(|arg| arg == "--dry-run");
+            migrate(&args[2], migrations, target, dry_run);
+        }
+
+        "explain" => {
+            if args.len() < 4 {
+                eprintln!("Error: explain command requires a file path and a key");
+                process::exit(1);
+            }
+
+            explain(&args[2], &args[3], option_value(&args[4..], "--profile"));
+        }
+
+        "check" => {
+            if args.len() < 3 {
+                eprintln!("Error: check command requires a file path");
+                process::exit(1);
+            }
+
+            let options = &args[3..];
+            let flag = |name: &str| options.iter().any(|arg| arg == name);
+            let hermetic = flag("--hermetic");
+            let mut env = noml::DotEnv::new().with_process_env(!hermetic);
+            if let Some(path) = option_value(options, "--env-file") {
+                if let Err(err) = env.load_file(path) {
+                    eprintln!("Error reading '{path}':");
+                    eprintln!("{}", err.user_message());
+                    process::exit(1);
+                }
+            }
+            for var in option_values(options, "--env") {
+                let Some((name, value)) = var.split_once('=') else {
+                    eprintln!("Error: --env expects NAME=VALUE, got '{var}'");
+                    process::exit(1);
+                };
+                env.insert(name, value);
+            }
+            let path = lockfile_path(&args[2], option_value(options, "--lockfile"));
+            let lockfile = (flag("--offline") || path.exists()).then(|| {
+                noml::Lockfile::load(&path).unwrap_or_else(|err| {
+                    eprintln!("Error reading '{}':", path.display());
+                    eprintln!("{}", err.user_message());
+                    process::exit(1);
+                })
+            });
+            check(
+                &args[2],
+                hermetic,
+                option_value(options, "--root"),
+                &env,
+                lockfile,
+            );
+        }
+
+        "lock" => {
+            if args.len() < 3 {
+                eprintln!("Error: lock command requires a file path");
+                process::exit(1);
+            }
+
+            let options = &args[3..];
+            let update = options.iter().any(|arg| arg == "--update");
+            lock(
+                &args[2],
+                &lockfile_path(&args[2], option_value(options, "--lockfile")),
+                update,
+            );
+        }
+
+        "env" => {
+            if args.len() < 3 {
+                eprintln!("Error: env command requires a file path");
+                process::exit(1);
+            }
+
+            let options = &args[3..];
+            let flag = |name: &str| options.iter().any(|arg| arg == name);
+            env_file(
+                &args[2],
+                option_value(options, "--keys"),
+                option_value(options, "--prefix").unwrap_or(""),
+                flag("--export"),
+                flag("--secrets"),
+                option_value(options, "--profile"),
+            );
+        }
+
+        "keygen" => keygen(),
+
+        "encrypt" | "decrypt" => {
+            if args.len() < 3 {
+                eprintln!("Error: {command} command requires a file path");
+                process::exit(1);
+            }
+
+            let options = &args[3..];
+            let keys = option_value(options, "--keys");
+            if command == "encrypt" && keys.is_none() {
+                eprintln!("Error: encrypt command requires --keys <key,...>");
+                process::exit(1);
+            }
+            let key_file = option_value(options, "--key-file");
+            crypt_file(&args[2], keys, key_file, command == "encrypt");
         }
 
         _ => {
@@ -82,30 +259,402 @@ fn validate_file(file_path: &str) {
     }
 }
 
-fn parse_file(file_path: &str) {
-    let content = match fs::read_to_string(file_path) {
-        Ok(content) => content,
+fn parse_file(file_path: &str, profile: Option<&str>) {
+    let config = match load_config(file_path, profile) {
+        Ok(config) => config,
         Err(err) => {
-            eprintln!("Error reading file '{file_path}': {err}");
+            eprintln!("Parse error in '{file_path}':");
+            eprintln!("{}", err.user_message());
             process::exit(1);
         }
     };
 
-    let document = match noml::parse(&content) {
-        Ok(doc) => doc,
+    println!("Successfully parsed '{file_path}':");
+    if let Some(profile) = config.active_profile() {
+        println!("Profile: {profile}");
+    }
+    println!();
+    println!("Structure:");
+    display_value(config.as_value(), 0);
+}
+
+/// Load a configuration file with `profile`, or the one from NOML_PROFILE
+fn load_config(file_path: &str, profile: Option<&str>) -> noml::Result<noml::Config> {
+    let mut builder = noml::Config::builder();
+    if let Some(profile) = profile {
+        builder = builder.profile(profile);
+    }
+    builder.build_from_file(file_path)
+}
+
+fn schema_doc(schema_path: &str, format: &str) {
+    let schema = match noml::Schema::from_file(schema_path) {
+        Ok(schema) => schema,
         Err(err) => {
-            eprintln!("Parse error in '{file_path}':");
+            eprintln!("Error loading schema '{schema_path}':");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    };
+
+    let output = match format {
+        "markdown" | "md" => schema.render_docs(noml::DocFormat::Markdown),
+        "html" => schema.render_docs(noml::DocFormat::Html),
+        "noml" | "template" => schema.render_template(),
+        _ => {
+            eprintln!("Error: unknown format '{format}' (expected markdown, html or noml)");
+            process::exit(1);
+        }
+    };
+
+    print!("{output}");
+}
+
+fn migrate(file_path: &str, migrations_path: &str, target: Option<u32>, dry_run: bool) {
+    let migrations = match noml::Migrations::from_file(migrations_path) {
+        Ok(migrations) => migrations,
+        Err(err) => {
+            eprintln!("Error loading migrations '{migrations_path}':");
             eprintln!("{}", err.user_message());
             process::exit(1);
         }
     };
+    let target = target.unwrap_or_else(|| migrations.latest());
 
-    let value = document;
+    let result = noml::Config::from_file(file_path).and_then(|mut config| {
+        let applied = config.migrate_to(&migrations, target)?;
+        if dry_run {
+            print!("{}", config.to_noml_string()?);
+        } else if applied > 0 {
+            config.save()?;
+        }
+        Ok(applied)
+    });
 
-    println!("Successfully parsed '{file_path}':");
-    println!();
-    println!("Structure:");
-    display_value(&value, 0);
+    match result {
+        Ok(applied) if !dry_run => {
+            println!("✓ {file_path}: applied {applied} migration(s), now at version {target}");
+        }
+        Ok(_) => {}
+        Err(err) => {
+            eprintln!("✗ Migration of '{file_path}' failed:");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    }
+}
+
+fn explain(file_path: &str, key: &str, profile: Option<&str>) {
+    let config = match load_config(file_path, profile) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("✗ Failed to load '{file_path}':");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    };
+
+    let Some(value) = config.get(key) else {
+        eprintln!("✗ Key '{key}' not found in '{file_path}'");
+        process::exit(1);
+    };
+    println!("{key} = {value}");
+    match config.origin(key) {
+        Some(origin) => println!("  from {origin}"),
+        None => println!("  origin unknown"),
+    }
+}
+
+/// Resolve a file and list what it read from outside: variables, files and
+/// directories with their hashes, and a digest of them all
+fn check(
+    file_path: &str,
+    hermetic: bool,
+    root: Option<&str>,
+    env: &noml::DotEnv,
+    lockfile: Option<noml::Lockfile>,
+) {
+    let dir = file_dir(file_path);
+    let mut resolver = if hermetic {
+        let mut config = noml::ResolverConfig::hermetic(root.map_or(dir, Path::new));
+        config.base_path = Some(dir.to_path_buf());
+        config.env_vars = Some(
+            env.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        );
+        noml::Resolver::with_config(config)
+    } else {
+        noml::Resolver::new()
+            .with_base_path(dir)
+            .with_dotenv(env, noml::EnvPrecedence::DotEnv)
+    };
+    if let Some(lockfile) = lockfile {
+        resolver = resolver.with_lockfile(lockfile);
+    }
+
+    let result = noml::parse_raw_from_file(file_path).and_then(|doc| resolver.resolve(&doc));
+    if let Err(err) = result {
+        eprintln!("✗ Failed to resolve '{file_path}':");
+        eprintln!("{}", err.user_message());
+        process::exit(1);
+    }
+
+    let manifest = resolver.manifest();
+    let mode = if hermetic { " hermetically" } else { "" };
+    println!("✓ {file_path} resolved{mode}");
+    for (name, hash) in manifest.env_vars() {
+        match hash {
+            Some(hash) => println!("  env   {name} sha256:{hash}"),
+            None => println!("  env   {name} unset"),
+        }
+    }
+    for (path, hash) in manifest.files() {
+        println!("  file  {} sha256:{hash}", path.display());
+    }
+    for (url, hash) in manifest.remote_includes() {
+        println!("  url   {url} sha256:{hash}");
+    }
+    for (path, hash) in manifest.directories() {
+        println!("  dir   {} sha256:{hash}", path.display());
+    }
+    println!("  digest {}", manifest.digest());
+}
+
+/// Fetch the remote includes of a file and pin them in the lockfile at
+/// `lock_path`, with vendored copies; `update` re-pins URLs already locked
+#[cfg(feature = "async")]
+fn lock(file_path: &str, lock_path: &Path, update: bool) {
+    let lockfile = if update {
+        Ok(noml::Lockfile::new(lock_path))
+    } else {
+        noml::Lockfile::open(lock_path)
+    };
+    let result = lockfile.and_then(|lockfile| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| noml::NomlError::io(file_path, e))?;
+        let document = noml::parse_raw_from_file(file_path)?;
+        let mut resolver = noml::Resolver::new()
+            .with_base_path(file_dir(file_path))
+            .with_lockfile(lockfile.clone().with_locking(true));
+        runtime.block_on(resolver.resolve_document_async(&document))?;
+        lockfile.save()?;
+        Ok(lockfile)
+    });
+    let lockfile = match result {
+        Ok(lockfile) => lockfile,
+        Err(err) => {
+            eprintln!("✗ Failed to lock '{file_path}':");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    };
+
+    println!("✓ Wrote {}", lock_path.display());
+    for url in lockfile.urls() {
+        let hash = lockfile.sha256(&url).unwrap_or_default();
+        println!("  {url} sha256:{hash}");
+    }
+}
+
+#[cfg(not(feature = "async"))]
+fn lock(_file_path: &str, _lock_path: &Path, _update: bool) {
+    eprintln!("Error: noml was built without the `async` feature needed to fetch remote includes");
+    process::exit(1);
+}
+
+/// The lockfile given with `--lockfile`, or `noml.lock` beside the file
+fn lockfile_path(file_path: &str, lockfile: Option<&str>) -> PathBuf {
+    lockfile.map_or_else(|| file_dir(file_path).join(noml::LOCKFILE), PathBuf::from)
+}
+
+/// Directory of a file given on the command line
+fn file_dir(file_path: &str) -> &Path {
+    Path::new(file_path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Print the values of `keys` (all of them without `keys`) as a `.env`
+/// file, naming each variable after its key: `database.host` becomes
+/// `DATABASE_HOST`
+fn env_file(
+    file_path: &str,
+    keys: Option<&str>,
+    prefix: &str,
+    export: bool,
+    secrets: bool,
+    profile: Option<&str>,
+) {
+    let config = match load_config(file_path, profile) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("✗ Failed to load '{file_path}':");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    };
+
+    let mut dotenv = noml::DotEnv::new();
+    let mut skipped = Vec::new();
+    match keys {
+        Some(keys) => {
+            for key in keys.split(',').map(str::trim) {
+                let Some(value) = config.get(key) else {
+                    eprintln!("✗ Key '{key}' not found in '{file_path}'");
+                    process::exit(1);
+                };
+                add_env_vars(&mut dotenv, key, value, prefix, secrets, &mut skipped);
+            }
+        }
+        None => add_env_vars(
+            &mut dotenv,
+            "",
+            config.as_value(),
+            prefix,
+            secrets,
+            &mut skipped,
+        ),
+    }
+
+    for key in skipped {
+        eprintln!("Skipped secret '{key}'; pass --secrets to include it");
+    }
+    print!("{}", dotenv.render(export));
+}
+
+fn add_env_vars(
+    dotenv: &mut noml::DotEnv,
+    key: &str,
+    value: &noml::Value,
+    prefix: &str,
+    secrets: bool,
+    skipped: &mut Vec<String>,
+) {
+    let text = match value {
+        noml::Value::Table(table) => {
+            for (name, value) in table {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{key}.{name}")
+                };
+                add_env_vars(dotenv, &key, value, prefix, secrets, skipped);
+            }
+            return;
+        }
+        noml::Value::Secret(_) if !secrets => {
+            skipped.push(key.to_string());
+            return;
+        }
+        noml::Value::Array(items) => items.iter().map(env_text).collect::<Vec<_>>().join(","),
+        value => env_text(value),
+    };
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            '.' | '-' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect();
+    dotenv.insert(format!("{prefix}{name}"), text);
+}
+
+/// A scalar as the text of an environment variable
+fn env_text(value: &noml::Value) -> String {
+    match value {
+        noml::Value::Null => String::new(),
+        noml::Value::String(text) => text.clone(),
+        noml::Value::Secret(secret) => secret.expose().to_string(),
+        // `Display` rounds these, so write the exact bytes and seconds
+        noml::Value::Size(bytes) => bytes.to_string(),
+        noml::Value::Duration(seconds) => seconds.to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn keygen() {
+    println!("{}", noml::EncryptionKey::generate().to_base64());
+}
+
+/// Encrypt or decrypt the values of `keys` (all encrypted values when
+/// decrypting without `keys`) and write the file back in place
+#[cfg(feature = "encryption")]
+fn crypt_file(file_path: &str, keys: Option<&str>, key_file: Option<&str>, encrypt: bool) {
+    use noml::KeyProvider;
+
+    let keys: Option<Vec<&str>> = keys.map(|keys| keys.split(',').map(str::trim).collect());
+    let result = match key_file {
+        Some(path) => noml::KeyFile::new(path).key(),
+        None => noml::EnvKey::default().key(),
+    }
+    .and_then(|key| {
+        let source =
+            fs::read_to_string(file_path).map_err(|e| noml::NomlError::io(file_path, e))?;
+        let mut document = noml::parse_raw(&source)?;
+        let count = match &keys {
+            Some(keys) if encrypt => noml::encrypt_paths(&mut document, keys, &key)?,
+            keys => noml::decrypt_paths(&mut document, keys.as_deref(), &key)?,
+        };
+        if count > 0 {
+            let output = noml::serialize_document(&document)?;
+            fs::write(file_path, output).map_err(|e| noml::NomlError::io(file_path, e))?;
+        }
+        Ok(count)
+    });
+
+    match result {
+        Ok(count) if encrypt => println!("✓ {file_path}: encrypted {count} value(s)"),
+        Ok(count) => println!("✓ {file_path}: decrypted {count} value(s)"),
+        Err(err) => {
+            let action = if encrypt { "Encryption" } else { "Decryption" };
+            eprintln!("✗ {action} of '{file_path}' failed:");
+            eprintln!("{}", err.user_message());
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+fn keygen() {
+    encryption_unavailable();
+}
+
+#[cfg(not(feature = "encryption"))]
+fn crypt_file(_file_path: &str, _keys: Option<&str>, _key_file: Option<&str>, _encrypt: bool) {
+    encryption_unavailable();
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encryption_unavailable() {
+    eprintln!("Error: noml was built without the `encryption` feature");
+    process::exit(1);
+}
+
+/// Find the value following `--flag` (or given as `--flag=value`)
+fn option_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    option_values(args, flag).into_iter().next()
+}
+
+/// Find the values of every `--flag` given
+fn option_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    let mut values = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            values.extend(iter.next().map(String::as_str));
+        } else if let Some(value) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            values.push(value);
+        }
+    }
+    values
 }
 
 fn display_value(value: &noml::Value, indent: usize) {
@@ -120,6 +669,7 @@ fn display_value(value: &noml::Value, indent: usize) {
         noml::Value::Size(bytes) => println!("{indent_str}{bytes}B"),
         noml::Value::Duration(secs) => println!("{indent_str}{secs}s"),
         noml::Value::Binary(data) => println!("{}<{} bytes>", indent_str, data.len()),
+        noml::Value::Secret(secret) => println!("{indent_str}{secret}"),
         noml::Value::Array(arr) => {
             println!("{indent_str}[");
             for (i, item) in arr.iter().enumerate() {
@@ -167,6 +717,13 @@ mod tests {
         assert!(noml::validate(&content).is_ok());
     }
 
+    #[test]
+    fn test_env_text_is_exact() {
+        let value = noml::parse("odd = @size(\"1500\")\nlong = @duration(\"100s\")").unwrap();
+        assert_eq!(super::env_text(value.get("odd").unwrap()), "1500");
+        assert_eq!(super::env_text(value.get("long").unwrap()), "100");
+    }
+
     #[test]
     fn test_parse_functionality() {
         let config = r#"
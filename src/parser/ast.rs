@@ -263,15 +263,31 @@ pub enum AstValue {
         args: Vec<AstNode>,
     },
 
-    /// Variable interpolation ${path}
+    /// Variable interpolation ${path}, optionally with a fallback
     Interpolation {
         /// The path to interpolate
         path: String,
+        /// What to do when the path is missing, e.g. `${path:-default}`
+        fallback: Option<Fallback>,
     },
 
-    /// Include/import statement
+    /// Include/import statement; `*` in the file name includes every
+    /// matching file, merged in sorted order
     Include {
-        /// Path to include
+        /// Path or pattern to include
+        path: String,
+        /// Dotted path of the part of the file to mount, e.g. `database`
+        subpath: Option<String>,
+        /// Keys to keep from the file or sub-path; all when `None`
+        only: Option<Vec<String>>,
+        /// SHA-256 the file's contents must have, in lowercase hex
+        sha256: Option<String>,
+    },
+
+    /// `include_dir("conf.d")`: every `.noml` file in a directory, merged
+    /// in sorted order
+    IncludeDir {
+        /// Directory to include
         path: String,
     },
 
@@ -284,6 +300,10 @@ pub enum AstValue {
     },
 }
 
+/// First key segment of profile sections, as in `[@profile.production]`;
+/// the `@` keeps them apart from any key a document holds as data
+pub(crate) const PROFILE_KEY: &str = "@profile";
+
 /// Table entry with key information
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableEntry {
@@ -315,6 +335,16 @@ pub struct KeySegment {
     pub quote_style: Option<StringStyle>,
 }
 
+/// Shell-style fallback for a `${...}` reference that is missing, null or
+/// an empty string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fallback {
+    /// `${path:-text}` uses `text` instead
+    Default(String),
+    /// `${path:?message}` fails with `message`
+    Required(String),
+}
+
 /// String quoting styles
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StringStyle {
@@ -446,14 +476,14 @@ impl AstNode {
                     _ => Err(NomlError::validation(format!("Unknown function: {name}"))),
                 }
             }
-            AstValue::Interpolation { path } => {
+            AstValue::Interpolation { path, .. } => {
                 // This should be resolved during processing
                 Err(NomlError::interpolation(
                     "Unresolved interpolation",
                     path.clone(),
                 ))
             }
-            AstValue::Include { path } => {
+            AstValue::Include { path, .. } | AstValue::IncludeDir { path } => {
                 // This should be resolved during processing
                 Err(NomlError::import(
                     path.clone(),
@@ -685,6 +715,16 @@ impl Span {
     }
 }
 
+impl TableEntry {
+    /// Whether this entry is a `[@profile.<name>]` section rather than data
+    pub(crate) fn is_profile(&self) -> bool {
+        self.key
+            .segments
+            .first()
+            .is_some_and(|segment| !segment.quoted && segment.name == PROFILE_KEY)
+    }
+}
+
 impl Key {
     /// Create a simple key from a string
     pub fn simple(name: String, span: Span) -> Self {
@@ -720,6 +760,15 @@ impl fmt::Display for Key {
     }
 }
 
+impl fmt::Display for Fallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fallback::Default(text) => write!(f, ":-{text}"),
+            Fallback::Required(message) => write!(f, ":?{message}"),
+        }
+    }
+}
+
 impl PartialEq for Key {
     fn eq(&self, other: &Self) -> bool {
         // Only compare segments, not span (for semantic equality)
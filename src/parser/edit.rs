@@ -0,0 +1,443 @@
+//! # Path-Based Document Editing
+//!
+//! Edit a parsed [`Document`] by dotted key path while keeping the comments
+//! and formatting of everything that isn't touched, so the result can be
+//! written back with the format-preserving serializer.
+//!
+//! Paths address tables through `[section]` headers, dotted keys and inline
+//! tables alike: `server.http.port` finds the same entry whether the source
+//! says `[server.http]` / `port = 80` or `server = { http = { port = 80 } }`.
+
+use super::ast::{
+    AstNode, AstValue, Comments, Document, Key, KeySegment, Span, StringStyle, TableEntry,
+};
+use crate::error::{NomlError, Result};
+use crate::resolver::NativeResolver;
+use crate::value::{format_size_exact, Secret, Value};
+use std::collections::HashMap;
+
+impl Document {
+    /// Get the AST node at a dotted key path
+    pub fn get_node(&self, path: &str) -> Option<&AstNode> {
+        find(root_entries(&self.root)?, &split(path))
+    }
+
+    /// Get the AST node at a dotted key path for in-place editing
+    pub fn get_node_mut(&mut self, path: &str) -> Option<&mut AstNode> {
+        find_mut(root_entries_mut(&mut self.root)?, &split(path))
+    }
+
+    /// Set the node at a dotted key path
+    ///
+    /// An existing entry keeps its key and comments; otherwise a new entry is
+    /// added inside the deepest table that already exists on the path.
+    pub fn set_node(&mut self, path: &str, node: AstNode) -> Result<()> {
+        let segments = split(path);
+        let entries = root_entries_mut(&mut self.root)
+            .ok_or_else(|| NomlError::validation("Document root is not a table"))?;
+        insert(entries, &segments, new_entry(&segments, node), false);
+        Ok(())
+    }
+
+    /// Set a [`Value`] at a dotted key path (see [`Document::set_node`])
+    ///
+    /// Fails for values holding a secret, as [`AstNode::from_value`] does.
+    pub fn set_value(&mut self, path: &str, value: &Value) -> Result<()> {
+        let node = AstNode::from_value(value)
+            .map_err(|_| NomlError::validation_at(secret_message(), path))?;
+        self.set_node(path, node)
+    }
+
+    /// Set a [`Value`] at a dotted key path, keeping an existing `@name(...)`
+    /// wrapper when `natives` has a type `name` that writes the value back
+    /// (see [`NativeResolver::to_node`])
+    pub fn set_native_value(
+        &mut self,
+        path: &str,
+        value: &Value,
+        natives: &HashMap<String, NativeResolver>,
+    ) -> Result<()> {
+        if let Some(AstValue::Native { type_name, .. }) = self.get_node(path).map(|n| &n.value) {
+            if let Some(native) = natives.get(type_name) {
+                if let Some(node) = native.to_node(type_name, value)? {
+                    return self.set_node(path, node);
+                }
+            }
+        }
+        self.set_value(path, value)
+    }
+
+    /// Remove the entry at a dotted key path, including every entry nested
+    /// under it. Returns whether anything was removed.
+    pub fn remove_path(&mut self, path: &str) -> bool {
+        match root_entries_mut(&mut self.root) {
+            Some(entries) => !take(entries, &split(path)).is_empty(),
+            None => false,
+        }
+    }
+
+    /// Move the entry at `from` (and everything nested under it) to `to`,
+    /// carrying its comments along. Returns whether anything was moved.
+    pub fn rename_path(&mut self, from: &str, to: &str) -> Result<bool> {
+        let target = split(to);
+        let entries = root_entries_mut(&mut self.root)
+            .ok_or_else(|| NomlError::validation("Document root is not a table"))?;
+
+        let taken = take(entries, &split(from));
+        if taken.is_empty() {
+            return Ok(false);
+        }
+        for (relative, mut entry) in taken {
+            let mut segments = target.clone();
+            segments.extend(relative);
+            entry.key = make_key(&segments, entry.key.span);
+            insert(entries, &segments, entry, false);
+        }
+        Ok(true)
+    }
+}
+
+impl AstNode {
+    /// Build an AST node that serializes to the NOML source for `value`
+    ///
+    /// Fails if `value` holds a [`Value::Secret`]: its text is never written
+    /// out, and writing the redaction marker instead would replace the
+    /// secret in the file.
+    pub fn from_value(value: &Value) -> Result<AstNode> {
+        node_from_value(value, &|_| Err(NomlError::validation(secret_message())))
+    }
+}
+
+/// Build the node for `value`, turning each secret into a node with `secret`
+pub(crate) fn node_from_value(
+    value: &Value,
+    secret: &dyn Fn(&Secret) -> Result<AstNode>,
+) -> Result<AstNode> {
+    let ast_value = match value {
+        Value::Null => AstValue::Null,
+        Value::Bool(b) => AstValue::Bool(*b),
+        Value::Integer(i) => AstValue::Integer {
+            value: *i,
+            raw: i.to_string(),
+        },
+        Value::Float(f) => AstValue::Float {
+            value: *f,
+            raw: if f.is_finite() && f.fract() == 0.0 {
+                format!("{f:.1}")
+            } else {
+                f.to_string()
+            },
+        },
+        Value::String(s) => string_value(s),
+        Value::Array(items) => AstValue::Array {
+            elements: items
+                .iter()
+                .map(|item| node_from_value(item, secret))
+                .collect::<Result<_>>()?,
+            multiline: false,
+            trailing_comma: false,
+        },
+        Value::Table(table) => AstValue::Table {
+            entries: table
+                .iter()
+                .map(|(key, value)| {
+                    Ok(TableEntry {
+                        key: make_key(std::slice::from_ref(key), Span::default()),
+                        value: node_from_value(value, secret)?,
+                        comments: Comments::new(),
+                    })
+                })
+                .collect::<Result<_>>()?,
+            inline: true,
+        },
+        Value::Size(bytes) => native("size", format_size_exact(*bytes)),
+        Value::Duration(seconds) => native("duration", format!("{seconds}s")),
+        Value::Secret(text) => return secret(text),
+        Value::Binary(data) => AstValue::Array {
+            elements: data
+                .iter()
+                .map(|byte| AstValue::Integer {
+                    value: i64::from(*byte),
+                    raw: byte.to_string(),
+                })
+                .map(|value| AstNode::new(value, Span::default()))
+                .collect(),
+            multiline: false,
+            trailing_comma: false,
+        },
+        #[cfg(feature = "chrono")]
+        Value::DateTime(dt) => string_value(&dt.to_rfc3339()),
+    };
+    Ok(AstNode::new(ast_value, Span::default()))
+}
+
+fn secret_message() -> &'static str {
+    "Secret values cannot be written to NOML source; write the @secret(...) source in the file instead"
+}
+
+fn string_value(s: &str) -> AstValue {
+    AstValue::String {
+        value: s.to_string(),
+        style: StringStyle::Double,
+        has_escapes: s.contains(['"', '\\', '\n', '\r', '\t']),
+    }
+}
+
+fn native(type_name: &str, arg: String) -> AstValue {
+    AstValue::Native {
+        type_name: type_name.to_string(),
+        args: vec![AstNode::new(string_value(&arg), Span::default())],
+    }
+}
+
+fn split(path: &str) -> Vec<String> {
+    path.split('.').map(str::to_string).collect()
+}
+
+fn key_names(key: &Key) -> Vec<&str> {
+    key.segments.iter().map(|s| s.name.as_str()).collect()
+}
+
+fn make_key(segments: &[String], span: Span) -> Key {
+    let segments = segments
+        .iter()
+        .map(|name| {
+            let bare = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            KeySegment {
+                name: name.clone(),
+                quoted: !bare,
+                quote_style: (!bare).then_some(StringStyle::Double),
+            }
+        })
+        .collect();
+    Key::dotted(segments, span)
+}
+
+fn new_entry(segments: &[String], value: AstNode) -> TableEntry {
+    TableEntry {
+        key: make_key(segments, Span::default()),
+        value,
+        comments: Comments::new(),
+    }
+}
+
+fn root_entries(root: &AstNode) -> Option<&Vec<TableEntry>> {
+    match &root.value {
+        AstValue::Table { entries, .. } => Some(entries),
+        _ => None,
+    }
+}
+
+fn root_entries_mut(root: &mut AstNode) -> Option<&mut Vec<TableEntry>> {
+    match &mut root.value {
+        AstValue::Table { entries, .. } => Some(entries),
+        _ => None,
+    }
+}
+
+/// Whether `path` starts with all of `prefix`
+fn has_prefix(path: &[impl AsRef<str>], prefix: &[impl AsRef<str>]) -> bool {
+    prefix.len() <= path.len()
+        && prefix
+            .iter()
+            .zip(path)
+            .all(|(a, b)| a.as_ref() == b.as_ref())
+}
+
+fn find<'a>(entries: &'a [TableEntry], path: &[String]) -> Option<&'a AstNode> {
+    for entry in entries {
+        let key = key_names(&entry.key);
+        if key.len() == path.len() && has_prefix(path, &key) {
+            return Some(&entry.value);
+        }
+        if key.len() < path.len() && has_prefix(path, &key) {
+            if let AstValue::Table { entries, .. } = &entry.value.value {
+                if let Some(node) = find(entries, &path[key.len()..]) {
+                    return Some(node);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn find_mut<'a>(entries: &'a mut [TableEntry], path: &[String]) -> Option<&'a mut AstNode> {
+    for entry in entries {
+        let key_len = entry.key.segments.len();
+        if !has_prefix(path, &key_names(&entry.key)) {
+            continue;
+        }
+        if key_len == path.len() {
+            return Some(&mut entry.value);
+        }
+        if let AstValue::Table { entries, .. } = &mut entry.value.value {
+            if let Some(node) = find_mut(entries, &path[key_len..]) {
+                return Some(node);
+            }
+        }
+    }
+    None
+}
+
+/// Insert `entry` at `path`, descending into existing tables first.
+/// An existing entry at the exact path has its value replaced.
+fn insert(entries: &mut Vec<TableEntry>, path: &[String], mut entry: TableEntry, inline: bool) {
+    // Replace an existing entry in place
+    if let Some(existing) = entries
+        .iter_mut()
+        .find(|e| key_names(&e.key).iter().eq(path.iter()))
+    {
+        let leading = std::mem::take(&mut existing.value.format.leading_whitespace);
+        existing.value = entry.value;
+        existing.value.format.leading_whitespace = leading;
+        if existing.comments.is_empty() {
+            existing.comments = entry.comments;
+        }
+        if inline {
+            make_inline(&mut existing.value);
+        }
+        return;
+    }
+
+    // Descend into the table that owns the longest matching prefix
+    let parent = entries
+        .iter_mut()
+        .filter(|e| {
+            let key = key_names(&e.key);
+            key.len() < path.len()
+                && has_prefix(path, &key)
+                && matches!(e.value.value, AstValue::Table { .. })
+        })
+        .max_by_key(|e| e.key.segments.len());
+    if let Some(parent) = parent {
+        let depth = parent.key.segments.len();
+        if let AstValue::Table {
+            entries: children,
+            inline: child_inline,
+        } = &mut parent.value.value
+        {
+            let child_inline = *child_inline || inline;
+            entry.key = make_key(&path[depth..], entry.key.span);
+            insert(children, &path[depth..], entry, child_inline);
+            return;
+        }
+    }
+
+    // Anything else stored under this path is replaced by the new entry
+    entries.retain(|e| !has_prefix(&key_names(&e.key), path));
+
+    if inline {
+        make_inline(&mut entry.value);
+    } else if matches!(entry.value.value, AstValue::Table { inline: false, .. })
+        && entry.value.format.leading_whitespace.is_empty()
+    {
+        // New sections read better with a blank line before the header
+        entry.value.format.leading_whitespace = "\n".to_string();
+    }
+    entries.push(entry);
+}
+
+/// Remove and return every entry at or under `path`, with the key segments
+/// that remain below `path`
+fn take(entries: &mut Vec<TableEntry>, path: &[String]) -> Vec<(Vec<String>, TableEntry)> {
+    let mut taken = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        let key: Vec<String> = key_names(&entries[i].key)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if has_prefix(&key, path) {
+            let entry = entries.remove(i);
+            taken.push((key[path.len()..].to_vec(), entry));
+            continue;
+        }
+        if has_prefix(path, &key) {
+            if let AstValue::Table {
+                entries: children, ..
+            } = &mut entries[i].value.value
+            {
+                taken.extend(take(children, &path[key.len()..]));
+            }
+        }
+        i += 1;
+    }
+    taken
+}
+
+/// Convert `[section]` tables to inline tables so they can live inside one
+fn make_inline(node: &mut AstNode) {
+    match &mut node.value {
+        AstValue::Table { entries, inline } => {
+            *inline = true;
+            for entry in entries {
+                make_inline(&mut entry.value);
+            }
+        }
+        AstValue::Array { elements, .. } => {
+            for element in elements {
+                make_inline(element);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+    use crate::serializer::serialize_document;
+    use crate::value::Value;
+
+    #[test]
+    fn test_edit_by_path_keeps_comments() {
+        let mut document = parse(
+            r#"# Header comment
+
+name = "svc" # inline
+
+# Database settings
+[database]
+host = "localhost"
+"#,
+        )
+        .unwrap();
+
+        assert!(document.get_node("database.host").is_some());
+        document
+            .set_value("database.port", &Value::Integer(5432))
+            .unwrap();
+        document
+            .set_value("name", &Value::String("api".into()))
+            .unwrap();
+        assert!(document
+            .rename_path("database.host", "database.hostname")
+            .unwrap());
+        assert!(!document.remove_path("missing"));
+
+        let output = serialize_document(&document).unwrap();
+        assert_eq!(
+            output,
+            r#"# Header comment
+
+name = "api" # inline
+
+# Database settings
+[database]
+port = 5432
+hostname = "localhost"
+"#
+        );
+        assert_eq!(
+            document
+                .to_value()
+                .unwrap()
+                .get("database.hostname")
+                .unwrap(),
+            &Value::String("localhost".into())
+        );
+    }
+}
@@ -7,8 +7,8 @@
 
 use crate::error::{NomlError, Result};
 use crate::parser::ast::{
-    AstNode, AstValue, Comment, CommentStyle, Comments, Document, Key, KeySegment, Span,
-    StringStyle, TableEntry,
+    AstNode, AstValue, Comment, CommentStyle, Comments, Document, Fallback, Key, KeySegment, Span,
+    StringStyle, TableEntry, PROFILE_KEY,
 };
 use crate::parser::lexer::{Lexer, StringStyle as LexerStringStyle, Token, TokenKind};
 use std::fs;
@@ -47,6 +47,9 @@ pub async fn parse_file_async(path: &std::path::Path) -> Result<Document> {
     parse_string(&source, Some(path.to_string_lossy().to_string()))
 }
 
+/// Sub-path, `only` keys and `sha256` pin of a parenthesized include
+type IncludeArgs = (Option<String>, Option<Vec<String>>, Option<String>);
+
 /// NOML parser implementation
 pub struct NomlParser<'a> {
     /// Input tokens
@@ -79,29 +82,35 @@ impl<'a> NomlParser<'a> {
         let mut entries = Vec::new();
         let mut comments = Comments::new();
 
-        // Collect leading comments
-        self.collect_leading_comments(&mut comments);
-
         while !self.is_at_end() {
-            // Collect any leading comments first
-            self.collect_leading_comments(&mut comments);
+            // Comments belong to the entry or table header that follows them
+            let (mut leading, blank_line) = self.collect_leading_trivia();
+
+            // A comment block separated from the first entry is the file header
+            if entries.is_empty() && blank_line && comments.before.is_empty() {
+                comments.before.append(&mut leading.before);
+            }
 
             // Skip whitespace and newlines
             if self.skip_insignificant_tokens() {
+                comments.before.extend(leading.before);
                 continue;
             }
 
             if self.is_at_end() {
+                // Trailing comments at the end of the document
+                comments.after.extend(leading.before);
                 break;
             }
 
             // Parse table entry or table header
             if self.check_token(&TokenKind::LeftBracket) {
                 // Parse table header - this creates nested table structure
-                self.parse_table_header(&mut entries)?;
+                self.parse_table_header(&mut entries, leading, blank_line)?;
             } else {
                 // Parse key-value pair
-                let kv_entry = self.parse_key_value_pair()?;
+                let mut kv_entry = self.parse_key_value_pair()?;
+                attach_leading(&mut kv_entry, leading, blank_line);
                 entries.push(kv_entry);
             }
         }
@@ -118,9 +127,13 @@ impl<'a> NomlParser<'a> {
     }
 
     /// Parse a table header like `[section]` or `[section.subsection]`
-    fn parse_table_header(&mut self, entries: &mut Vec<TableEntry>) -> Result<()> {
+    fn parse_table_header(
+        &mut self,
+        entries: &mut Vec<TableEntry>,
+        mut comments: Comments,
+        blank_line: bool,
+    ) -> Result<()> {
         let start_span = self.current_span();
-        let mut comments = Comments::new();
 
         // Collect comments before the header
         self.collect_leading_comments(&mut comments);
@@ -155,17 +168,21 @@ impl<'a> NomlParser<'a> {
         let mut table_entries = Vec::new();
         while !self.is_at_end() && !self.check_token(&TokenKind::LeftBracket) {
             // Collect any comments first
-            self.collect_leading_comments(&mut comments);
+            let checkpoint = self.pos;
+            let (leading, blank_line) = self.collect_leading_trivia();
 
             if self.skip_insignificant_tokens() {
                 continue;
             }
 
             if self.is_at_end() || self.check_token(&TokenKind::LeftBracket) {
+                // Leave these comments for the next header (or end of document)
+                self.pos = checkpoint;
                 break;
             }
 
-            let entry = self.parse_key_value_pair()?;
+            let mut entry = self.parse_key_value_pair()?;
+            attach_leading(&mut entry, leading, blank_line);
             table_entries.push(entry);
         }
 
@@ -173,13 +190,21 @@ impl<'a> NomlParser<'a> {
         let end_span = self.current_span();
         let table_span = start_span.merge(&end_span);
 
-        let table_value = AstNode::new(
+        let mut table_value = AstNode::new(
             AstValue::Table {
                 entries: table_entries,
                 inline: false,
             },
             table_span,
         );
+        if blank_line {
+            table_value.format.leading_whitespace = "\n".to_string();
+        }
+
+        // Each [[array]] element keeps the comments of its own header
+        if is_array_of_tables {
+            table_value.comments = std::mem::take(&mut comments);
+        }
 
         // Create the table entry
         // For array of tables, we need special handling to create arrays
@@ -287,6 +312,19 @@ impl<'a> NomlParser<'a> {
         let token = self.advance()?;
 
         match &token.kind {
+            // `@profile` is the one key starting with '@'
+            TokenKind::At => match self.advance()?.kind {
+                TokenKind::Identifier(name) if PROFILE_KEY[1..] == *name => Ok(KeySegment {
+                    name: PROFILE_KEY.to_string(),
+                    quoted: false,
+                    quote_style: None,
+                }),
+                _ => Err(NomlError::parse(
+                    format!("Only '{PROFILE_KEY}' may start a key with '@'"),
+                    token.span.start_line,
+                    token.span.start_column,
+                )),
+            },
             TokenKind::Identifier(name) => Ok(KeySegment {
                 name: name.to_string(),
                 quoted: false,
@@ -323,7 +361,24 @@ impl<'a> NomlParser<'a> {
             TokenKind::LeftBrace => self.parse_inline_table(),
 
             // Functions and special constructs
-            TokenKind::EnvFunc => self.parse_env_function(),
+            TokenKind::EnvFunc => self.parse_function_call(),
+            TokenKind::Identifier(name)
+                if *name == "include_dir"
+                    && matches!(
+                        self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                        Some(TokenKind::LeftParen)
+                    ) =>
+            {
+                self.parse_include()
+            }
+            TokenKind::Identifier(_)
+                if matches!(
+                    self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                    Some(TokenKind::LeftParen)
+                ) =>
+            {
+                self.parse_function_call()
+            }
             TokenKind::At => self.parse_native_type(),
             TokenKind::InterpolationStart => self.parse_interpolation(),
             TokenKind::Include => self.parse_include(),
@@ -555,12 +610,22 @@ impl<'a> NomlParser<'a> {
         Ok(AstNode::new(ast_value, span))
     }
 
-    /// Parse env() function
-    fn parse_env_function(&mut self) -> Result<AstNode> {
+    /// Parse a function call like env("HOME") or hostname()
+    fn parse_function_call(&mut self) -> Result<AstNode> {
         let start_span = self.current_span();
 
-        // Consume 'env'
-        self.consume_token(&TokenKind::EnvFunc, "Expected 'env'")?;
+        // Consume the function name
+        let name = match &self.advance()?.kind {
+            TokenKind::EnvFunc => "env".to_string(),
+            TokenKind::Identifier(name) => name.to_string(),
+            _ => {
+                return Err(NomlError::parse(
+                    "Expected function name",
+                    start_span.start_line,
+                    start_span.start_column,
+                ))
+            }
+        };
 
         // Consume '('
         self.consume_token(&TokenKind::LeftParen, "Expected '('")?;
@@ -590,10 +655,7 @@ impl<'a> NomlParser<'a> {
         let end_span = self.current_span();
         let span = start_span.merge(&end_span);
 
-        let ast_value = AstValue::FunctionCall {
-            name: "env".to_string(),
-            args,
-        };
+        let ast_value = AstValue::FunctionCall { name, args };
 
         Ok(AstNode::new(ast_value, span))
     }
@@ -616,6 +678,18 @@ impl<'a> NomlParser<'a> {
             ));
         };
 
+        // The `@unset` marker of documents that extend another needs no parens
+        if type_name == "unset" && !self.check_token(&TokenKind::LeftParen) {
+            let span = start_span.merge(&self.current_span());
+            return Ok(AstNode::new(
+                AstValue::Native {
+                    type_name,
+                    args: Vec::new(),
+                },
+                span,
+            ));
+        }
+
         // Consume '('
         self.consume_token(&TokenKind::LeftParen, "Expected '('")?;
 
@@ -705,44 +779,193 @@ impl<'a> NomlParser<'a> {
         // Join path segments with dots
         let path = path_segments.join(".");
 
+        // Optional `:-default` / `:?message`
+        let mut fallback = None;
+        if let Ok(token) = self.peek() {
+            if let TokenKind::InterpolationFallback { required, text } = &token.kind {
+                fallback = Some(if *required {
+                    Fallback::Required(text.clone())
+                } else {
+                    Fallback::Default(text.clone())
+                });
+                self.advance()?;
+            }
+        }
+
         // Consume '}'
         self.consume_token(&TokenKind::RightBrace, "Expected '}'")?;
 
         let end_span = self.current_span();
         let span = start_span.merge(&end_span);
 
-        let ast_value = AstValue::Interpolation { path };
+        let ast_value = AstValue::Interpolation { path, fallback };
 
         Ok(AstNode::new(ast_value, span))
     }
 
-    /// Parse include statement
+    /// Parse `include "path"`, `include("path")` or `include_dir("path")`;
+    /// the parenthesized include may select part of the file with
+    /// `include("path", "sub.path")`, `include("path").sub.path` or
+    /// `include("path", only = ["a", "b"])`; `sha256 = "..."` pins the
+    /// file's contents
     fn parse_include(&mut self) -> Result<AstNode> {
         let start_span = self.current_span();
 
-        // Consume 'include'
-        self.consume_token(&TokenKind::Include, "Expected 'include'")?;
+        // Consume 'include' or 'include_dir'
+        let directory = !matches!(self.advance()?.kind, TokenKind::Include);
+        let parenthesized = self.match_token(&TokenKind::LeftParen);
 
         // Parse the path string
-        let path_node = self.parse_string_value()?;
-        let path = if let AstValue::String { ref value, .. } = path_node.value {
-            value.clone()
+        let path = match &self.peek()?.kind {
+            TokenKind::String { value, .. } => value.to_string(),
+            _ => {
+                return Err(NomlError::parse(
+                    "Expected string path for include",
+                    self.current_line(),
+                    self.current_column(),
+                ))
+            }
+        };
+        self.advance()?;
+        let (subpath, only, sha256) = if parenthesized && !directory {
+            self.parse_include_selection()?
         } else {
-            return Err(NomlError::parse(
-                "Expected string path for include",
-                self.current_line(),
-                self.current_column(),
-            ));
+            if parenthesized {
+                self.consume_token(&TokenKind::RightParen, "Expected ')' after include path")?;
+            }
+            (None, None, None)
         };
 
         let end_span = self.current_span();
         let span = start_span.merge(&end_span);
 
-        let ast_value = AstValue::Include { path };
+        let ast_value = if directory {
+            AstValue::IncludeDir { path }
+        } else {
+            AstValue::Include {
+                path,
+                subpath,
+                only,
+                sha256,
+            }
+        };
 
         Ok(AstNode::new(ast_value, span))
     }
 
+    /// Parse the rest of `include("path"`: an optional sub-path string,
+    /// `only = [...]` key list and `sha256 = "..."` pin, the closing paren
+    /// and any `.key` suffixes
+    fn parse_include_selection(&mut self) -> Result<IncludeArgs> {
+        let mut segments: Vec<String> = Vec::new();
+        let mut only = None;
+        let mut sha256 = None;
+        while self.match_token(&TokenKind::Comma) {
+            let token = self.peek()?;
+            match &token.kind {
+                TokenKind::String { value, .. } if segments.is_empty() => {
+                    segments.extend(value.split('.').map(str::to_string));
+                    self.advance()?;
+                }
+                TokenKind::Identifier("only") if only.is_none() => {
+                    self.advance()?;
+                    self.consume_token(&TokenKind::Equals, "Expected '=' after 'only'")?;
+                    only = Some(self.parse_include_keys()?);
+                }
+                TokenKind::Identifier("sha256") if sha256.is_none() => {
+                    self.advance()?;
+                    self.consume_token(&TokenKind::Equals, "Expected '=' after 'sha256'")?;
+                    sha256 = Some(self.parse_include_digest()?);
+                }
+                _ => return Err(NomlError::parse(
+                    "Expected a sub-path string, 'only = [...]' or 'sha256 = \"...\"' in include",
+                    token.span.start_line,
+                    token.span.start_column,
+                )),
+            }
+        }
+        self.consume_token(&TokenKind::RightParen, "Expected ')' after include path")?;
+
+        while self.match_token(&TokenKind::Dot) {
+            let token = self.peek()?;
+            let segment = match &token.kind {
+                TokenKind::Identifier(name) => name.to_string(),
+                TokenKind::String { value, .. } => value.to_string(),
+                _ => {
+                    return Err(NomlError::parse(
+                        "Expected key after '.' in include",
+                        token.span.start_line,
+                        token.span.start_column,
+                    ))
+                }
+            };
+            segments.push(segment);
+            self.advance()?;
+        }
+
+        let subpath = (!segments.is_empty()).then(|| segments.join("."));
+        Ok((subpath, only, sha256))
+    }
+
+    /// Parse the hex digest of `sha256 = "..."`
+    fn parse_include_digest(&mut self) -> Result<String> {
+        let token = self.peek()?;
+        let digest = match &token.kind {
+            TokenKind::String { value, .. }
+                if value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                value.to_ascii_lowercase()
+            }
+            _ => {
+                return Err(NomlError::parse(
+                    "Expected a 64-digit hex SHA-256 after 'sha256 ='",
+                    token.span.start_line,
+                    token.span.start_column,
+                ))
+            }
+        };
+        self.advance()?;
+        Ok(digest)
+    }
+
+    /// Parse the `["a", "b"]` key list of `only = [...]`
+    fn parse_include_keys(&mut self) -> Result<Vec<String>> {
+        self.consume_token(&TokenKind::LeftBracket, "Expected '[' after 'only ='")?;
+        let mut keys = Vec::new();
+        while !self.check_token(&TokenKind::RightBracket) {
+            let token = self.peek()?;
+            match &token.kind {
+                TokenKind::String { value, .. } if value.contains('.') => {
+                    return Err(NomlError::parse(
+                        format!(
+                            "'only' takes top-level keys, not the path '{value}'; \
+                             select a table with the sub-path argument instead, \
+                             e.g. include(\"file.noml\", \"{}\", only = [\"{}\"])",
+                            value.rsplit_once('.').map_or("", |(table, _)| table),
+                            value.rsplit_once('.').map_or("", |(_, key)| key),
+                        ),
+                        token.span.start_line,
+                        token.span.start_column,
+                    ))
+                }
+                TokenKind::String { value, .. } => keys.push(value.to_string()),
+                _ => {
+                    return Err(NomlError::parse(
+                        "Expected key name string in 'only' list",
+                        token.span.start_line,
+                        token.span.start_column,
+                    ))
+                }
+            }
+            self.advance()?;
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume_token(&TokenKind::RightBracket, "Expected ']' after 'only' keys")?;
+        Ok(keys)
+    }
+
     // Helper methods for token management
 
     /// Check if at end of tokens
@@ -911,13 +1134,53 @@ impl<'a> NomlParser<'a> {
         }
     }
 
+    /// Collect the comments before the next entry, and whether a blank line
+    /// appears anywhere among them
+    fn collect_leading_trivia(&mut self) -> (Comments, bool) {
+        let mut comments = Comments::new();
+        let mut blank_line = self.blank_line_before();
+        while let Ok(token) = self.peek() {
+            match &token.kind {
+                TokenKind::Comment { text } => {
+                    comments.add_before(Comment {
+                        text: text.clone(),
+                        span: token.span,
+                        style: CommentStyle::Line,
+                    });
+                    self.pos += 1;
+                    blank_line |= self.blank_line_before();
+                }
+                TokenKind::Whitespace | TokenKind::Newline => {
+                    self.pos += 1;
+                }
+                _ => break,
+            }
+        }
+        (comments, blank_line)
+    }
+
+    /// Whether a blank line separates the next token from the previous one
+    fn blank_line_before(&self) -> bool {
+        match (self.pos.checked_sub(1), self.peek()) {
+            (Some(prev), Ok(token)) => token.span.start_line > self.tokens[prev].span.end_line + 1,
+            _ => false,
+        }
+    }
+
     /// Parse inline comment
     fn parse_inline_comment(&mut self) -> Result<Option<Comment>> {
         // Skip whitespace first
         self.skip_whitespace();
 
+        let previous_line = self
+            .pos
+            .checked_sub(1)
+            .map(|prev| self.tokens[prev].span.end_line);
+
         if let Ok(token) = self.peek() {
-            if let TokenKind::Comment { text } = &token.kind {
+            // Only a comment on the same line is inline
+            let same_line = previous_line.is_some_and(|line| token.span.start_line == line);
+            if let (TokenKind::Comment { text }, true) = (&token.kind, same_line) {
                 let comment = Comment {
                     text: text.clone(),
                     span: token.span,
@@ -932,6 +1195,16 @@ impl<'a> NomlParser<'a> {
     }
 }
 
+/// Attach comments and blank-line information collected before an entry
+fn attach_leading(entry: &mut TableEntry, leading: Comments, blank_line: bool) {
+    let mut before = leading.before;
+    before.append(&mut entry.comments.before);
+    entry.comments.before = before;
+    if blank_line {
+        entry.value.format.leading_whitespace = "\n".to_string();
+    }
+}
+
 /// Convert lexer string style to AST string style
 fn convert_string_style(style: &LexerStringStyle) -> StringStyle {
     match style {
@@ -1083,4 +1356,44 @@ key = "value"
             .iter()
             .any(|c| c.text.contains("Comment in section")));
     }
+
+    #[test]
+    fn parse_selective_includes() {
+        let source = r#"
+whole = include("shared.noml")
+db = include("shared.noml", "database")
+primary = include("shared.noml").database.primary
+creds = include("secrets.noml", only = ["user", "password"])
+"#;
+        let doc = parse_string(source, None).unwrap();
+        let AstValue::Table { entries, .. } = &doc.root.value else {
+            panic!("root is not a table");
+        };
+        let includes: Vec<_> = entries
+            .iter()
+            .map(|entry| match &entry.value.value {
+                AstValue::Include { subpath, only, .. } => (subpath.as_deref(), only.clone()),
+                other => panic!("not an include: {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            includes,
+            vec![
+                (None, None),
+                (Some("database"), None),
+                (Some("database.primary"), None),
+                (None, Some(vec!["user".to_string(), "password".to_string()])),
+            ]
+        );
+
+        assert!(parse_string(r#"x = include("a.noml", 42)"#, None).is_err());
+        assert!(parse_string(r#"x = include("a.noml", only = "user")"#, None).is_err());
+        let err =
+            parse_string(r#"x = include("a.noml", only = ["database.user"])"#, None).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(r#"include("file.noml", "database", only = ["user"])"#),
+            "{err}"
+        );
+    }
 }
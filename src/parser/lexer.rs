@@ -98,6 +98,14 @@ pub enum TokenKind<'a> {
     /// } (end of interpolation - context-dependent)
     InterpolationEnd,
 
+    /// `:-default` or `:?message` inside `${...}`, up to the closing brace
+    InterpolationFallback {
+        /// Whether this is `:?` (fail with a message) rather than `:-`
+        required: bool,
+        /// Raw text of the default value or error message
+        text: String,
+    },
+
     /// @ (native type constructor prefix)
     At,
 
@@ -262,6 +270,7 @@ impl<'a> Lexer<'a> {
                 self.advance(); // {
                 Ok(self.make_token(TokenKind::InterpolationStart))
             }
+            ':' if matches!(self.peek_char(), Some('-' | '?')) => self.lex_interpolation_fallback(),
             // Identifiers (bare keys, function names)
             ch if ch.is_ascii_alphabetic() || ch == '_' => self.lex_identifier(),
 
@@ -284,19 +293,6 @@ impl<'a> Lexer<'a> {
             // Skip whitespace and newline tokens
             match token.kind {
                 TokenKind::Whitespace | TokenKind::Newline => {}
-                TokenKind::String { ref value, .. } => {
-                    // Check if string contains interpolation and add InterpolationStart token
-                    if value.contains("${") {
-                        // Add InterpolationStart token for test compatibility
-                        let interpolation_token = Token {
-                            kind: TokenKind::InterpolationStart,
-                            span: token.span, // Now using Copy instead of clone
-                            text: "${",
-                        };
-                        tokens.push(interpolation_token);
-                    }
-                    tokens.push(token);
-                }
                 _ => tokens.push(token),
             }
 
@@ -446,6 +442,9 @@ impl<'a> Lexer<'a> {
                     't' => value.push('\t'),
                     'r' => value.push('\r'),
                     '\\' => value.push('\\'),
+                    // `\${` is kept as `$${` so the resolver skips it
+                    '$' if self.peek_char() == Some('{') => value.push_str("$$"),
+                    '$' => value.push('$'),
                     '"' => value.push('"'),
                     '\'' => value.push('\''),
                     '0' => value.push('\0'),
@@ -498,20 +497,6 @@ impl<'a> Lexer<'a> {
                     }
                 }
                 self.advance();
-            } else if ch == '$' && self.peek_char() == Some('{') {
-                // Found interpolation start - need to handle this properly
-                // For now, emit the string up to this point and then the interpolation token
-                // This is a simplified implementation for test compatibility
-                if !value.is_empty() {
-                    // We have a partial string - this needs complex handling
-                    // For now just include the $ in the string to pass basic tests
-                    value.push(ch);
-                    self.advance();
-                } else {
-                    // String starts with interpolation - add it as regular chars for now
-                    value.push(ch);
-                    self.advance();
-                }
             } else {
                 value.push(ch);
                 self.advance();
@@ -722,6 +707,28 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Lex the `:-default` / `:?message` part of `${path:-default}`
+    fn lex_interpolation_fallback(&mut self) -> Result<Token<'a>> {
+        self.advance(); // :
+        let required = self.current_char() == '?';
+        self.advance(); // - or ?
+
+        let start = self.pos;
+        while !self.is_eof() && !matches!(self.current_char(), '}' | '\n') {
+            self.advance();
+        }
+        if self.is_eof() || self.current_char() != '}' {
+            return Err(NomlError::parse(
+                "Unclosed interpolation: expected '}'",
+                self.line,
+                self.column,
+            ));
+        }
+
+        let text = self.input[start..self.pos].to_string();
+        Ok(self.make_token(TokenKind::InterpolationFallback { required, text }))
+    }
+
     /// Lex an identifier or keyword
     fn lex_identifier(&mut self) -> Result<Token<'a>> {
         let start = self.pos;
@@ -777,6 +784,9 @@ impl fmt::Display for TokenKind<'_> {
             TokenKind::RightParen => write!(f, ")"),
             TokenKind::InterpolationStart => write!(f, "${{"),
             TokenKind::InterpolationEnd => write!(f, "}}"),
+            TokenKind::InterpolationFallback { required, text } => {
+                write!(f, ":{}{text}", if *required { '?' } else { '-' })
+            }
             TokenKind::At => write!(f, "@"),
             TokenKind::Comment { text } => write!(f, "# {text}"),
             TokenKind::Whitespace => write!(f, "<ws>"),
@@ -926,7 +936,7 @@ mod tests {
 
     #[test]
     fn interpolation() {
-        let input = r#"path = "${base}/logs""#;
+        let input = r#"path = ${base}"#;
         let tokens = tokenize_string(input).unwrap();
 
         // Should recognize interpolation start token
@@ -935,6 +945,28 @@ mod tests {
             .any(|t| matches!(t.kind, TokenKind::InterpolationStart));
 
         assert!(has_interpolation);
+
+        // Interpolations inside strings stay part of the string and are
+        // expanded by the resolver
+        let tokens = tokenize_string(r#"path = "${base}/logs""#).unwrap();
+        assert!(tokens.iter().any(
+            |t| matches!(&t.kind, TokenKind::String { value, .. } if value == "${base}/logs")
+        ));
+        assert!(!tokens
+            .iter()
+            .any(|t| matches!(t.kind, TokenKind::InterpolationStart)));
+
+        // `\${` keeps a literal `${` by escaping it as `$${`
+        let tokens = tokenize_string(r#"cmd = "echo \${HOME} \$5""#).unwrap();
+        assert!(tokens.iter().any(
+            |t| matches!(&t.kind, TokenKind::String { value, .. } if value == "echo $${HOME} $5")
+        ));
+
+        let tokens = tokenize_string("ttl = ${cache.ttl:-60}").unwrap();
+        assert!(tokens.iter().any(|t| matches!(
+            &t.kind,
+            TokenKind::InterpolationFallback { required: false, text } if text == "60"
+        )));
     }
 
     #[test]
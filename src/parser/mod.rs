@@ -54,11 +54,12 @@
 //! ```
 
 pub mod ast;
+pub(crate) mod edit;
 pub mod grammar;
 pub mod lexer;
 
 // Re-export main parsing functions and types
-pub use ast::{AstNode, AstValue, Comment, Comments, Document, Key, Span, TableEntry};
+pub use ast::{AstNode, AstValue, Comment, Comments, Document, Fallback, Key, Span, TableEntry};
 pub use grammar::{parse_file, parse_string, NomlParser};
 pub use lexer::{Lexer, Token, TokenKind};
 
@@ -0,0 +1,411 @@
+//! # Include Caching
+//!
+//! Resolving a document reads and parses every file it includes. When the
+//! same base files are included many times, by one document or by many
+//! resolves, an [`IncludeCache`] in
+//! [`ResolverConfig::include_cache`](super::ResolverConfig) keeps each
+//! parsed file by canonical path. A cached file is reused while the
+//! loader reports the same modification time, or, when it can't tell or
+//! the time changed, while its contents hash the same. Files that resolve
+//! the same wherever they are included — plain values, no `env()`,
+//! includes, `${...}` references or native types — also keep their
+//! resolved values.
+//!
+//! Cloning the cache shares it, so resolvers and configurations built
+//! from one [`IncludeCache`] see each other's entries.
+//!
+//! ```rust
+//! use noml::{parse_string, IncludeCache, InMemoryLoader, Resolver};
+//!
+//! let cache = IncludeCache::new();
+//! let loader = InMemoryLoader::new().with_file("base.noml", "workers = 4");
+//! let document = parse_string(r#"base = include "base.noml""#, None)?;
+//!
+//! for _ in 0..2 {
+//!     let value = Resolver::new()
+//!         .with_loader(loader.clone())
+//!         .with_include_cache(cache.clone())
+//!         .resolve(&document)?;
+//!     assert_eq!(value.get("base.workers").unwrap().as_integer()?, 4);
+//! }
+//! assert_eq!(cache.len(), 1);
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use super::graph;
+use super::loader::{normalize, IncludeLoader};
+use super::manifest::sha256;
+use crate::error::Result;
+use crate::parser::ast::{AstNode, AstValue, Document};
+use crate::parser::parse_string;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[cfg(feature = "async")]
+use std::time::{Duration, Instant};
+
+/// Parsed included files shared between resolves, by canonical path
+#[derive(Debug, Clone, Default)]
+pub struct IncludeCache {
+    entries: Arc<Mutex<HashMap<PathBuf, Entry>>>,
+}
+
+/// An included file, read and parsed
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    /// Canonical path the entry is cached under
+    key: PathBuf,
+    /// Modification time reported by the loader before it was read
+    modified: Option<SystemTime>,
+    /// SHA-256 of the file's contents
+    pub(crate) hash: String,
+    /// Size of the file's contents in bytes
+    pub(crate) len: usize,
+    pub(crate) document: Arc<Document>,
+    /// Whether the document resolves to the same value wherever it is
+    /// included
+    standalone: bool,
+    /// The resolved value of a standalone document, once resolved
+    pub(crate) value: Option<Value>,
+}
+
+impl IncludeCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of files cached
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no file is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every cached file
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Forget the file at `path`, returning whether it was cached
+    pub fn invalidate(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let mut entries = self.entries.lock().unwrap();
+        let canonical = std::fs::canonicalize(path).ok();
+        let removed = canonical.is_some_and(|path| entries.remove(&path).is_some());
+        entries.remove(&normalize(path)).is_some() || removed
+    }
+
+    /// The file at `path`, from the cache while it is unchanged
+    pub(crate) fn load(
+        &self,
+        loader: &dyn IncludeLoader,
+        path: &Path,
+        from: Option<&Path>,
+    ) -> Result<Entry> {
+        let key = loader.canonicalize(path);
+        // Taken before reading, so a change made meanwhile is seen next time
+        let modified = loader.modified(path);
+        let cached = self.entries.lock().unwrap().get(&key).cloned();
+        if let (Some(cached), Some(_)) = (&cached, modified) {
+            if cached.modified == modified {
+                return Ok(cached.clone());
+            }
+        }
+
+        let source = loader.load(path, from)?;
+        let hash = sha256(source.as_bytes());
+        let entry = match cached {
+            Some(mut cached) if cached.hash == hash => {
+                cached.modified = modified;
+                cached
+            }
+            _ => Entry::parse(key.clone(), path, &source, hash, modified)?,
+        };
+        self.entries.lock().unwrap().insert(key, entry.clone());
+        Ok(entry)
+    }
+
+    /// Keep the resolved value of a standalone file, unless the file
+    /// changed since `entry` was read
+    pub(crate) fn store_value(&self, entry: &Entry, value: &Value) {
+        if !entry.standalone {
+            return;
+        }
+        if let Some(cached) = self.entries.lock().unwrap().get_mut(&entry.key) {
+            if cached.hash == entry.hash {
+                cached.value = Some(value.clone());
+            }
+        }
+    }
+}
+
+impl Entry {
+    /// Read and parse `path` without a cache
+    pub(crate) fn read(
+        loader: &dyn IncludeLoader,
+        path: &Path,
+        from: Option<&Path>,
+    ) -> Result<Self> {
+        let source = loader.load(path, from)?;
+        let hash = sha256(source.as_bytes());
+        Self::parse(path.to_path_buf(), path, &source, hash, None)
+    }
+
+    fn parse(
+        key: PathBuf,
+        path: &Path,
+        source: &str,
+        hash: String,
+        modified: Option<SystemTime>,
+    ) -> Result<Self> {
+        let document = parse_string(source, Some(path.display().to_string()))?;
+        Ok(Self {
+            key,
+            modified,
+            hash,
+            len: source.len(),
+            standalone: standalone(&document),
+            document: Arc::new(document),
+            value: None,
+        })
+    }
+}
+
+/// Whether `document` resolves the same wherever it is included: no
+/// `extends` or profiles, and only literal values
+fn standalone(document: &Document) -> bool {
+    let AstValue::Table { entries, .. } = &document.root.value else {
+        return false;
+    };
+    let special = entries.iter().any(|entry| {
+        entry.is_profile()
+            || matches!(entry.key.segments.as_slice(), [key] if key.name == "extends")
+    });
+    !special && literal(&document.root)
+}
+
+fn literal(node: &AstNode) -> bool {
+    match &node.value {
+        AstValue::Table { entries, .. } => entries.iter().all(|entry| literal(&entry.value)),
+        AstValue::Array { elements, .. } => elements.iter().all(literal),
+        AstValue::Include { .. }
+        | AstValue::IncludeDir { .. }
+        | AstValue::FunctionCall { .. }
+        | AstValue::Native { .. }
+        | AstValue::Interpolation { .. } => false,
+        _ => !graph::has_interpolation(node),
+    }
+}
+
+/// Remote includes fetched so far, kept for a time-to-live and then
+/// revalidated with the server's `ETag`
+///
+/// Cloning the cache shares it. Entries younger than the TTL are used
+/// without a request; older ones are fetched again with `If-None-Match`,
+/// and a `304 Not Modified` answer keeps the cached content for another
+/// TTL.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, Fetched>>>,
+}
+
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+struct Fetched {
+    content: String,
+    etag: Option<String>,
+    fetched: Instant,
+}
+
+#[cfg(feature = "async")]
+impl Default for HttpCache {
+    /// A cache keeping responses for five minutes
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300))
+    }
+}
+
+#[cfg(feature = "async")]
+impl HttpCache {
+    /// Create an empty cache whose entries are fresh for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+        }
+    }
+
+    /// How long an entry is used without asking the server
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The content fetched from `url`, if it is still fresh
+    pub fn get(&self, url: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(url)?;
+        (entry.fetched.elapsed() < self.ttl).then(|| entry.content.clone())
+    }
+
+    /// Keep content fetched from `url` along with its `ETag`, if any
+    pub fn insert(&self, url: impl Into<String>, content: impl Into<String>, etag: Option<String>) {
+        self.entries.lock().unwrap().insert(
+            url.into(),
+            Fetched {
+                content: content.into(),
+                etag,
+                fetched: Instant::now(),
+            },
+        );
+    }
+
+    /// Number of URLs cached, fresh or not
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no URL is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every cached response
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// The `ETag` to revalidate a cached response for `url` with
+    pub(crate) fn etag(&self, url: &str) -> Option<String> {
+        self.entries.lock().unwrap().get(url)?.etag.clone()
+    }
+
+    /// The cached content for `url` after the server answered that it is
+    /// unchanged, fresh for another TTL
+    pub(crate) fn revalidate(&self, url: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(url)?;
+        entry.fetched = Instant::now();
+        Some(entry.content.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::{InMemoryLoader, Resolver};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Files in memory with a settable modification time, counting reads
+    #[derive(Debug, Default)]
+    struct Timed {
+        files: Mutex<InMemoryLoader>,
+        modified: Mutex<Option<SystemTime>>,
+        loads: AtomicUsize,
+    }
+
+    impl IncludeLoader for Timed {
+        fn load(&self, path: &Path, from: Option<&Path>) -> Result<String> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            self.files.lock().unwrap().load(path, from)
+        }
+
+        fn modified(&self, _path: &Path) -> Option<SystemTime> {
+            *self.modified.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_include_cache() {
+        let loader = Arc::new(Timed::default());
+        loader
+            .files
+            .lock()
+            .unwrap()
+            .insert("base.noml", "workers = 4");
+        *loader.modified.lock().unwrap() = Some(SystemTime::UNIX_EPOCH);
+        let cache = IncludeCache::new();
+        let resolve = || {
+            let mut resolver = Resolver::new().with_include_cache(cache.clone());
+            resolver.config.loader = loader.clone();
+            let document = parse_string(
+                "a = include \"base.noml\"\nb = include \"./base.noml\"",
+                None,
+            )
+            .unwrap();
+            resolver.resolve(&document).unwrap()
+        };
+
+        // Both includes name one file, read once while its time is unchanged
+        assert_eq!(resolve().get("b.workers"), Some(&Value::Integer(4)));
+        resolve();
+        assert_eq!(loader.loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+
+        // A new time makes the file be read again; new contents are parsed
+        *loader.modified.lock().unwrap() = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+        loader
+            .files
+            .lock()
+            .unwrap()
+            .insert("base.noml", "workers = 8");
+        assert_eq!(resolve().get("a.workers"), Some(&Value::Integer(8)));
+        assert_eq!(loader.loads.load(Ordering::SeqCst), 2);
+
+        // Without a time, the contents are compared on every read
+        *loader.modified.lock().unwrap() = None;
+        loader
+            .files
+            .lock()
+            .unwrap()
+            .insert("base.noml", "workers = 16");
+        assert_eq!(resolve().get("a.workers"), Some(&Value::Integer(16)));
+
+        assert!(cache.invalidate("base.noml"));
+        assert!(cache.is_empty());
+
+        // Only files without references or functions keep their values
+        let plain = parse_string("workers = 4", None).unwrap();
+        let dynamic = parse_string("workers = env(\"WORKERS\")", None).unwrap();
+        assert!(standalone(&plain) && !standalone(&dynamic));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_http_cache() {
+        let cache = HttpCache::new(Duration::ZERO);
+        cache.insert(
+            "https://example.com/a.noml",
+            "x = 1",
+            Some("\"v1\"".to_string()),
+        );
+        assert_eq!(cache.get("https://example.com/a.noml"), None);
+        assert_eq!(
+            cache.etag("https://example.com/a.noml").as_deref(),
+            Some("\"v1\"")
+        );
+        assert_eq!(
+            cache.revalidate("https://example.com/a.noml").as_deref(),
+            Some("x = 1")
+        );
+
+        let cache = HttpCache::default();
+        cache
+            .clone()
+            .insert("https://example.com/b.noml", "y = 2", None);
+        assert_eq!(
+            cache.get("https://example.com/b.noml").as_deref(),
+            Some("y = 2")
+        );
+    }
+}
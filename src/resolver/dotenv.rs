@@ -0,0 +1,442 @@
+//! # `.env` Files
+//!
+//! [`DotEnv`] reads the `.env` files most local setups keep next to their
+//! configuration, so `env()` sees their variables without a separate crate:
+//!
+//! ```text
+//! # Comments and blank lines are skipped
+//! export APP_ENV=development
+//! DATABASE_HOST=localhost          # unquoted values end at a comment
+//! DATABASE_URL="postgres://app@${DATABASE_HOST}/app"
+//! GREETING='no ${expansion} in single quotes'
+//! CERT="-----BEGIN CERTIFICATE-----
+//! MIIB...
+//! -----END CERTIFICATE-----"
+//! ```
+//!
+//! `${VAR}` in unquoted and double-quoted values expands to a variable set
+//! earlier in the file, or else to nothing; with
+//! [`DotEnv::with_process_env`] it falls back to the process environment.
+//! Double-quoted values also understand `\n`, `\r`, `\t`, `\"`, `\\` and `\$`.
+//!
+//! ```rust
+//! use noml::{parse_string, DotEnv, EnvPrecedence, Resolver};
+//!
+//! let dotenv = DotEnv::parse("export DATABASE_HOST=localhost\nDATABASE_PORT=5432\n")?;
+//! let document = parse_string(r#"host = env("DATABASE_HOST")"#, None)?;
+//! let value = Resolver::new()
+//!     .with_dotenv(&dotenv, EnvPrecedence::DotEnv)
+//!     .resolve(&document)?;
+//! assert_eq!(value.get("host").unwrap().as_string()?, "localhost");
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use crate::error::{NomlError, Result};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Which wins when a `.env` file and the process environment both set a
+/// variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvPrecedence {
+    /// The process environment wins, so deployments can override `.env`
+    #[default]
+    Process,
+    /// The `.env` file wins
+    DotEnv,
+}
+
+/// Variables read from `.env` files, in the order they were set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotEnv {
+    vars: IndexMap<String, String>,
+    /// Whether `${VAR}` falls back to the process environment
+    process_env: bool,
+}
+
+impl DotEnv {
+    /// Create an empty set of variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the text of a `.env` file
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut dotenv = Self::new();
+        dotenv.extend_from(source)?;
+        Ok(dotenv)
+    }
+
+    /// Read a `.env` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut dotenv = Self::new();
+        dotenv.load_file(path)?;
+        Ok(dotenv)
+    }
+
+    /// Let `${VAR}` in files read from now on expand variables they don't
+    /// set from the process environment; hermetic setups leave this off
+    pub fn with_process_env(mut self, fallback: bool) -> Self {
+        self.process_env = fallback;
+        self
+    }
+
+    /// Read a `.env` file over these variables, replacing any it sets again
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| NomlError::io(path.display().to_string(), e))?;
+        self.extend_from(&source)
+    }
+
+    /// The value of `name`, if set
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    /// Set `name`, replacing any earlier value
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.vars.insert(name.into(), value.into());
+    }
+
+    /// The variables and their values, in the order they were set
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// The number of variables
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Whether no variables are set
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Write the variables as a `.env` file, prefixing each line with
+    /// `export ` if `export` is set so a shell can `source` it
+    pub fn render(&self, export: bool) -> String {
+        let prefix = if export { "export " } else { "" };
+        let mut output = String::new();
+        for (name, value) in &self.vars {
+            output.push_str(prefix);
+            output.push_str(name);
+            output.push('=');
+            output.push_str(&quote(value));
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Lay these variables over `env` as `precedence` says
+    pub(crate) fn overlay(&self, env: &mut HashMap<String, String>, precedence: EnvPrecedence) {
+        for (name, value) in &self.vars {
+            if precedence == EnvPrecedence::DotEnv || !env.contains_key(name) {
+                env.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    fn extend_from(&mut self, source: &str) -> Result<()> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+        };
+        while let Some((name, value)) = parser.assignment(self)? {
+            self.vars.insert(name, value);
+        }
+        Ok(())
+    }
+}
+
+/// Quote `value` for a `.env` file unless it is safe bare
+fn quote(value: &str) -> String {
+    let bare = |c: char| c.is_ascii_alphanumeric() || "_-./:@,+".contains(c);
+    if !value.is_empty() && value.chars().all(bare) {
+        return value.to_string();
+    }
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    /// The next `NAME=value`, or `None` at the end of the file
+    fn assignment(&mut self, vars: &DotEnv) -> Result<Option<(String, String)>> {
+        self.skip_blank_lines();
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let mut name = self.name();
+        if name == "export" && matches!(self.peek(), Some(' ' | '\t')) {
+            self.skip_spaces();
+            name = self.name();
+        }
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("Expected a variable name"));
+        }
+        self.skip_spaces();
+        if self.peek() != Some('=') {
+            return Err(self.error(format!("Expected '=' after '{name}'")));
+        }
+        self.bump();
+        self.skip_spaces();
+        let value = match self.peek() {
+            Some('\'') => self.single_quoted()?,
+            Some('"') => self.double_quoted(vars)?,
+            _ => self.unquoted(vars)?,
+        };
+        self.end_of_line()?;
+        Ok(Some((name, value)))
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+        name
+    }
+
+    fn single_quoted(&mut self) -> Result<String> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('\'') => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err(NomlError::parse("Unterminated quoted value", line, column)),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self, vars: &DotEnv) -> Result<String> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.bump();
+                    return Ok(value);
+                }
+                Some('\\') => {
+                    self.bump();
+                    match self.bump() {
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some('t') => value.push('\t'),
+                        Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                        Some(c) => {
+                            value.push('\\');
+                            value.push(c);
+                        }
+                        None => break,
+                    }
+                }
+                Some('$') => self.expansion(vars, &mut value)?,
+                Some(c) => {
+                    value.push(c);
+                    self.bump();
+                }
+                None => break,
+            }
+        }
+        Err(NomlError::parse("Unterminated quoted value", line, column))
+    }
+
+    fn unquoted(&mut self, vars: &DotEnv) -> Result<String> {
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '\n' => break,
+                '#' if value.is_empty() || value.ends_with([' ', '\t']) => break,
+                '$' => self.expansion(vars, &mut value)?,
+                c => {
+                    value.push(c);
+                    self.bump();
+                }
+            }
+        }
+        Ok(value.trim_end().to_string())
+    }
+
+    /// Expand a `${VAR}` at the current `$` into `value`; a `$` not
+    /// followed by `{` is kept as it is
+    fn expansion(&mut self, vars: &DotEnv, value: &mut String) -> Result<()> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        if self.peek() != Some('{') {
+            value.push('$');
+            return Ok(());
+        }
+        self.bump();
+        let name = self.name();
+        if self.bump() != Some('}') || name.is_empty() {
+            return Err(NomlError::parse(
+                "Expected '${NAME}' in expansion",
+                line,
+                column,
+            ));
+        }
+        match vars.get(&name) {
+            Some(found) => value.push_str(found),
+            None if vars.process_env => value.push_str(&std::env::var(&name).unwrap_or_default()),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Allow only spaces and a comment before the next line
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('#') => {
+                while !matches!(self.peek(), None | Some('\n')) {
+                    self.bump();
+                }
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("Unexpected '{c}' after value"))),
+        }
+    }
+
+    fn skip_blank_lines(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r')) {
+            self.bump();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> NomlError {
+        NomlError::parse(message, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dotenv() {
+        let source = r#"
+# Local development
+export APP_ENV=development
+HOST = localhost   # trailing comment
+URL="http://${HOST}:8080/#top"
+RAW='${HOST} stays \n as is'
+ESCAPED="tab\there \${HOST} \"quoted\""
+EMPTY=
+CERT="line one
+line two"
+TAG=v1#not-a-comment
+"#;
+        let dotenv = DotEnv::parse(source).unwrap();
+        assert_eq!(dotenv.get("APP_ENV"), Some("development"));
+        assert_eq!(dotenv.get("HOST"), Some("localhost"));
+        assert_eq!(dotenv.get("URL"), Some("http://localhost:8080/#top"));
+        assert_eq!(dotenv.get("RAW"), Some("${HOST} stays \\n as is"));
+        assert_eq!(dotenv.get("ESCAPED"), Some("tab\there ${HOST} \"quoted\""));
+        assert_eq!(dotenv.get("EMPTY"), Some(""));
+        assert_eq!(dotenv.get("CERT"), Some("line one\nline two"));
+        assert_eq!(dotenv.get("TAG"), Some("v1#not-a-comment"));
+        assert_eq!(dotenv.len(), 8);
+
+        // Rendering reads back the same
+        assert_eq!(DotEnv::parse(&dotenv.render(true)).unwrap(), dotenv);
+        assert!(dotenv.render(false).starts_with("APP_ENV=development\n"));
+
+        let error = DotEnv::parse("A=1\nB \"2\"").unwrap_err();
+        assert!(matches!(error, NomlError::Parse { line: 2, .. }));
+        assert!(DotEnv::parse("A=\"open").is_err());
+        assert!(DotEnv::parse("A=${B").is_err());
+    }
+
+    #[test]
+    fn test_process_env_fallback() {
+        std::env::set_var("NOML_TEST_DOTENV_HOME", "/home/app");
+        let source = "DATA=${NOML_TEST_DOTENV_HOME}/data";
+        assert_eq!(DotEnv::parse(source).unwrap().get("DATA"), Some("/data"));
+
+        let mut dotenv = DotEnv::new().with_process_env(true);
+        dotenv.extend_from(source).unwrap();
+        assert_eq!(dotenv.get("DATA"), Some("/home/app/data"));
+    }
+
+    #[test]
+    fn test_overlay_precedence() {
+        let dotenv = DotEnv::parse("A=file\nB=file").unwrap();
+        let process = HashMap::from([("A".to_string(), "process".to_string())]);
+
+        let mut env = process.clone();
+        dotenv.overlay(&mut env, EnvPrecedence::Process);
+        assert_eq!(env["A"], "process");
+        assert_eq!(env["B"], "file");
+
+        let mut env = process;
+        dotenv.overlay(&mut env, EnvPrecedence::DotEnv);
+        assert_eq!(env["A"], "file");
+    }
+}
@@ -0,0 +1,419 @@
+//! # Encrypted Values
+//!
+//! With the `encryption` feature, secrets can be committed inside NOML files
+//! as `@encrypted("...")` values. Each is the base64 of a random nonce
+//! followed by the ChaCha20-Poly1305 ciphertext of the text, so any change
+//! to it is detected. The resolver decrypts them to [`Secret`] values with
+//! the key from a [`KeyProvider`]: the [`KEY_ENV`] environment variable by
+//! default, or one set with
+//! [`Resolver::with_key_provider`](super::Resolver::with_key_provider).
+//!
+//! ```rust
+//! use noml::{encrypt_paths, parse_raw, serialize_document, EncryptionKey, Resolver};
+//!
+//! let key = EncryptionKey::generate();
+//! let mut document = parse_raw("# Primary database\npassword = \"hunter2\"\n")?;
+//! encrypt_paths(&mut document, &["password"], &key)?;
+//! assert!(serialize_document(&document)?.contains("password = @encrypted(\""));
+//!
+//! let value = Resolver::new().with_key_provider(key).resolve(&document)?;
+//! assert_eq!(value.get("password").unwrap().as_secret()?.expose(), "hunter2");
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+//!
+//! `noml keygen`, `noml encrypt` and `noml decrypt` do the same from the
+//! command line, keeping the rest of the file as written.
+
+use super::native::{NativeResolver, NativeType};
+use crate::error::{NomlError, Result};
+use crate::parser::ast::{AstNode, AstValue, Document, Span, StringStyle};
+use crate::schema::FieldType;
+use crate::value::{Secret, Value};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Environment variable the key is read from unless another
+/// [`KeyProvider`] is set: the base64 of 32 random bytes
+pub const KEY_ENV: &str = "NOML_ENCRYPTION_KEY";
+
+/// Size of a ChaCha20-Poly1305 nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit key for encrypting and decrypting values
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Generate a random key
+    pub fn generate() -> Self {
+        Self(ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    /// Read a key written by [`EncryptionKey::to_base64`]
+    pub fn from_base64(text: &str) -> Result<Self> {
+        STANDARD
+            .decode(text.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(Self)
+            .ok_or_else(|| {
+                NomlError::validation("An encryption key must be 32 bytes, base64-encoded")
+            })
+    }
+
+    /// The key as base64, for key files and environment variables
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    /// Encrypt `text` to the argument of an `@encrypted("...")` value
+    pub fn encrypt(&self, text: &str) -> String {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, text.as_bytes())
+            .expect("encrypting into memory can't fail");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        STANDARD.encode(sealed)
+    }
+
+    /// Decrypt the argument of an `@encrypted("...")` value; fails if it was
+    /// encrypted with another key or changed since
+    pub fn decrypt(&self, encrypted: &str) -> Result<Secret> {
+        let sealed = STANDARD
+            .decode(encrypted.trim())
+            .map_err(|_| NomlError::validation("Encrypted value is not valid base64"))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(NomlError::validation("Encrypted value is too short"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let text = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                NomlError::validation(
+                    "Failed to decrypt value: wrong key, or the value was modified",
+                )
+            })?;
+        String::from_utf8(text)
+            .map(Secret::new)
+            .map_err(|_| NomlError::validation("Decrypted value is not valid UTF-8"))
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey([redacted])")
+    }
+}
+
+/// Where the key for `@encrypted("...")` values comes from
+pub trait KeyProvider: fmt::Debug + Send + Sync {
+    /// The key to decrypt with
+    fn key(&self) -> Result<EncryptionKey>;
+}
+
+impl KeyProvider for EncryptionKey {
+    fn key(&self) -> Result<EncryptionKey> {
+        Ok(self.clone())
+    }
+}
+
+/// Reads a base64 key from a file
+#[derive(Debug, Clone)]
+pub struct KeyFile {
+    path: PathBuf,
+}
+
+impl KeyFile {
+    /// Read the key from the file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl KeyProvider for KeyFile {
+    fn key(&self) -> Result<EncryptionKey> {
+        let text = std::fs::read_to_string(&self.path)
+            .map_err(|e| NomlError::io(self.path.display().to_string(), e))?;
+        EncryptionKey::from_base64(&text)
+    }
+}
+
+/// Reads a base64 key from an environment variable, [`KEY_ENV`] by default
+#[derive(Debug, Clone)]
+pub struct EnvKey {
+    var: String,
+}
+
+impl EnvKey {
+    /// Read the key from the environment variable `var`
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl Default for EnvKey {
+    fn default() -> Self {
+        Self::new(KEY_ENV)
+    }
+}
+
+impl KeyProvider for EnvKey {
+    fn key(&self) -> Result<EncryptionKey> {
+        let text = std::env::var(&self.var).map_err(|_| NomlError::env_var(&self.var, false))?;
+        EncryptionKey::from_base64(&text)
+    }
+}
+
+/// The `@encrypted("...")` native type, decrypting with a key provider
+struct Encrypted(Arc<dyn KeyProvider>);
+
+impl NativeType for Encrypted {
+    fn resolve(&self, args: &[Value]) -> Result<Value> {
+        match args {
+            [Value::String(encrypted)] => self.0.key()?.decrypt(encrypted).map(Value::Secret),
+            _ => Err(NomlError::validation(
+                "@encrypted() requires exactly 1 string argument",
+            )),
+        }
+    }
+
+    fn field_type(&self) -> Option<FieldType> {
+        Some(FieldType::Secret)
+    }
+}
+
+/// The native resolver for `@encrypted("...")` using `provider`
+pub(crate) fn native(provider: Arc<dyn KeyProvider>) -> NativeResolver {
+    NativeResolver::from_type(Encrypted(provider))
+}
+
+/// Encrypt the strings at `paths` in place, keeping everything else in the
+/// document as written; values already encrypted are left alone. Returns
+/// the number of values encrypted.
+pub fn encrypt_paths(
+    document: &mut Document,
+    paths: &[&str],
+    key: &EncryptionKey,
+) -> Result<usize> {
+    let mut count = 0;
+    for path in paths {
+        let node = document
+            .get_node_mut(path)
+            .ok_or_else(|| NomlError::key_not_found(*path))?;
+        if is_encrypted(node) {
+            continue;
+        }
+        // `@secret("...")` keeps its form around the encrypted value, so
+        // decrypting restores it
+        let wrapped =
+            matches!(&node.value, AstValue::Native { type_name, .. } if type_name == "secret");
+        let target = match &mut node.value {
+            AstValue::Native { args, .. } if wrapped => match args.as_mut_slice() {
+                [arg] => arg,
+                _ => return Err(NomlError::validation_at("Malformed @secret() value", *path)),
+            },
+            _ => node,
+        };
+        let text = match &target.value {
+            AstValue::String { value, .. } if value.contains("${") => {
+                return Err(NomlError::validation_at(
+                    "Can't encrypt a string with interpolations",
+                    *path,
+                ))
+            }
+            AstValue::String { value, .. } => key.encrypt(value),
+            _ if wrapped => {
+                return Err(NomlError::validation_at("Malformed @secret() value", *path))
+            }
+            _ => {
+                return Err(NomlError::validation_at(
+                    "Only strings can be encrypted",
+                    *path,
+                ))
+            }
+        };
+        target.value = encrypted(text, target.span);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Decrypt the `@encrypted("...")` values at `paths`, or every one in the
+/// document when `paths` is `None`, back to the strings or `@secret("...")`
+/// values they were. Returns the number of values decrypted.
+pub fn decrypt_paths(
+    document: &mut Document,
+    paths: Option<&[&str]>,
+    key: &EncryptionKey,
+) -> Result<usize> {
+    let Some(paths) = paths else {
+        return decrypt_all(&mut document.root, key);
+    };
+    let mut count = 0;
+    for path in paths {
+        let node = document
+            .get_node_mut(path)
+            .ok_or_else(|| NomlError::key_not_found(*path))?;
+        if !is_encrypted(node) {
+            return Err(NomlError::validation_at("Value is not encrypted", *path));
+        }
+        count += decrypt_all(node, key)?;
+    }
+    Ok(count)
+}
+
+/// Decrypt every encrypted value in `node` and below
+fn decrypt_all(node: &mut AstNode, key: &EncryptionKey) -> Result<usize> {
+    if let Some(encrypted) = encrypted_arg(node) {
+        let secret = key.decrypt(encrypted)?;
+        // The text is literal, so a `${` in it must not become a reference
+        node.value = string(secret.into_exposed().replace("${", "$${"));
+        return Ok(1);
+    }
+    let mut count = 0;
+    match &mut node.value {
+        AstValue::Table { entries, .. } => {
+            for entry in entries {
+                count += decrypt_all(&mut entry.value, key)?;
+            }
+        }
+        AstValue::Array { elements, .. } | AstValue::Native { args: elements, .. } => {
+            for element in elements {
+                count += decrypt_all(element, key)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(count)
+}
+
+/// Whether `node` is `@encrypted("...")` or `@secret(@encrypted("..."))`
+fn is_encrypted(node: &AstNode) -> bool {
+    match &node.value {
+        AstValue::Native { type_name, args } if type_name == "secret" => {
+            matches!(args.as_slice(), [arg] if encrypted_arg(arg).is_some())
+        }
+        _ => encrypted_arg(node).is_some(),
+    }
+}
+
+/// The argument of an `@encrypted("...")` node
+fn encrypted_arg(node: &AstNode) -> Option<&str> {
+    match &node.value {
+        AstValue::Native { type_name, args } if type_name == "encrypted" => match args.as_slice() {
+            [AstNode {
+                value: AstValue::String { value, .. },
+                ..
+            }] => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// An `@encrypted("...")` value holding `encrypted`
+fn encrypted(encrypted: String, span: Span) -> AstValue {
+    AstValue::Native {
+        type_name: "encrypted".to_string(),
+        args: vec![AstNode::new(string(encrypted), span)],
+    }
+}
+
+fn string(value: String) -> AstValue {
+    AstValue::String {
+        has_escapes: value.contains(['"', '\\', '\n', '\r', '\t']),
+        value,
+        style: StringStyle::Double,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_string;
+    use crate::resolver::Resolver;
+    use crate::serializer::serialize_document;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = EncryptionKey::generate();
+        let encrypted = key.encrypt("hunter2");
+        assert_ne!(key.encrypt("hunter2"), encrypted, "nonces must differ");
+        assert_eq!(key.decrypt(&encrypted).unwrap().expose(), "hunter2");
+        assert_eq!(EncryptionKey::from_base64(&key.to_base64()).unwrap(), key);
+        assert!(!format!("{key:?}").contains(&key.to_base64()));
+
+        // Another key or a changed value fails
+        assert!(EncryptionKey::generate().decrypt(&encrypted).is_err());
+        let mut tampered = STANDARD.decode(&encrypted).unwrap();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.decrypt(&STANDARD.encode(tampered)).is_err());
+        assert!(EncryptionKey::from_base64("c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_document_in_place() {
+        let key = EncryptionKey::generate();
+        let source = "# Database\n[db]\nuser = \"app\" # login\npassword = \"hunter2\" # rotate monthly\ntoken = @secret(\"t0k\")\n";
+        let mut document = parse_string(source, None).unwrap();
+        let count = encrypt_paths(&mut document, &["db.password", "db.token"], &key).unwrap();
+        assert_eq!(count, 2);
+
+        let encrypted = serialize_document(&document).unwrap();
+        assert!(!encrypted.contains("hunter2") && !encrypted.contains("t0k"));
+        assert!(encrypted.contains("token = @secret(@encrypted(\""));
+        assert_eq!(
+            encrypt_paths(&mut document, &["db.token"], &key).unwrap(),
+            0
+        );
+        assert!(encrypted.starts_with("# Database\n[db]\nuser = \"app\" # login\n"));
+        assert!(encrypted.contains("# rotate monthly"));
+        assert!(encrypt_paths(&mut document, &["db.user.name"], &key).is_err());
+
+        // Resolving decrypts to secrets; the default key source is unset here
+        let document = parse_string(&encrypted, None).unwrap();
+        let value = Resolver::new()
+            .with_key_provider(key.clone())
+            .resolve(&document)
+            .unwrap();
+        let password = value.get("db.password").unwrap();
+        assert_eq!(password.as_secret().unwrap().expose(), "hunter2");
+        let token = value.get("db.token").unwrap();
+        assert_eq!(token.as_secret().unwrap().expose(), "t0k");
+        assert!(Resolver::new()
+            .with_key_provider(EnvKey::new("NOML_TEST_UNSET_KEY"))
+            .resolve(&document)
+            .is_err());
+
+        let mut document = parse_string(&encrypted, None).unwrap();
+        assert_eq!(decrypt_paths(&mut document, None, &key).unwrap(), 2);
+        let decrypted = serialize_document(&document).unwrap();
+        assert!(decrypted.contains("password = \"hunter2\" # rotate monthly"));
+        assert!(decrypted.contains("token = @secret(\"t0k\")"));
+
+        // Decrypted text stays literal
+        let source = format!("cmd = @encrypted(\"{}\")", key.encrypt("echo ${HOME}"));
+        let mut document = parse_string(&source, None).unwrap();
+        decrypt_paths(&mut document, Some(&["cmd"]), &key).unwrap();
+        let decrypted = serialize_document(&document).unwrap();
+        assert!(decrypted.contains("cmd = \"echo $${HOME}\""), "{decrypted}");
+        let value = crate::parse(&decrypted).unwrap();
+        assert_eq!(
+            value.get("cmd").unwrap().as_string().unwrap(),
+            "echo ${HOME}"
+        );
+    }
+}
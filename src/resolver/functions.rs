@@ -0,0 +1,353 @@
+//! # Function Registry
+//!
+//! Function calls such as `env("HOME")` are looked up by name in a
+//! [`FunctionRegistry`]. Applications can register their own functions, such
+//! as `region()` or `hostname()`, and remove the built-in ones
+//! (see [`stdlib`](super::stdlib) for the standard library).
+//!
+//! ```rust
+//! use noml::{parse_string, FunctionRegistry, Resolver, Value};
+//!
+//! let functions = FunctionRegistry::new()
+//!     .with_function("upper", 1..=1, |args, ctx| match &args[0] {
+//!         Value::String(s) => Ok(Value::String(s.to_uppercase())),
+//!         _ => Err(ctx.error("expected a string")),
+//!     });
+//!
+//! let document = parse_string(r#"name = upper("api")"#, None)?;
+//! let value = Resolver::new().with_functions(functions).resolve(&document)?;
+//! assert_eq!(value.get("name").unwrap().as_string()?, "API");
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use super::loader::IncludeLoader;
+use super::manifest::Manifest;
+use super::secrets::SecretProvider;
+use super::security::{LoadBudget, SecurityPolicy};
+use crate::error::{NomlError, Result};
+use crate::parser::ast::Span;
+use crate::value::{Secret, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Type alias for function implementations
+type FunctionFn = Arc<dyn Fn(&[Value], &ResolveContext<'_>) -> Result<Value> + Send + Sync>;
+
+/// What a function can see about the call being resolved
+pub struct ResolveContext<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) span: Span,
+    pub(crate) base_path: Option<&'a Path>,
+    pub(crate) from: Option<&'a Path>,
+    pub(crate) loader: &'a dyn IncludeLoader,
+    pub(crate) policy: &'a SecurityPolicy,
+    pub(crate) budget: &'a LoadBudget,
+    pub(crate) env_vars: Option<&'a HashMap<String, String>>,
+    pub(crate) allow_missing_env: bool,
+    pub(crate) hermetic: bool,
+    pub(crate) secrets: &'a HashMap<String, Arc<dyn SecretProvider>>,
+    pub(crate) manifest: &'a Mutex<Manifest>,
+}
+
+impl ResolveContext<'_> {
+    /// Name the function was called by
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Location of the call in the source
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Directory relative paths are resolved against, if known
+    pub fn base_path(&self) -> Option<&Path> {
+        self.base_path
+    }
+
+    /// Read a file through the resolver's [`IncludeLoader`], relative to
+    /// [`base_path`](ResolveContext::base_path); fails with
+    /// [`NomlError::Security`] when the security policy forbids it
+    pub fn read_file(&self, path: &str) -> Result<String> {
+        self.policy.check_include(path)?;
+        let resolved = self.loader.resolve(path, self.base_path);
+        self.policy.check_root(&resolved)?;
+        self.budget
+            .check_size(self.policy, path, self.loader.size(&resolved))?;
+        let source = self.loader.load(&resolved, self.from)?;
+        self.budget.charge(self.policy, path, source.len())?;
+        self.manifest
+            .lock()
+            .unwrap()
+            .record_file(&resolved, &source);
+        Ok(source)
+    }
+
+    /// Look up an environment variable, using the resolver's configured
+    /// variables instead of the process environment when set; fails with
+    /// [`NomlError::Security`] when the security policy forbids it, or when
+    /// resolution is hermetic and no variables were configured
+    pub fn env_var(&self, name: &str) -> Result<Option<String>> {
+        self.policy.check_env(name)?;
+        if self.hermetic && self.env_vars.is_none() {
+            return Err(NomlError::security(
+                format!("hermetic resolution can't read '{name}' from the process environment"),
+                name,
+            ));
+        }
+        let value = lookup_env(self.env_vars, name);
+        self.manifest
+            .lock()
+            .unwrap()
+            .record_env(name, value.as_deref());
+        Ok(value)
+    }
+
+    /// Whether missing environment variables resolve to `null`
+    pub fn allow_missing_env(&self) -> bool {
+        self.allow_missing_env
+    }
+
+    /// Look up `key` in the [`SecretProvider`] registered as `provider`
+    pub fn secret(&self, provider: &str, key: &str) -> Result<Option<Secret>> {
+        let found = self
+            .secrets
+            .get(provider)
+            .ok_or_else(|| self.error(format!("no secret provider named '{provider}'")))?;
+        found.get(key)
+    }
+
+    /// Create an error pointing at the function call
+    pub fn error(&self, message: impl Into<String>) -> NomlError {
+        NomlError::parse(
+            format!("{}(): {}", self.name, message.into()),
+            self.span.start_line,
+            self.span.start_column,
+        )
+    }
+}
+
+/// Look up `name` in `vars`, or in the process environment when unset
+pub(crate) fn lookup_env(vars: Option<&HashMap<String, String>>, name: &str) -> Option<String> {
+    match vars {
+        Some(vars) => vars.get(name).cloned(),
+        None => env::var(name).ok(),
+    }
+}
+
+#[derive(Clone)]
+struct Function {
+    min_args: usize,
+    max_args: Option<usize>,
+    call: FunctionFn,
+}
+
+impl Function {
+    fn check_arity(&self, count: usize, context: &ResolveContext<'_>) -> Result<()> {
+        let fits = count >= self.min_args && self.max_args.is_none_or(|max| count <= max);
+        if fits {
+            return Ok(());
+        }
+        let expected = match self.max_args {
+            Some(max) if max == self.min_args => format!("{max}"),
+            Some(max) => format!("{} to {max}", self.min_args),
+            None => format!("at least {}", self.min_args),
+        };
+        let noun = if expected == "1" {
+            "argument"
+        } else {
+            "arguments"
+        };
+        Err(context.error(format!("expected {expected} {noun}, got {count}")))
+    }
+}
+
+/// Named functions callable from NOML documents
+///
+/// Cloning a registry is cheap and keeps every registered function.
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, Function>,
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionRegistry {
+    /// Create a registry with the standard library: `env()`, `format()`,
+    /// `file()`, `hash()` and friends
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        super::stdlib::register(&mut registry);
+        registry
+    }
+
+    /// Create a registry without any functions, not even the built-ins
+    pub fn empty() -> Self {
+        Self {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Register a function taking a number of arguments within `arity`,
+    /// replacing any function with the same name
+    pub fn register<F>(
+        &mut self,
+        name: impl Into<String>,
+        arity: impl RangeBounds<usize>,
+        function: F,
+    ) -> &mut Self
+    where
+        F: Fn(&[Value], &ResolveContext<'_>) -> Result<Value> + Send + Sync + 'static,
+    {
+        let min_args = match arity.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let max_args = match arity.end_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => Some(n.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+        self.functions.insert(
+            name.into(),
+            Function {
+                min_args,
+                max_args,
+                call: Arc::new(function),
+            },
+        );
+        self
+    }
+
+    /// Builder-style [`FunctionRegistry::register`]
+    pub fn with_function<F>(
+        mut self,
+        name: impl Into<String>,
+        arity: impl RangeBounds<usize>,
+        function: F,
+    ) -> Self
+    where
+        F: Fn(&[Value], &ResolveContext<'_>) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.register(name, arity, function);
+        self
+    }
+
+    /// Remove a function, e.g. to disable `env()`. Returns whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    /// Whether a function is registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Names of all registered functions, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Call the function named in `context` after checking its arity
+    pub(crate) fn call(&self, args: &[Value], context: &ResolveContext<'_>) -> Result<Value> {
+        let function = self.functions.get(context.name).ok_or_else(|| {
+            NomlError::unknown_function(
+                context.name,
+                context.span.start_line,
+                context.span.start_column,
+            )
+        })?;
+        function.check_arity(args.len(), context)?;
+        let value = (function.call)(args, context)?;
+        // Text made from a secret stays secret
+        Ok(match value {
+            Value::String(text) if args.iter().any(Value::is_secret) => Value::secret(text),
+            value => value,
+        })
+    }
+}
+
+impl fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_string;
+    use crate::resolver::{Resolver, ResolverConfig};
+
+    #[test]
+    fn test_custom_and_disabled_functions() {
+        let mut functions = FunctionRegistry::new();
+        functions
+            .register("hostname", 0..=0, |_, _| Ok(Value::String("web-1".into())))
+            .register("path", 1.., |args, ctx| {
+                let parts = args
+                    .iter()
+                    .map(|arg| arg.as_string().map(str::to_string))
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(|_| ctx.error("arguments must be strings"))?;
+                Ok(Value::String(parts.join("/")))
+            });
+
+        // Registries clone with their custom functions intact
+        let config = ResolverConfig {
+            functions: functions.clone(),
+            env_vars: Some(HashMap::from([(
+                "HOME".to_string(),
+                "/home/app".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let document = parse_string(
+            r#"
+            host = hostname()
+            path = path(env("HOME"), "data", hostname())
+            "#,
+            None,
+        )
+        .unwrap();
+        let value = Resolver::with_config(config.clone())
+            .resolve(&document)
+            .unwrap();
+        assert_eq!(value.get("host").unwrap().as_string().unwrap(), "web-1");
+        assert_eq!(
+            value.get("path").unwrap().as_string().unwrap(),
+            "/home/app/data/web-1"
+        );
+
+        let document = parse_string("\nhost = hostname(1)", None).unwrap();
+        let err = Resolver::with_config(config)
+            .resolve(&document)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 2, column 8: hostname(): expected 0 arguments, got 1"
+        );
+
+        assert!(functions.remove("env"));
+        assert!(functions.contains("path") && !functions.contains("env"));
+        assert!(FunctionRegistry::empty().names().is_empty());
+        let document = parse_string(r#"home = env("HOME")"#, None).unwrap();
+        let err = Resolver::new()
+            .with_functions(functions)
+            .resolve(&document)
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown function 'env'"), "{err}");
+    }
+}
@@ -0,0 +1,409 @@
+//! # Interpolation Dependency Graph
+//!
+//! Values containing `${...}` references are set aside while the rest of the
+//! document (includes, `env()`, native types) is resolved. The references
+//! between them form a graph that is resolved in topological order, so a
+//! key may refer to any other key in the document, wherever it is defined.
+//! Cycles are reported with the full chain of key paths.
+
+use super::interpolation::{self, Part};
+use crate::error::{NomlError, Result};
+use crate::parser::ast::{AstNode, AstValue};
+use crate::value::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A value waiting for the values it references
+#[derive(Debug, Clone)]
+pub(crate) struct Pending {
+    /// Where the resolved value goes
+    pub path: Vec<String>,
+    /// The unresolved node
+    pub node: AstNode,
+    /// Mount point of the included file the node came from (empty for the
+    /// main document); references fall back to paths relative to it
+    pub scope: Vec<String>,
+    /// Included file the node came from, for paths relative to it
+    pub file: Option<PathBuf>,
+}
+
+/// The part of a selective include to keep, such as
+/// `include("shared.noml", "database")`
+///
+/// The whole file is resolved in its own layout, so its interpolations can
+/// refer to keys outside the selection; references from the rest of the
+/// document are mapped into that layout, and the selection replaces the
+/// mounted value once everything is resolved.
+#[derive(Debug, Clone)]
+pub(crate) struct Selection {
+    /// Where the include is mounted
+    pub mount: Vec<String>,
+    /// Sub-path of the file to mount there
+    pub selected: Vec<String>,
+    /// Keys to keep from the sub-path; all when `None`
+    pub only: Option<Vec<String>>,
+}
+
+impl Selection {
+    /// Mount point and sub-path together: where the selected value is while
+    /// the document is resolved
+    fn full(&self) -> Vec<String> {
+        self.mount.iter().chain(&self.selected).cloned().collect()
+    }
+
+    /// Whether the key starting `rest`, a path below the sub-path, is kept
+    fn keeps(&self, rest: &[String]) -> bool {
+        match (&self.only, rest.first()) {
+            (Some(only), Some(key)) => only.contains(key),
+            _ => true,
+        }
+    }
+
+    /// Check the selection against the included value before resolving
+    pub(crate) fn check(&self, included: &Value) -> std::result::Result<(), String> {
+        let missing =
+            |path: &[String]| format!("'{}' not found in the included file", path.join("."));
+        let selected = get_path(included, &self.selected).ok_or_else(|| missing(&self.selected))?;
+        let Some(only) = &self.only else {
+            return Ok(());
+        };
+        let Value::Table(table) = selected else {
+            return Err(format!(
+                "'only' needs a table, but '{}' is not one",
+                self.selected.join(".")
+            ));
+        };
+        match only.iter().find(|key| !table.contains_key(*key)) {
+            Some(key) => {
+                let mut path = self.selected.clone();
+                path.push(key.clone());
+                Err(missing(&path))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Where a path as seen from outside the include is while the document
+    /// is resolved; `None` for keys `only` leaves out
+    fn expand(&self, path: Vec<String>) -> Option<Vec<String>> {
+        let Some(rest) = path.strip_prefix(self.mount.as_slice()) else {
+            return Some(path);
+        };
+        if !self.keeps(rest) {
+            return None;
+        }
+        Some(
+            self.full()
+                .into_iter()
+                .chain(rest.iter().cloned())
+                .collect(),
+        )
+    }
+
+    /// Where a value resolved at `path` ends up once the selection is
+    /// applied; `None` if it is left out
+    pub(crate) fn collapse(&self, path: &[String]) -> Option<Vec<String>> {
+        let rest = path.strip_prefix(self.full().as_slice())?;
+        if !self.keeps(rest) {
+            return None;
+        }
+        Some(self.mount.iter().chain(rest).cloned().collect())
+    }
+
+    /// Apply the selection to `value`, the resolved value at `base`
+    pub(crate) fn apply(&self, base: &[String], value: &mut Value) {
+        let target = match self.mount.strip_prefix(base) {
+            Some(mount) => get_path_mut(value, mount),
+            // Only the selected value itself was copied
+            None if base == self.full() => {
+                self.narrow(value);
+                return;
+            }
+            None => return,
+        };
+        if let Some(target) = target {
+            let mut selected = get_path(target, &self.selected)
+                .cloned()
+                .unwrap_or(Value::Null);
+            self.narrow(&mut selected);
+            *target = selected;
+        }
+    }
+
+    /// Drop the keys `only` leaves out from the selected value
+    fn narrow(&self, value: &mut Value) {
+        if let (Some(only), Value::Table(table)) = (&self.only, value) {
+            table.retain(|key, _| only.contains(key));
+        }
+    }
+}
+
+/// Whether a node needs other values before it can be resolved
+pub(crate) fn has_interpolation(node: &AstNode) -> bool {
+    match &node.value {
+        AstValue::Interpolation { .. } => true,
+        AstValue::String { value, .. } => value.contains("${"),
+        AstValue::FunctionCall { args, .. } | AstValue::Native { args, .. } => {
+            args.iter().any(has_interpolation)
+        }
+        AstValue::Array { elements, .. } => elements.iter().any(has_interpolation),
+        AstValue::Table { entries, .. } => entries.iter().any(|e| has_interpolation(&e.value)),
+        _ => false,
+    }
+}
+
+/// Every `${...}` path referenced by a node
+pub(crate) fn references(node: &AstNode) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_references(node, &mut paths);
+    paths
+}
+
+fn collect_references(node: &AstNode, paths: &mut Vec<String>) {
+    match &node.value {
+        AstValue::Interpolation { path, .. } => paths.push(path.clone()),
+        AstValue::String { value, .. } => {
+            // Unclosed references are reported when the string is resolved
+            for part in interpolation::split(value).unwrap_or_default() {
+                if let Part::Reference(reference) = part {
+                    paths.push(reference.path);
+                }
+            }
+        }
+        AstValue::FunctionCall { args, .. } | AstValue::Native { args, .. } => {
+            for arg in args {
+                collect_references(arg, paths);
+            }
+        }
+        AstValue::Array { elements, .. } => {
+            for element in elements {
+                collect_references(element, paths);
+            }
+        }
+        AstValue::Table { entries, .. } => {
+            for entry in entries {
+                collect_references(&entry.value, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The absolute path a reference points to
+///
+/// Paths are looked up from the document root first, then relative to the
+/// scope of the included file the reference appears in. Paths into
+/// selective includes mounted outside that file are mapped into the
+/// included file's layout; keys they leave out have no target.
+pub(crate) fn target(
+    reference: &str,
+    scope: &[String],
+    selections: &[Selection],
+    root: &Value,
+) -> Option<Vec<String>> {
+    let expand = |path: Vec<String>| {
+        selections
+            .iter()
+            .rev()
+            .filter(|selection| !scope.starts_with(&selection.mount))
+            .try_fold(path, |path, selection| selection.expand(path))
+    };
+    let absolute = expand(reference.split('.').map(str::to_string).collect());
+    let found = |path: &Option<Vec<String>>| {
+        path.as_ref()
+            .is_some_and(|path| get_path(root, path).is_some())
+    };
+    if scope.is_empty() || found(&absolute) {
+        return absolute;
+    }
+    let scoped = expand(
+        scope
+            .iter()
+            .cloned()
+            .chain(reference.split('.').map(str::to_string))
+            .collect(),
+    );
+    if found(&scoped) {
+        scoped
+    } else {
+        absolute
+    }
+}
+
+/// Order pending values so each comes after everything it references
+pub(crate) fn resolution_order(
+    pending: &[Pending],
+    selections: &[Selection],
+    root: &Value,
+) -> Result<Vec<usize>> {
+    let by_path: BTreeMap<&[String], usize> = pending
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (item.path.as_slice(), i))
+        .collect();
+
+    // A reference depends on pending values at, above or below its target
+    let dependencies: Vec<Vec<usize>> = pending
+        .iter()
+        .map(|item| {
+            let mut deps = Vec::new();
+            for reference in references(&item.node) {
+                let Some(target) = target(&reference, &item.scope, selections, root) else {
+                    continue;
+                };
+                for len in 1..=target.len() {
+                    if let Some(&dep) = by_path.get(&target[..len]) {
+                        deps.push(dep);
+                    }
+                }
+                deps.extend(
+                    by_path
+                        .range(target.as_slice()..)
+                        .take_while(|(path, _)| path.starts_with(&target))
+                        .filter(|(path, _)| path.len() > target.len())
+                        .map(|(_, &dep)| dep),
+                );
+            }
+            deps
+        })
+        .collect();
+
+    let mut state = vec![Visit::New; pending.len()];
+    let mut order = Vec::with_capacity(pending.len());
+    let mut stack = Vec::new();
+    for start in 0..pending.len() {
+        visit(
+            start,
+            &dependencies,
+            &mut state,
+            &mut stack,
+            &mut order,
+            pending,
+        )?;
+    }
+    Ok(order)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+fn visit(
+    node: usize,
+    dependencies: &[Vec<usize>],
+    state: &mut [Visit],
+    stack: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+    pending: &[Pending],
+) -> Result<()> {
+    match state[node] {
+        Visit::Done => return Ok(()),
+        Visit::InProgress => {
+            let start = stack.iter().position(|&n| n == node).unwrap_or(0);
+            let chain: Vec<String> = stack[start..]
+                .iter()
+                .chain(std::iter::once(&node))
+                .map(|&n| pending[n].path.join("."))
+                .collect();
+            return Err(NomlError::circular_reference(chain.join(" -> ")));
+        }
+        Visit::New => {}
+    }
+
+    state[node] = Visit::InProgress;
+    stack.push(node);
+    for &dep in &dependencies[node] {
+        visit(dep, dependencies, state, stack, order, pending)?;
+    }
+    stack.pop();
+    state[node] = Visit::Done;
+    order.push(node);
+    Ok(())
+}
+
+/// Look up a value by path segments
+pub(crate) fn get_path<'a>(root: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(root, |current, segment| match current {
+            Value::Table(table) => table.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// Look up a value by path segments for changing it
+fn get_path_mut<'a>(root: &'a mut Value, path: &[String]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(root, |current, segment| match current {
+            Value::Table(table) => table.get_mut(segment),
+            Value::Array(items) => items.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// Store a value by path segments, creating tables along the way
+///
+/// Tables already at the path are merged with a new table rather than
+/// replaced, so `[a.b]` and a later `[a]` section both survive.
+pub(crate) fn set_path(root: &mut Value, path: &[String], value: Value) -> Result<()> {
+    let Some((last, parents)) = path.split_last() else {
+        *root = value;
+        return Ok(());
+    };
+
+    let mut current = root;
+    for segment in parents {
+        current = match current {
+            Value::Table(table) => table
+                .entry(segment.clone())
+                .or_insert_with(Value::empty_table),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| NomlError::validation(format!("Invalid array index '{segment}'")))?,
+            _ => {
+                return Err(NomlError::validation(format!(
+                    "Cannot set '{}': '{segment}' is not a table",
+                    path.join(".")
+                )))
+            }
+        };
+    }
+
+    match current {
+        Value::Table(table) => {
+            merge_into(table, last, value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| NomlError::validation(format!("Invalid array index '{last}'")))?;
+            *slot = value;
+            Ok(())
+        }
+        _ => Err(NomlError::validation(format!(
+            "Cannot set '{}': parent is not a table",
+            path.join(".")
+        ))),
+    }
+}
+
+fn merge_into(table: &mut BTreeMap<String, Value>, key: &str, value: Value) {
+    match (table.get_mut(key), value) {
+        (Some(Value::Table(existing)), Value::Table(new)) => {
+            for (child, value) in new {
+                merge_into(existing, &child, value);
+            }
+        }
+        (_, value) => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
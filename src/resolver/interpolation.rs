@@ -0,0 +1,119 @@
+//! # Interpolation Expressions
+//!
+//! Scanning of `${...}` references inside strings. `$${` is an escape for a
+//! literal `${` (written `\${` in double-quoted strings), so shell snippets
+//! and templates for other tools can be kept as-is.
+//!
+//! A reference may carry a shell-style fallback that applies when the value
+//! is missing, null or an empty string: `${cache.ttl:-60}` uses `60` instead
+//! and `${api.token:?set api.token}` fails with the given message.
+
+use crate::parser::ast::Fallback;
+use crate::value::Value;
+
+/// A `${...}` reference inside a string
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Reference {
+    /// Dotted path of the referenced value
+    pub path: String,
+    /// `:-` / `:?` fallback, if any
+    pub fallback: Option<Fallback>,
+}
+
+/// A piece of an interpolated string
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Part {
+    /// Literal text, with `$${` escapes turned into `${`
+    Text(String),
+    /// A reference to resolve
+    Reference(Reference),
+}
+
+/// Split a string into literal text and references
+///
+/// Fails with the byte offset of a `${` that is never closed.
+pub(crate) fn split(text: &str) -> Result<Vec<Part>, usize> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with("$${") {
+            literal.push_str("${");
+            i += 3;
+        } else if let Some(expression) = rest.strip_prefix("${") {
+            let end = expression.find('}').ok_or(i)?;
+            if !literal.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Reference(parse_reference(&expression[..end])));
+            i += 2 + end + 1;
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            literal.push(ch);
+            i += ch.len_utf8();
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(Part::Text(literal));
+    }
+    Ok(parts)
+}
+
+/// Parse the inside of `${...}` into a path and optional fallback
+pub(crate) fn parse_reference(expression: &str) -> Reference {
+    let operator = [":-", ":?"]
+        .iter()
+        .filter_map(|op| expression.find(op))
+        .min();
+
+    let Some(at) = operator else {
+        return Reference {
+            path: expression.trim().to_string(),
+            fallback: None,
+        };
+    };
+
+    let text = expression[at + 2..].to_string();
+    let fallback = if expression[at..].starts_with(":?") {
+        Fallback::Required(text)
+    } else {
+        Fallback::Default(text)
+    };
+    Reference {
+        path: expression[..at].trim().to_string(),
+        fallback: Some(fallback),
+    }
+}
+
+/// Whether a fallback applies to a looked-up value
+pub(crate) fn is_unset(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        Some(_) => false,
+    }
+}
+
+/// The value of a `:-` default used as a whole value
+///
+/// Numbers and booleans keep their type, so `port = ${port:-8080}` is an
+/// integer either way; anything else is a string.
+pub(crate) fn default_value(text: &str) -> Value {
+    let text = text.trim();
+    if let Ok(i) = text.parse::<i64>() {
+        Value::Integer(i)
+    } else if let Some(f) = text
+        .parse::<f64>()
+        .ok()
+        .filter(|_| text.contains(|c: char| c.is_ascii_digit()))
+    {
+        Value::Float(f)
+    } else if let Ok(b) = text.parse::<bool>() {
+        Value::Bool(b)
+    } else {
+        Value::String(text.to_string())
+    }
+}
@@ -0,0 +1,354 @@
+//! # Include Loaders
+//!
+//! `include "file.noml"` and `file("...")` read their files through the
+//! [`IncludeLoader`] in [`ResolverConfig::loader`](super::ResolverConfig).
+//! [`FsLoader`] reads from disk and is the default; [`InMemoryLoader`] and
+//! [`EmbeddedLoader`] serve files from memory, for hermetic tests, configs
+//! compiled into binaries or sandboxed loading.
+//!
+//! ```rust
+//! use noml::{parse_string, InMemoryLoader, Resolver};
+//!
+//! let loader = InMemoryLoader::new()
+//!     .with_file("database.noml", "host = \"db.internal\"\nport = 5432");
+//!
+//! let document = parse_string(r#"database = include "database.noml""#, None)?;
+//! let value = Resolver::new().with_loader(loader).resolve(&document)?;
+//! assert_eq!(value.get("database.port").unwrap().as_integer()?, 5432);
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use crate::error::{NomlError, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where included files are read from
+pub trait IncludeLoader: fmt::Debug + Send + Sync {
+    /// Read the file at `path`, included from the file `from` (`None` when
+    /// the including document has no file)
+    fn load(&self, path: &Path, from: Option<&Path>) -> Result<String>;
+
+    /// The path an include of `path` refers to, relative to the directory
+    /// `dir` of the including file or the configured base path
+    fn resolve(&self, path: &str, dir: Option<&Path>) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            dir.unwrap_or(Path::new(".")).join(path)
+        }
+    }
+
+    /// The files directly inside `dir`, for glob and directory includes;
+    /// loaders that can't list files refuse them
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        Err(NomlError::io(
+            dir.display().to_string(),
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "this loader can't list directories",
+            ),
+        ))
+    }
+
+    /// When the file at `path` was last changed, if the loader knows;
+    /// an [`IncludeCache`](super::IncludeCache) trusts an unchanged time
+    /// without reading the file again
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let _ = path;
+        None
+    }
+
+    /// The size in bytes of the file at `path`, if the loader knows; a file
+    /// too big for [`SecurityPolicy::max_include_bytes`](super::SecurityPolicy::max_include_bytes)
+    /// is refused without reading it
+    fn size(&self, path: &Path) -> Option<u64> {
+        let _ = path;
+        None
+    }
+
+    /// The path that names the same file as `path` whichever way it was
+    /// written, used to key the include cache
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        normalize(path)
+    }
+}
+
+/// Reads included files from disk
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsLoader;
+
+impl IncludeLoader for FsLoader {
+    fn load(&self, path: &Path, _from: Option<&Path>) -> Result<String> {
+        std::fs::read_to_string(path).map_err(|e| NomlError::io(path.display().to_string(), e))
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let error = |e| NomlError::io(dir.display().to_string(), e);
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(error)? {
+            let path = entry.map_err(error)?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn size(&self, path: &Path) -> Option<u64> {
+        std::fs::metadata(path).ok().map(|m| m.len())
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_| normalize(path))
+    }
+}
+
+/// Serves included files from a map of path to content
+///
+/// Paths are compared after removing `.` and `..` components, so
+/// `./config/../db.noml` finds a file added as `db.noml`.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryLoader {
+    files: HashMap<PathBuf, String>,
+}
+
+impl InMemoryLoader {
+    /// Create a loader without any files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a file
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+
+    /// Builder-style [`InMemoryLoader::insert`]
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+}
+
+impl<P: AsRef<Path>, S: Into<String>> FromIterator<(P, S)> for InMemoryLoader {
+    fn from_iter<I: IntoIterator<Item = (P, S)>>(files: I) -> Self {
+        let mut loader = Self::new();
+        for (path, contents) in files {
+            loader.insert(path, contents);
+        }
+        loader
+    }
+}
+
+impl IncludeLoader for InMemoryLoader {
+    fn load(&self, path: &Path, _from: Option<&Path>) -> Result<String> {
+        self.files
+            .get(&normalize(path))
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        Ok(files_in(self.files.keys(), dir))
+    }
+}
+
+/// Serves files compiled into the binary, typically with `include_str!`
+///
+/// ```rust
+/// use noml::EmbeddedLoader;
+///
+/// let loader = EmbeddedLoader::from_files(&[
+///     ("defaults.noml", "workers = 4"),
+///     // ("db.noml", include_str!("../config/db.noml")),
+/// ]);
+/// # let _ = loader;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedLoader {
+    files: HashMap<PathBuf, &'static str>,
+}
+
+impl EmbeddedLoader {
+    /// Create a loader without any files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a loader from `(path, contents)` pairs
+    pub fn from_files(files: &[(&str, &'static str)]) -> Self {
+        let mut loader = Self::new();
+        for (path, contents) in files {
+            loader = loader.with_file(path, contents);
+        }
+        loader
+    }
+
+    /// Add or replace a file
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: &'static str) -> Self {
+        self.files.insert(normalize(path.as_ref()), contents);
+        self
+    }
+}
+
+impl IncludeLoader for EmbeddedLoader {
+    fn load(&self, path: &Path, _from: Option<&Path>) -> Result<String> {
+        self.files
+            .get(&normalize(path))
+            .map(|contents| contents.to_string())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn list(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        Ok(files_in(self.files.keys(), dir))
+    }
+}
+
+/// The keys directly inside `dir`, as paths below `dir`
+fn files_in<'a>(keys: impl Iterator<Item = &'a PathBuf>, dir: &Path) -> Vec<PathBuf> {
+    let normalized = normalize(dir);
+    keys.filter(|key| key.parent() == Some(normalized.as_path()))
+        .filter_map(|key| key.file_name())
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+fn not_found(path: &Path) -> NomlError {
+    NomlError::io(
+        path.display().to_string(),
+        io::Error::new(io::ErrorKind::NotFound, "no such file in loader"),
+    )
+}
+
+/// `path` with `.` components dropped and `..` applied, without touching
+/// the filesystem
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_string;
+    use crate::resolver::{Resolver, ResolverConfig};
+    use crate::value::Value;
+    use std::sync::{Arc, Mutex};
+
+    /// Records which file each include was loaded from
+    #[derive(Debug, Default)]
+    struct Recording {
+        files: InMemoryLoader,
+        loads: Mutex<Vec<(PathBuf, Option<PathBuf>)>>,
+    }
+
+    impl IncludeLoader for Recording {
+        fn load(&self, path: &Path, from: Option<&Path>) -> Result<String> {
+            self.loads
+                .lock()
+                .unwrap()
+                .push((path.to_path_buf(), from.map(Path::to_path_buf)));
+            self.files.load(path, from)
+        }
+    }
+
+    #[test]
+    fn test_in_memory_includes() {
+        let files: InMemoryLoader = [
+            ("config/app.noml", "db = include \"db/main.noml\""),
+            (
+                "config/db/main.noml",
+                "pool = include \"../pool.noml\"\nurl = file(\"url.txt\")",
+            ),
+            ("config/db/url.txt", "postgres://db"),
+            ("config/pool.noml", "size = 5"),
+        ]
+        .into_iter()
+        .collect();
+        let loader = Recording {
+            files,
+            ..Default::default()
+        };
+
+        let document = parse_string(r#"app = include "config/app.noml""#, None).unwrap();
+        let mut resolver = Resolver::new().with_loader(loader);
+        let value = resolver.resolve(&document).unwrap();
+        assert_eq!(value.get("app.db.pool.size"), Some(&Value::Integer(5)));
+        assert_eq!(
+            value.get("app.db.url").unwrap().as_string().unwrap(),
+            "postgres://db"
+        );
+
+        let document = parse_string(r#"missing = include "nope.noml""#, None).unwrap();
+        let err = resolver.resolve(&document).unwrap_err();
+        assert!(err.to_string().contains("no such file in loader"), "{err}");
+
+        let embedded = EmbeddedLoader::from_files(&[("shared/base.noml", "workers = 4")]);
+        let document = parse_string(r#"base = include "./shared/base.noml""#, None).unwrap();
+        let value = Resolver::new()
+            .with_loader(embedded)
+            .resolve(&document)
+            .unwrap();
+        assert_eq!(value.get("base.workers"), Some(&Value::Integer(4)));
+    }
+
+    #[test]
+    fn test_loader_sees_including_file() {
+        let loader = Recording {
+            files: InMemoryLoader::new()
+                .with_file("/etc/app/db.noml", "pool = include \"pool.noml\"")
+                .with_file("/etc/app/pool.noml", "size = 5"),
+            ..Default::default()
+        };
+        let document = parse_string(
+            r#"db = include "db.noml""#,
+            Some("/etc/app/main.noml".to_string()),
+        )
+        .unwrap();
+        let mut config = ResolverConfig {
+            base_path: Some(PathBuf::from("/etc/app")),
+            ..Default::default()
+        };
+        let loader = Arc::new(loader);
+        config.loader = loader.clone();
+        Resolver::with_config(config).resolve(&document).unwrap();
+
+        let loads = loader.loads.lock().unwrap();
+        assert_eq!(
+            *loads,
+            vec![
+                (
+                    PathBuf::from("/etc/app/db.noml"),
+                    Some(PathBuf::from("/etc/app/main.noml"))
+                ),
+                (
+                    PathBuf::from("/etc/app/pool.noml"),
+                    Some(PathBuf::from("/etc/app/db.noml"))
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! # Lockfiles
+//!
+//! A [`Lockfile`] pins every remote include to the SHA-256 of its contents
+//! and keeps a vendored copy of each, so configurations with
+//! `include "https://..."` resolve the same way every time and without the
+//! network. `noml lock app.noml` fetches the remote includes and writes
+//! `noml.lock` with the copies in `noml-vendor/` beside it:
+//!
+//! ```noml
+//! # Generated by `noml lock`; pins remote includes to vendored copies
+//! version = 1
+//! includes = [
+//!     { url = "https://config.example.com/base.noml", sha256 = "9f86d0..." },
+//! ]
+//! ```
+//!
+//! Given a lockfile ([`Resolver::with_lockfile`](super::Resolver::with_lockfile)),
+//! [`Resolver::resolve`](super::Resolver::resolve) reads locked remote
+//! includes from their vendored copies. Async resolution fetches them and
+//! fails with [`NomlError::Security`] when the contents no longer match
+//! the lock or a URL isn't locked, or reads only the vendored copies when
+//! offline. Only a lockfile [`with_locking`](Lockfile::with_locking), as
+//! `noml lock` uses, takes in URLs it doesn't have yet.
+//!
+//! ```rust
+//! use noml::{parse_string, Lockfile, Resolver};
+//!
+//! let dir = tempfile::tempdir()?;
+//! let lockfile = Lockfile::new(dir.path().join("noml.lock"));
+//! lockfile.insert("https://config.example.com/base.noml", "workers = 4");
+//! lockfile.save()?;
+//!
+//! let document = parse_string(r#"base = include "https://config.example.com/base.noml""#, None)?;
+//! let value = Resolver::new()
+//!     .with_lockfile(Lockfile::load(dir.path().join("noml.lock"))?)
+//!     .resolve(&document)?;
+//! assert_eq!(value.get("base.workers").unwrap().as_integer()?, 4);
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::manifest::sha256;
+use crate::error::{NomlError, Result};
+use crate::value::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// File name of the lockfile `noml lock` writes beside a configuration
+pub const LOCKFILE: &str = "noml.lock";
+
+/// Directory beside the lockfile holding the vendored copies
+const VENDOR_DIR: &str = "noml-vendor";
+
+/// Remote includes pinned to content hashes, with vendored copies
+///
+/// Cloning the lockfile shares it, so URLs a resolver locks are written by
+/// [`Lockfile::save`] on any clone.
+#[derive(Debug, Clone)]
+pub struct Lockfile {
+    path: PathBuf,
+    entries: Arc<Mutex<BTreeMap<String, Locked>>>,
+    /// Whether URLs that aren't locked are locked when fetched
+    locking: bool,
+}
+
+/// A locked remote include
+#[derive(Debug, Clone)]
+struct Locked {
+    sha256: String,
+    content: String,
+}
+
+impl Lockfile {
+    /// An empty lockfile to be saved at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            entries: Arc::default(),
+            locking: false,
+        }
+    }
+
+    /// Lock URLs that aren't locked yet to the contents fetched for them,
+    /// instead of refusing them
+    pub fn with_locking(mut self, locking: bool) -> Self {
+        self.locking = locking;
+        self
+    }
+
+    /// Read the lockfile at `path` and its vendored copies, checking each
+    /// copy against the hash it was locked with
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let lockfile = Self::new(path);
+        let name = lockfile.path.display().to_string();
+        let source =
+            fs::read_to_string(&lockfile.path).map_err(|e| NomlError::io(name.clone(), e))?;
+        let value = crate::parse(&source)?;
+        match value.get("version") {
+            Some(Value::Integer(1)) => {}
+            _ => {
+                return Err(NomlError::validation(format!(
+                    "Unsupported lockfile version in '{name}'"
+                )))
+            }
+        }
+
+        let includes = match value.get("includes") {
+            Some(includes) => includes.as_array()?.as_slice(),
+            None => &[],
+        };
+        let mut entries = BTreeMap::new();
+        for include in includes {
+            let field = |key: &str| -> Result<String> {
+                let value = include.get(key).ok_or_else(|| {
+                    NomlError::validation(format!(
+                        "Lockfile '{name}' has an include without '{key}'"
+                    ))
+                })?;
+                Ok(value.as_string()?.to_string())
+            };
+            let (url, hash) = (field("url")?, field("sha256")?);
+            let vendored = lockfile.vendored(&hash);
+            let content = fs::read_to_string(&vendored)
+                .map_err(|e| NomlError::io(vendored.display().to_string(), e))?;
+            if sha256(content.as_bytes()) != hash {
+                return Err(NomlError::security(
+                    format!(
+                        "Vendored copy '{}' doesn't match the sha256 it was locked with",
+                        vendored.display()
+                    ),
+                    url,
+                ));
+            }
+            entries.insert(
+                url,
+                Locked {
+                    sha256: hash,
+                    content,
+                },
+            );
+        }
+        *lockfile.entries.lock().unwrap() = entries;
+        Ok(lockfile)
+    }
+
+    /// [`Lockfile::load`] the lockfile at `path`, or start an empty one if
+    /// there is none yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::new(path))
+        }
+    }
+
+    /// Where the lockfile is saved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory of vendored copies, `noml-vendor` beside the lockfile
+    pub fn vendor_dir(&self) -> PathBuf {
+        self.path.parent().unwrap_or(Path::new("")).join(VENDOR_DIR)
+    }
+
+    /// The SHA-256 `url` is locked to, if it is locked
+    pub fn sha256(&self, url: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(url).map(|locked| locked.sha256.clone())
+    }
+
+    /// The locked URLs, in sorted order
+    pub fn urls(&self) -> Vec<String> {
+        self.entries.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Number of locked URLs
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no URL is locked
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Lock `url` to `content`, replacing any earlier lock, and return its
+    /// SHA-256
+    pub fn insert(&self, url: impl Into<String>, content: impl Into<String>) -> String {
+        let content = content.into();
+        let hash = sha256(content.as_bytes());
+        let locked = Locked {
+            sha256: hash.clone(),
+            content,
+        };
+        self.entries.lock().unwrap().insert(url.into(), locked);
+        hash
+    }
+
+    /// Unlock `url`, returning whether it was locked
+    pub fn remove(&self, url: &str) -> bool {
+        self.entries.lock().unwrap().remove(url).is_some()
+    }
+
+    /// Write the lockfile and a vendored copy of each locked URL; copies no
+    /// longer locked are removed
+    pub fn save(&self) -> Result<()> {
+        let entries = self.entries.lock().unwrap().clone();
+        let dir = self.vendor_dir();
+        let io_error = |path: &Path, e| NomlError::io(path.display().to_string(), e);
+        if !entries.is_empty() {
+            fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        }
+        for locked in entries.values() {
+            let vendored = self.vendored(&locked.sha256);
+            fs::write(&vendored, &locked.content).map_err(|e| io_error(&vendored, e))?;
+        }
+        if let Ok(listing) = fs::read_dir(&dir) {
+            for file in listing.flatten().map(|entry| entry.path()) {
+                let stale = file
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| {
+                        file.extension().is_some_and(|ext| ext == "noml")
+                            && stem.len() == 64
+                            && !entries.values().any(|locked| locked.sha256 == stem)
+                    });
+                if stale {
+                    fs::remove_file(&file).map_err(|e| io_error(&file, e))?;
+                }
+            }
+        }
+
+        let mut text = String::from(
+            "# Generated by `noml lock`; pins remote includes to vendored copies\nversion = 1\n",
+        );
+        if entries.is_empty() {
+            text.push_str("includes = []\n");
+        } else {
+            text.push_str("includes = [\n");
+            for (url, locked) in &entries {
+                let _ = writeln!(
+                    text,
+                    "    {{ url = \"{}\", sha256 = \"{}\" }},",
+                    url.replace('\\', "\\\\").replace('"', "\\\""),
+                    locked.sha256
+                );
+            }
+            text.push_str("]\n");
+        }
+        fs::write(&self.path, text).map_err(|e| io_error(&self.path, e))
+    }
+
+    /// The vendored contents of `url`, if it is locked
+    pub(crate) fn content(&self, url: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(url).map(|locked| locked.content.clone())
+    }
+
+    /// Check contents fetched from `url` against its lock; a URL that isn't
+    /// locked is refused unless this lockfile is
+    /// [locking](Lockfile::with_locking)
+    pub(crate) fn check(&self, url: &str, content: &str) -> Result<()> {
+        let hash = sha256(content.as_bytes());
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(url) {
+            Some(locked) if locked.sha256 != hash => Err(NomlError::security(
+                format!(
+                    "Remote include changed since it was locked (sha256 {hash}, locked {}); \
+                     run `noml lock --update` to accept it",
+                    locked.sha256
+                ),
+                url,
+            )),
+            Some(_) => Ok(()),
+            None if !self.locking => Err(NomlError::security(
+                "Remote include is not in the lockfile; run `noml lock` to add it",
+                url,
+            )),
+            None => {
+                let locked = Locked {
+                    sha256: hash,
+                    content: content.to_string(),
+                };
+                entries.insert(url.to_string(), locked);
+                Ok(())
+            }
+        }
+    }
+
+    /// Path of the vendored copy of contents hashing to `hash`
+    fn vendored(&self, hash: &str) -> PathBuf {
+        self.vendor_dir().join(format!("{hash}.noml"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOCKFILE);
+        let lockfile = Lockfile::open(&path).unwrap();
+        assert!(lockfile.is_empty());
+        let base = lockfile.insert("https://example.com/base.noml", "workers = 4");
+        lockfile.insert("https://example.com/old.noml", "x = 1");
+        lockfile.save().unwrap();
+        assert!(lockfile.remove("https://example.com/old.noml"));
+        lockfile.save().unwrap();
+
+        // Only the copy still locked is kept
+        let vendored: Vec<_> = fs::read_dir(lockfile.vendor_dir()).unwrap().collect();
+        assert_eq!(vendored.len(), 1);
+        let loaded = Lockfile::load(&path).unwrap();
+        assert_eq!(loaded.urls(), ["https://example.com/base.noml"]);
+        assert_eq!(
+            loaded.sha256("https://example.com/base.noml"),
+            Some(base.clone())
+        );
+
+        // Fetched contents must match the lock; new URLs are only locked
+        // when asked
+        assert!(loaded
+            .check("https://example.com/base.noml", "workers = 4")
+            .is_ok());
+        let err = loaded
+            .check("https://example.com/base.noml", "workers = 8")
+            .unwrap_err();
+        assert!(matches!(err, NomlError::Security { .. }), "{err}");
+        let err = loaded
+            .check("https://example.com/new.noml", "y = 2")
+            .unwrap_err();
+        assert!(err.to_string().contains("noml lock"), "{err}");
+        assert_eq!(loaded.len(), 1);
+        let locking = loaded.clone().with_locking(true);
+        locking
+            .check("https://example.com/new.noml", "y = 2")
+            .unwrap();
+        assert_eq!(loaded.len(), 2);
+
+        // A vendored copy edited by hand is refused
+        fs::write(loaded.vendored(&base), "workers = 99").unwrap();
+        let err = Lockfile::load(&path).unwrap_err();
+        assert!(matches!(err, NomlError::Security { .. }), "{err}");
+    }
+}
@@ -0,0 +1,172 @@
+//! # Resolution Manifests
+//!
+//! Every resolution records a [`Manifest`] of what it read from outside the
+//! document: the environment variables `env()` looked up, the files
+//! included or read with `file()`, and the directories listed for glob and
+//! directory includes, each with a SHA-256 of its contents. A build system
+//! can cache a resolved configuration under [`Manifest::digest`] and resolve
+//! again only once [`Manifest::is_current`] turns false.
+//!
+//! [`ResolverConfig::hermetic`](super::ResolverConfig::hermetic) makes the
+//! manifest complete: `env()` reads only the variables given to the
+//! resolver, includes stay under one root and remote includes are refused.
+//!
+//! ```rust
+//! use noml::{parse_string, Resolver, ResolverConfig};
+//! use std::collections::HashMap;
+//!
+//! let mut config = ResolverConfig::hermetic("/srv/app");
+//! config.env_vars = Some(HashMap::from([("PORT".to_string(), "8080".to_string())]));
+//!
+//! let document = parse_string(r#"port = env("PORT")"#, None)?;
+//! let mut resolver = Resolver::with_config(config);
+//! resolver.resolve(&document)?;
+//!
+//! let manifest = resolver.manifest();
+//! assert_eq!(manifest.env_vars().map(|(name, _)| name).collect::<Vec<_>>(), ["PORT"]);
+//! assert_eq!(manifest.files().count(), 0);
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use super::functions::lookup_env;
+use super::ResolverConfig;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// What a resolution read from outside the document, with content hashes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// Hash of each variable's value, `None` when it was unset
+    env: BTreeMap<String, Option<String>>,
+    files: BTreeMap<PathBuf, String>,
+    /// Hash of each remote include, by URL, and whether the document pins it
+    remote: BTreeMap<String, (String, bool)>,
+    /// Hash of the sorted names of the files listed
+    dirs: BTreeMap<PathBuf, String>,
+}
+
+impl Manifest {
+    /// The environment variables read, with the SHA-256 of each value, or
+    /// `None` for variables that were unset
+    pub fn env_vars(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.env
+            .iter()
+            .map(|(name, hash)| (name.as_str(), hash.as_deref()))
+    }
+
+    /// The files read, with the SHA-256 of each one's contents
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.files
+            .iter()
+            .map(|(path, hash)| (path.as_path(), hash.as_str()))
+    }
+
+    /// The remote includes fetched or read from a lockfile, by URL, with
+    /// the SHA-256 of each one's contents
+    pub fn remote_includes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.remote
+            .iter()
+            .map(|(url, (hash, _))| (url.as_str(), hash.as_str()))
+    }
+
+    /// The directories listed for glob and directory includes, with the
+    /// SHA-256 of the names found in each
+    pub fn directories(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.dirs
+            .iter()
+            .map(|(path, hash)| (path.as_path(), hash.as_str()))
+    }
+
+    /// A SHA-256 over the whole manifest, to use as a cache key
+    pub fn digest(&self) -> String {
+        let mut text = String::new();
+        for (name, hash) in &self.env {
+            let _ = writeln!(text, "env {name} {}", hash.as_deref().unwrap_or("-"));
+        }
+        for (path, hash) in &self.files {
+            let _ = writeln!(text, "file {} {hash}", path.display());
+        }
+        for (url, (hash, _)) in &self.remote {
+            let _ = writeln!(text, "remote {url} {hash}");
+        }
+        for (path, hash) in &self.dirs {
+            let _ = writeln!(text, "dir {} {hash}", path.display());
+        }
+        sha256(text.as_bytes())
+    }
+
+    /// Whether resolving again with `config` would read the same variables,
+    /// files and directories as recorded here
+    ///
+    /// Remote includes aren't fetched again: one is current when the
+    /// document pins it or `config`'s lockfile locks it to the same hash.
+    pub fn is_current(&self, config: &ResolverConfig) -> bool {
+        let env = self.env.iter().all(|(name, hash)| {
+            lookup_env(config.env_vars.as_ref(), name).map(|value| sha256(value.as_bytes()))
+                == *hash
+        });
+        let files = self.files.iter().all(|(path, hash)| {
+            config
+                .loader
+                .load(path, None)
+                .is_ok_and(|source| sha256(source.as_bytes()) == *hash)
+        });
+        let dirs = self.dirs.iter().all(|(dir, hash)| {
+            config
+                .loader
+                .list(dir)
+                .is_ok_and(|files| listing_hash(&files) == *hash)
+        });
+        let remote = self.remote.iter().all(|(url, (hash, pinned))| {
+            *pinned
+                || config
+                    .lockfile
+                    .as_ref()
+                    .and_then(|lockfile| lockfile.sha256(url))
+                    .is_some_and(|locked| locked == *hash)
+        });
+        env && files && remote && dirs
+    }
+
+    pub(crate) fn record_env(&mut self, name: &str, value: Option<&str>) {
+        let hash = value.map(|value| sha256(value.as_bytes()));
+        self.env.insert(name.to_string(), hash);
+    }
+
+    pub(crate) fn record_file(&mut self, path: &Path, source: &str) {
+        self.record_hash(path, sha256(source.as_bytes()));
+    }
+
+    /// Record a file whose contents hash to `hash` without reading it again
+    pub(crate) fn record_hash(&mut self, path: &Path, hash: String) {
+        self.files.insert(path.to_path_buf(), hash);
+    }
+
+    /// Record a remote include, pinned to its hash by the document or not
+    pub(crate) fn record_remote(&mut self, url: &str, source: &str, pinned: bool) {
+        let hash = sha256(source.as_bytes());
+        self.remote.insert(url.to_string(), (hash, pinned));
+    }
+
+    pub(crate) fn record_dir(&mut self, dir: &Path, files: &[PathBuf]) {
+        self.dirs.insert(dir.to_path_buf(), listing_hash(files));
+    }
+}
+
+fn listing_hash(files: &[PathBuf]) -> String {
+    let mut names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    names.sort();
+    sha256(names.join("\n").as_bytes())
+}
+
+/// The SHA-256 of `data` in lowercase hex
+pub(crate) fn sha256(data: &[u8]) -> String {
+    let mut hex = String::with_capacity(64);
+    for byte in Sha256::digest(data) {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
@@ -0,0 +1,253 @@
+//! # Include Merging
+//!
+//! `include "conf.d/*.noml"` and `include_dir("conf.d")` load every matching
+//! file in sorted order and merge them into one table, later files
+//! overriding earlier ones as set by [`MergeStrategy`]. This is the drop-in
+//! directory pattern of systemd and nginx:
+//!
+//! ```noml
+//! # 00-base.noml sets defaults, 50-prod.noml overrides some of them
+//! server = include_dir("conf.d")
+//! ```
+//!
+//! A document with a top-level `extends = "base.noml"` is deep-merged over
+//! its base the same way, with [`KeyMerge`] markers choosing how single keys
+//! merge.
+
+use crate::value::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// How the files of a glob or directory include are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Tables merge recursively; any other value from a later file replaces
+    /// the earlier one
+    #[default]
+    Deep,
+    /// Top-level keys from a later file replace the earlier ones whole
+    Shallow,
+    /// Like [`MergeStrategy::Deep`], but arrays are concatenated
+    AppendArrays,
+    /// Like [`MergeStrategy::Deep`], but setting a value that an earlier
+    /// file already set is an error
+    Strict,
+}
+
+/// How one key of a document that `extends` another merges over the base,
+/// chosen by writing `@replace(...)`, `@append(...)`, `@prepend(...)` or
+/// `@unset` as its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyMerge {
+    /// Replace the base value whole, even a table
+    Replace,
+    /// Add the array's elements after the base's
+    Append,
+    /// Add the array's elements before the base's
+    Prepend,
+    /// Remove the key from the base
+    Unset,
+}
+
+impl KeyMerge {
+    /// The marker written `@name`
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "replace" => Some(KeyMerge::Replace),
+            "append" => Some(KeyMerge::Append),
+            "prepend" => Some(KeyMerge::Prepend),
+            "unset" => Some(KeyMerge::Unset),
+            _ => None,
+        }
+    }
+}
+
+/// How values already merged moved when another file was merged in
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Moved {
+    /// The value at this path was replaced
+    Replaced(Vec<String>),
+    /// The value at this path was removed by `@unset`
+    Removed(Vec<String>),
+    /// The elements of the merged file's array at this path now start at
+    /// `offset`
+    Shifted {
+        /// Path of the array
+        array: Vec<String>,
+        /// Number of elements before them
+        offset: usize,
+    },
+    /// The elements already in the array at this path moved along by
+    /// `count` to make room for prepended ones
+    Prepended {
+        /// Path of the array
+        array: Vec<String>,
+        /// Number of elements prepended
+        count: usize,
+    },
+}
+
+/// Merge the table `source` into `target`, recording in `moved` how values
+/// under `path` moved; `keys` overrides the strategy for single keys by
+/// path. Fails with the path of a conflicting key under
+/// [`MergeStrategy::Strict`].
+pub(crate) fn merge(
+    target: &mut BTreeMap<String, Value>,
+    source: BTreeMap<String, Value>,
+    strategy: MergeStrategy,
+    keys: &HashMap<Vec<String>, KeyMerge>,
+    path: &mut Vec<String>,
+    moved: &mut Vec<Moved>,
+) -> Result<(), Vec<String>> {
+    for (key, value) in source {
+        path.push(key.clone());
+        let rule = keys.get(path.as_slice()).copied();
+        if rule == Some(KeyMerge::Unset) {
+            target.remove(&key);
+            moved.push(Moved::Removed(path.clone()));
+            path.pop();
+            continue;
+        }
+        match (rule, target.get_mut(&key), value) {
+            // New tables may still hold markers below them
+            (_, None, Value::Table(table)) => {
+                let mut fresh = BTreeMap::new();
+                merge(&mut fresh, table, strategy, keys, path, moved)?;
+                target.insert(key, Value::Table(fresh));
+            }
+            (_, None, value) => {
+                target.insert(key, value);
+            }
+            (Some(KeyMerge::Append), Some(Value::Array(existing)), Value::Array(items))
+            | (None, Some(Value::Array(existing)), Value::Array(items))
+                if rule.is_some() || strategy == MergeStrategy::AppendArrays =>
+            {
+                moved.push(Moved::Shifted {
+                    array: path.clone(),
+                    offset: existing.len(),
+                });
+                existing.extend(items);
+            }
+            (Some(KeyMerge::Prepend), Some(Value::Array(existing)), Value::Array(mut items)) => {
+                moved.push(Moved::Prepended {
+                    array: path.clone(),
+                    count: items.len(),
+                });
+                items.append(existing);
+                *existing = items;
+            }
+            (None, Some(Value::Table(existing)), Value::Table(table))
+                if strategy != MergeStrategy::Shallow =>
+            {
+                merge(existing, table, strategy, keys, path, moved)?;
+            }
+            (None, Some(_), _) if strategy == MergeStrategy::Strict => return Err(path.clone()),
+            (_, Some(existing), value) => {
+                moved.push(Moved::Replaced(path.clone()));
+                *existing = value;
+            }
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Move `path` along by `offset` if it points into an element of the
+/// array at `array`
+pub(crate) fn shift_index(path: &mut [String], array: &[String], offset: usize) {
+    if path.len() > array.len() && path.starts_with(array) {
+        if let Ok(index) = path[array.len()].parse::<usize>() {
+            path[array.len()] = (index + offset).to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(source: &str) -> BTreeMap<String, Value> {
+        let value = crate::parse(source).unwrap();
+        value.as_table().unwrap().clone()
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let base = table("tags = [\"a\"]\n[db]\nhost = \"localhost\"\nport = 5432");
+        let prod = table("tags = [\"b\"]\n[db]\nhost = \"db.prod\"");
+        let merged = |strategy| {
+            let mut target = base.clone();
+            let mut moved = Vec::new();
+            merge(
+                &mut target,
+                prod.clone(),
+                strategy,
+                &HashMap::new(),
+                &mut Vec::new(),
+                &mut moved,
+            )
+            .map(|()| (Value::Table(target), moved))
+        };
+
+        let (deep, moved) = merged(MergeStrategy::Deep).unwrap();
+        assert_eq!(deep.get("db.host").unwrap().as_string().unwrap(), "db.prod");
+        assert_eq!(deep.get("db.port").unwrap().as_integer().unwrap(), 5432);
+        assert_eq!(deep.get("tags").unwrap().as_array().unwrap().len(), 1);
+        assert_eq!(
+            moved,
+            vec![
+                Moved::Replaced(vec!["db".to_string(), "host".to_string()]),
+                Moved::Replaced(vec!["tags".to_string()]),
+            ]
+        );
+
+        let (shallow, _) = merged(MergeStrategy::Shallow).unwrap();
+        assert!(shallow.get("db.port").is_none());
+
+        let (append, moved) = merged(MergeStrategy::AppendArrays).unwrap();
+        assert_eq!(append.get("tags").unwrap().as_array().unwrap().len(), 2);
+        assert!(moved.contains(&Moved::Shifted {
+            array: vec!["tags".to_string()],
+            offset: 1
+        }));
+
+        assert_eq!(
+            merged(MergeStrategy::Strict).unwrap_err(),
+            vec!["db".to_string(), "host".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_key_merges() {
+        let mut target = table("tags = [\"a\", \"b\"]\nold = 1\n[db]\nhost = \"h\"\nport = 1");
+        let source = table("tags = [\"z\"]\nold = 0\n[db]\nport = 2");
+        let key = |path: &str| path.split('.').map(str::to_string).collect::<Vec<_>>();
+        let keys = HashMap::from([
+            (key("tags"), KeyMerge::Prepend),
+            (key("old"), KeyMerge::Unset),
+            (key("db"), KeyMerge::Replace),
+        ]);
+        let mut moved = Vec::new();
+        merge(
+            &mut target,
+            source,
+            MergeStrategy::Strict,
+            &keys,
+            &mut Vec::new(),
+            &mut moved,
+        )
+        .unwrap();
+
+        let merged = Value::Table(target);
+        let tags = merged.get("tags").unwrap().as_array().unwrap();
+        assert_eq!(tags[0].as_string().unwrap(), "z");
+        assert_eq!(tags.len(), 3);
+        assert!(merged.get("old").is_none());
+        assert!(merged.get("db.host").is_none());
+        assert!(moved.contains(&Moved::Prepended {
+            array: key("tags"),
+            count: 1
+        }));
+        assert!(moved.contains(&Moved::Removed(key("old"))));
+        assert_eq!(KeyMerge::from_name("append"), Some(KeyMerge::Append));
+    }
+}
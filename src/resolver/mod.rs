@@ -0,0 +1,2430 @@
+//! # NOML Resolver
+//!
+//! This module handles the resolution of dynamic NOML features:
+//! - Function calls such as env(), looked up in a [`FunctionRegistry`]
+//! - File inclusion via include statements (local and HTTP)
+//! - Variable interpolation via ${path} syntax
+//! - Native type resolution via @type() syntax
+//!
+//! Interpolations may reference any key in the document, including keys
+//! defined later or inside included files; see [`graph`] for the ordering.
+
+mod cache;
+mod dotenv;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod functions;
+mod graph;
+mod interpolation;
+mod loader;
+mod lockfile;
+mod manifest;
+mod merge;
+mod native;
+mod provenance;
+mod secrets;
+pub(crate) mod sections;
+mod security;
+mod stdlib;
+
+#[cfg(feature = "async")]
+pub use cache::HttpCache;
+pub use cache::IncludeCache;
+pub use dotenv::{DotEnv, EnvPrecedence};
+#[cfg(feature = "encryption")]
+pub use encryption::{
+    decrypt_paths, encrypt_paths, EncryptionKey, EnvKey, KeyFile, KeyProvider, KEY_ENV,
+};
+pub use functions::{FunctionRegistry, ResolveContext};
+pub use loader::{EmbeddedLoader, FsLoader, InMemoryLoader, IncludeLoader};
+pub use lockfile::{Lockfile, LOCKFILE};
+pub use manifest::Manifest;
+pub use merge::MergeStrategy;
+pub(crate) use native::builtins as builtin_natives;
+pub use native::{NativeResolver, NativeType};
+pub use provenance::{Origin, OriginKind, Provenance};
+pub use secrets::{EnvSecretProvider, FileSecretProvider, InMemorySecretProvider, SecretProvider};
+pub use security::{EnvAccess, SecurityPolicy};
+
+use crate::error::{NomlError, Result};
+use crate::parser::ast::{
+    AstNode, AstValue, Document, Fallback, Key, KeySegment, Span, StringStyle, TableEntry,
+};
+use crate::parser::edit::node_from_value;
+use crate::value::Value;
+use graph::{Pending, Selection};
+use indexmap::IndexMap;
+use interpolation::Part;
+use merge::{KeyMerge, Moved};
+use security::LoadBudget;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "async")]
+use reqwest;
+#[cfg(feature = "async")]
+use std::time::Duration;
+
+/// Configuration for the resolver
+#[derive(Debug, Clone)]
+pub struct ResolverConfig {
+    /// Base path for resolving relative includes
+    pub base_path: Option<PathBuf>,
+    /// Environment variables to use (if None, uses std::env)
+    pub env_vars: Option<HashMap<String, String>>,
+    /// Maximum include depth to prevent infinite recursion
+    pub max_include_depth: usize,
+    /// Whether to allow missing environment variables
+    pub allow_missing_env: bool,
+    /// Custom native type resolvers
+    pub native_resolvers: HashMap<String, NativeResolver>,
+    /// Functions callable from documents, such as `env()`
+    pub functions: FunctionRegistry,
+    /// Where included files are read from; the filesystem by default
+    pub loader: Arc<dyn IncludeLoader>,
+    /// What documents may include and read; allows everything by default
+    pub security: SecurityPolicy,
+    /// Where `secret("provider", "key")` looks secrets up, by provider name
+    pub secret_providers: HashMap<String, Arc<dyn SecretProvider>>,
+    /// How the files of a glob or directory include are merged
+    pub merge_strategy: MergeStrategy,
+    /// Profile whose `[@profile.<name>]` sections are laid over each document
+    pub profile: Option<String>,
+    /// Whether resolution must be reproducible: `env()` may only read
+    /// `env_vars`, and the security policy must confine includes to a root
+    /// and refuse remote ones (see [`ResolverConfig::hermetic`])
+    pub hermetic: bool,
+    /// Parsed included files kept between resolves; none by default
+    pub include_cache: Option<IncludeCache>,
+    /// Hashes and vendored copies remote includes are pinned to
+    pub lockfile: Option<Lockfile>,
+    /// Whether async resolution reads remote includes only from the
+    /// lockfile's vendored copies instead of fetching them
+    #[cfg(feature = "async")]
+    pub offline: bool,
+    /// HTTP client timeout for remote includes (async feature only)
+    #[cfg(feature = "async")]
+    pub http_timeout: Duration,
+    /// Cache for HTTP includes to avoid repeated requests
+    #[cfg(feature = "async")]
+    pub http_cache: Option<HttpCache>,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        Self {
+            base_path: None,
+            env_vars: None,
+            max_include_depth: 10,
+            allow_missing_env: false,
+            native_resolvers: native::builtins(),
+            functions: FunctionRegistry::new(),
+            loader: Arc::new(FsLoader),
+            security: SecurityPolicy::default(),
+            secret_providers: HashMap::new(),
+            merge_strategy: MergeStrategy::default(),
+            profile: None,
+            hermetic: false,
+            include_cache: None,
+            lockfile: None,
+            #[cfg(feature = "async")]
+            offline: false,
+            #[cfg(feature = "async")]
+            http_timeout: Duration::from_secs(30),
+            #[cfg(feature = "async")]
+            http_cache: Some(HttpCache::default()),
+        }
+    }
+}
+
+/// The main resolver for NOML documents
+pub struct Resolver {
+    config: ResolverConfig,
+    include_stack: Vec<PathBuf>,
+    variables: IndexMap<String, Value>,
+    /// The document resolved so far, used to look up interpolations
+    context: Value,
+    /// Include mount point of the value being resolved
+    scope: Vec<String>,
+    /// Selective includes to apply once interpolations are resolved
+    selections: Vec<Selection>,
+    /// Merge markers of the document being merged over the one it extends
+    key_merges: Option<HashMap<Vec<String>, KeyMerge>>,
+    /// Origins recorded by [`Resolver::resolve_with_provenance`]
+    provenance: Option<Provenance>,
+    /// Profiles declared by the documents resolved so far
+    profiles: BTreeSet<String>,
+    /// File of the document being resolved, if it was read from one
+    source_file: Option<PathBuf>,
+    /// Files and bytes included so far, checked against the security policy
+    budget: LoadBudget,
+    /// Variables and files read so far
+    manifest: Mutex<Manifest>,
+}
+
+/// A value built apart from the table it is merged into, with the
+/// interpolations, origins and selective includes recorded for it
+struct Built {
+    /// File the value came from, for errors
+    name: String,
+    value: Value,
+    pending: Vec<Pending>,
+    provenance: Option<Provenance>,
+    /// Index of the first selective include recorded while building it
+    selections: usize,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResolverConfig {
+    /// A configuration whose results depend only on what it is given, so
+    /// they can be cached against the [`Manifest`] of what was read
+    ///
+    /// `env()` reads only [`env_vars`](ResolverConfig::env_vars), empty
+    /// until set, and never the process environment; includes are resolved
+    /// against `root` and must stay inside it; remote includes are refused.
+    /// Secret and key providers are supplied by the application and are not
+    /// tracked.
+    pub fn hermetic(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            base_path: Some(root.clone()),
+            env_vars: Some(HashMap::new()),
+            security: SecurityPolicy {
+                allowed_roots: vec![root],
+                allow_remote_includes: false,
+                ..SecurityPolicy::default()
+            },
+            hermetic: true,
+            ..Self::default()
+        }
+    }
+
+    /// The schema type values of the native type `name` satisfy, if declared
+    pub fn native_field_type(&self, name: &str) -> Option<crate::schema::FieldType> {
+        self.native_resolvers.get(name)?.field_type()
+    }
+}
+
+impl Resolver {
+    /// Create a new resolver with default configuration
+    pub fn new() -> Self {
+        Self::with_config(ResolverConfig::default())
+    }
+
+    /// Create a new resolver with custom configuration
+    pub fn with_config(config: ResolverConfig) -> Self {
+        Self {
+            config,
+            include_stack: Vec::new(),
+            variables: IndexMap::new(),
+            context: Value::Null,
+            scope: Vec::new(),
+            selections: Vec::new(),
+            key_merges: None,
+            provenance: None,
+            profiles: BTreeSet::new(),
+            source_file: None,
+            budget: LoadBudget::default(),
+            manifest: Mutex::default(),
+        }
+    }
+
+    /// Set the base path for resolving relative includes
+    pub fn with_base_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.base_path = Some(path.into());
+        self
+    }
+
+    /// Set custom environment variables
+    pub fn with_env_vars(mut self, env_vars: HashMap<String, String>) -> Self {
+        self.config.env_vars = Some(env_vars);
+        self
+    }
+
+    /// Lay the variables of a `.env` file over the environment `env()` reads,
+    /// which is the process environment unless set with
+    /// [`Resolver::with_env_vars`]
+    pub fn with_dotenv(mut self, dotenv: &DotEnv, precedence: EnvPrecedence) -> Self {
+        let mut env_vars = self
+            .config
+            .env_vars
+            .take()
+            .unwrap_or_else(|| std::env::vars().collect());
+        dotenv.overlay(&mut env_vars, precedence);
+        self.config.env_vars = Some(env_vars);
+        self
+    }
+
+    /// Add a custom native type resolver
+    pub fn with_native_resolver<S: Into<String>>(
+        mut self,
+        name: S,
+        resolver: NativeResolver,
+    ) -> Self {
+        self.config.native_resolvers.insert(name.into(), resolver);
+        self
+    }
+
+    /// Add or replace a function callable from documents
+    pub fn with_function<S, F>(
+        mut self,
+        name: S,
+        arity: impl std::ops::RangeBounds<usize>,
+        function: F,
+    ) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&[Value], &ResolveContext<'_>) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.config.functions.register(name, arity, function);
+        self
+    }
+
+    /// Replace the functions callable from documents
+    pub fn with_functions(mut self, functions: FunctionRegistry) -> Self {
+        self.config.functions = functions;
+        self
+    }
+
+    /// Read included files through `loader` instead of the filesystem
+    pub fn with_loader<L: IncludeLoader + 'static>(mut self, loader: L) -> Self {
+        self.config.loader = Arc::new(loader);
+        self
+    }
+
+    /// Apply a security policy to includes, `file()` and `env()`
+    pub fn with_security_policy(mut self, policy: SecurityPolicy) -> Self {
+        self.config.security = policy;
+        self
+    }
+
+    /// Register a provider for `secret("name", "key")` under `name`
+    pub fn with_secret_provider<S, P>(mut self, name: S, provider: P) -> Self
+    where
+        S: Into<String>,
+        P: SecretProvider + 'static,
+    {
+        self.config
+            .secret_providers
+            .insert(name.into(), Arc::new(provider));
+        self
+    }
+
+    /// Decrypt `@encrypted("...")` values with the key from `provider`
+    /// instead of the `NOML_ENCRYPTION_KEY` environment variable
+    #[cfg(feature = "encryption")]
+    pub fn with_key_provider<P: KeyProvider + 'static>(self, provider: P) -> Self {
+        self.with_native_resolver("encrypted", encryption::native(Arc::new(provider)))
+    }
+
+    /// Set how the files of a glob or directory include are merged
+    pub fn with_merge_strategy(mut self, strategy: MergeStrategy) -> Self {
+        self.config.merge_strategy = strategy;
+        self
+    }
+
+    /// Keep parsed included files in `cache`, shared with every resolver
+    /// given a clone of it
+    pub fn with_include_cache(mut self, cache: IncludeCache) -> Self {
+        self.config.include_cache = Some(cache);
+        self
+    }
+
+    /// Pin remote includes to `lockfile`: locked URLs resolve from their
+    /// vendored copies, and fetched contents must match their lock
+    pub fn with_lockfile(mut self, lockfile: Lockfile) -> Self {
+        self.config.lockfile = Some(lockfile);
+        self
+    }
+
+    /// Read remote includes only from the lockfile's vendored copies,
+    /// without the network
+    #[cfg(feature = "async")]
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.config.offline = offline;
+        self
+    }
+
+    /// Lay the `[@profile.<name>]` sections of `profile` over each document
+    pub fn with_profile<S: Into<String>>(mut self, profile: S) -> Self {
+        self.config.profile = Some(profile.into());
+        self
+    }
+
+    /// The variables and files the last resolution read
+    pub fn manifest(&self) -> Manifest {
+        self.manifest.lock().unwrap().clone()
+    }
+
+    /// Profiles declared by the last resolved document and its includes
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.profiles.iter().map(String::as_str)
+    }
+
+    /// Resolve a document, processing all includes, interpolations, and function calls
+    ///
+    /// Everything without a `${...}` reference is resolved first; the
+    /// interpolated values then follow in dependency order. A reference cycle
+    /// fails with [`NomlError::CircularReference`] naming every key involved.
+    /// Remote includes are read from the vendored copies of a
+    /// [`Lockfile`], if one is set.
+    pub fn resolve(&mut self, document: &Document) -> Result<Value> {
+        self.start()?;
+        match self.resolve_locked_includes(document)? {
+            Some(document) => self.resolve_document(&document),
+            None => self.resolve_document(document),
+        }
+    }
+
+    /// `document` with its remote includes replaced by the lockfile's
+    /// vendored copies, or `None` without a lockfile or remote includes
+    fn resolve_locked_includes(&mut self, document: &Document) -> Result<Option<Document>> {
+        let remote = self.collect_http_includes(&document.root);
+        if remote.is_empty() || self.config.lockfile.is_none() {
+            return Ok(None);
+        }
+        let mut contents = HashMap::new();
+        for (url, pin) in remote {
+            self.config.security.check_include(&url)?;
+            let content = self.locked_content(&url)?;
+            self.accept_remote(&url, pin.as_deref(), &content)?;
+            contents.insert(url, content);
+        }
+        self.with_remote_contents(document, &contents).map(Some)
+    }
+
+    /// The vendored copy of the remote include `url`
+    fn locked_content(&self, url: &str) -> Result<String> {
+        self.config
+            .lockfile
+            .as_ref()
+            .and_then(|lockfile| lockfile.content(url))
+            .ok_or_else(|| {
+                NomlError::import(url, "not in the lockfile; run `noml lock` to vendor it")
+            })
+    }
+
+    /// Check the contents of the remote include `url` against its `sha256`
+    /// pin and the lockfile, and count them as read
+    fn accept_remote(&self, url: &str, pin: Option<&str>, content: &str) -> Result<()> {
+        check_pin(url, pin, &manifest::sha256(content.as_bytes()))?;
+        if let Some(lockfile) = &self.config.lockfile {
+            lockfile.check(url, content)?;
+        }
+        self.budget
+            .charge(&self.config.security, url, content.len())?;
+        self.manifest
+            .lock()
+            .unwrap()
+            .record_remote(url, content, pin.is_some());
+        Ok(())
+    }
+
+    /// `document` with each remote include replaced by its contents
+    fn with_remote_contents(
+        &self,
+        document: &Document,
+        contents: &HashMap<String, String>,
+    ) -> Result<Document> {
+        Ok(Document {
+            root: self.replace_http_includes_with_content(&document.root, contents)?,
+            source_path: document.source_path.clone(),
+            source_text: document.source_text.clone(),
+        })
+    }
+
+    /// Reset the include budget and manifest, and check that a hermetic
+    /// configuration really is one
+    fn start(&mut self) -> Result<()> {
+        self.budget = LoadBudget::default();
+        *self.manifest.lock().unwrap() = Manifest::default();
+        let security = &self.config.security;
+        if self.config.hermetic
+            && (security.allowed_roots.is_empty() || security.allow_remote_includes)
+        {
+            return Err(NomlError::security(
+                "hermetic resolution needs includes confined to a root and remote includes refused",
+                "hermetic",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve a document without resetting the include budget
+    fn resolve_document(&mut self, document: &Document) -> Result<Value> {
+        self.include_stack.clear();
+        self.source_file = document.source_path.as_ref().map(PathBuf::from);
+
+        self.selections.clear();
+        self.key_merges = None;
+        self.profiles.clear();
+
+        let mut pending = Vec::new();
+        let root = self.build_document(&document.root, &mut Vec::new(), &[], &mut pending)?;
+        if let Some(profile) = &self.config.profile {
+            if !self.profiles.contains(profile) {
+                let declared: Vec<&str> = self.profiles().collect();
+                return Err(NomlError::validation(format!(
+                    "Unknown profile '{profile}' (declared: {})",
+                    if declared.is_empty() {
+                        "none".to_string()
+                    } else {
+                        declared.join(", ")
+                    }
+                )));
+            }
+        }
+        let order = graph::resolution_order(&pending, &self.selections, &root)?;
+
+        self.context = root;
+        let result = self.resolve_pending(&pending, &order);
+        self.scope.clear();
+        let mut root = std::mem::replace(&mut self.context, Value::Null);
+        result?;
+        self.apply_selections(&mut root);
+        Ok(root)
+    }
+
+    /// Resolve a document, also recording where every value came from
+    ///
+    /// Each dotted key path maps to the file and span it was written at,
+    /// the includes that led there, and whether it came from a literal, an
+    /// `env()` lookup, an interpolation or a native type.
+    pub fn resolve_with_provenance(&mut self, document: &Document) -> Result<(Value, Provenance)> {
+        self.provenance = Some(Provenance::new());
+        let result = self.resolve(document);
+        let provenance = self.provenance.take().unwrap_or_default();
+        result.map(|value| (value, provenance))
+    }
+
+    /// Resolve everything except interpolations, collecting those in `pending`
+    fn build(
+        &mut self,
+        node: &AstNode,
+        path: &mut Vec<String>,
+        scope: &[String],
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        self.record_origin(path, node);
+        match &node.value {
+            AstValue::Table { entries, .. } => {
+                let mut table = Value::empty_table();
+                for entry in entries {
+                    let segments: Vec<String> =
+                        entry.key.segments.iter().map(|s| s.name.clone()).collect();
+                    let depth = path.len();
+                    path.extend(segments.iter().cloned());
+                    let value = self.build(&entry.value, path, scope, pending)?;
+                    path.truncate(depth);
+                    graph::set_path(&mut table, &segments, value)?;
+                }
+                Ok(table)
+            }
+
+            AstValue::Array { elements, .. } => {
+                let mut items = Vec::with_capacity(elements.len());
+                for (i, element) in elements.iter().enumerate() {
+                    path.push(i.to_string());
+                    items.push(self.build(element, path, scope, pending)?);
+                    path.pop();
+                }
+                Ok(Value::Array(items))
+            }
+
+            AstValue::Include {
+                path: include_path,
+                subpath,
+                only,
+                sha256,
+            } => {
+                let value = self.build_include(
+                    include_path,
+                    false,
+                    sha256.as_deref(),
+                    &node.span,
+                    path,
+                    pending,
+                )?;
+                if subpath.is_some() || only.is_some() {
+                    let selection = Selection {
+                        mount: path.clone(),
+                        selected: subpath
+                            .iter()
+                            .flat_map(|subpath| subpath.split('.'))
+                            .map(str::to_string)
+                            .collect(),
+                        only: only.clone(),
+                    };
+                    selection
+                        .check(&value)
+                        .map_err(|reason| NomlError::import(include_path, reason))?;
+                    self.selections.push(selection);
+                }
+                Ok(value)
+            }
+
+            AstValue::IncludeDir { path: include_path } => {
+                self.build_include(include_path, true, None, &node.span, path, pending)
+            }
+
+            AstValue::Native { type_name, args }
+                if KeyMerge::from_name(type_name).is_some()
+                    && !self.config.native_resolvers.contains_key(type_name) =>
+            {
+                self.build_key_merge(type_name, args, &node.span, path, scope, pending)
+            }
+
+            _ if graph::has_interpolation(node) => {
+                pending.push(Pending {
+                    path: path.clone(),
+                    node: node.clone(),
+                    scope: scope.to_vec(),
+                    file: self.include_stack.last().cloned(),
+                });
+                Ok(Value::Null)
+            }
+
+            _ => self.resolve_value(node),
+        }
+    }
+
+    /// Note where the value at `path` comes from, when tracking provenance
+    fn record_origin(&mut self, path: &[String], node: &AstNode) {
+        if self.provenance.is_none() || path.is_empty() {
+            return;
+        }
+        let kind = match &node.value {
+            AstValue::Table { .. } | AstValue::Array { .. } => OriginKind::Literal,
+            AstValue::Include { path, .. } | AstValue::IncludeDir { path } => {
+                OriginKind::Include { path: path.clone() }
+            }
+            _ if graph::has_interpolation(node) => OriginKind::Interpolation {
+                references: graph::references(node),
+            },
+            AstValue::FunctionCall { name, args } => match args.first().map(|arg| &arg.value) {
+                Some(AstValue::String { value: var, .. }) if name == "env" => OriginKind::Env {
+                    found: functions::lookup_env(self.config.env_vars.as_ref(), var).is_some(),
+                    var: var.clone(),
+                },
+                _ => OriginKind::Function { name: name.clone() },
+            },
+            AstValue::Native { type_name, .. } => OriginKind::Native {
+                type_name: type_name.clone(),
+            },
+            _ => OriginKind::Literal,
+        };
+
+        let mut included_from: Vec<PathBuf> = self
+            .source_file
+            .iter()
+            .chain(&self.include_stack)
+            .cloned()
+            .collect();
+        let origin = Origin {
+            kind,
+            file: included_from.pop(),
+            span: Some(node.span),
+            included_from,
+        };
+        let key = path.join(".");
+        if let Some(provenance) = &mut self.provenance {
+            // Tables keep the origin of where they were first opened, such
+            // as the include that mounted them
+            if matches!(node.value, AstValue::Table { .. }) {
+                provenance.insert_missing(key, origin);
+            } else {
+                provenance.replace(&key, origin);
+            }
+        }
+    }
+
+    /// Resolve a node whose references are all available to its value
+    fn resolve_value(&mut self, node: &AstNode) -> Result<Value> {
+        match &node.value {
+            AstValue::FunctionCall { name, args } => self.call_function(name, args, &node.span),
+            _ => {
+                let resolved = self.resolve_node(node)?;
+                self.extract_value(resolved)
+            }
+        }
+    }
+
+    /// Call a registered function with its arguments resolved
+    fn call_function(&mut self, name: &str, args: &[AstNode], span: &Span) -> Result<Value> {
+        let values = args
+            .iter()
+            .map(|arg| self.resolve_value(arg))
+            .collect::<Result<Vec<_>>>()?;
+        let context = ResolveContext {
+            name,
+            span: *span,
+            base_path: self.current_dir(),
+            from: self.current_file(),
+            loader: self.config.loader.as_ref(),
+            policy: &self.config.security,
+            budget: &self.budget,
+            env_vars: self.config.env_vars.as_ref(),
+            allow_missing_env: self.config.allow_missing_env,
+            hermetic: self.config.hermetic,
+            secrets: &self.config.secret_providers,
+            manifest: &self.manifest,
+        };
+        self.config.functions.call(&values, &context)
+    }
+
+    /// Resolve interpolated values in dependency order
+    fn resolve_pending(&mut self, pending: &[Pending], order: &[usize]) -> Result<()> {
+        for &index in order {
+            let item = &pending[index];
+            self.scope.clone_from(&item.scope);
+            self.include_stack.extend(item.file.clone());
+            let span = item.node.span;
+            let value = match &item.node.value {
+                // A whole-value reference is copied as-is, keeping its type
+                AstValue::Interpolation { path, fallback } => {
+                    self.resolve_reference(path, fallback.as_ref())
+                }
+                _ => self.resolve_value(&item.node),
+            }
+            .map_err(|err| err.at_key(item.path.join("."), span.start_line, span.start_column));
+            if item.file.is_some() {
+                self.include_stack.pop();
+            }
+            graph::set_path(&mut self.context, &item.path, value?)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a single AST node
+    fn resolve_node(&mut self, node: &AstNode) -> Result<AstNode> {
+        match &node.value {
+            AstValue::String {
+                value,
+                style,
+                has_escapes,
+            } => {
+                // Check for interpolation in strings
+                let (resolved_value, secret) =
+                    self.resolve_interpolation_in_string(value, &node.span)?;
+                if secret {
+                    return Ok(secret_node(resolved_value, node.span));
+                }
+                Ok(AstNode::new(
+                    AstValue::String {
+                        value: resolved_value,
+                        style: *style, // Copy instead of clone for simple enums
+                        has_escapes: *has_escapes,
+                    },
+                    node.span, // Copy instead of clone now that Span is Copy
+                ))
+            }
+
+            AstValue::Array {
+                elements,
+                multiline,
+                trailing_comma,
+            } => {
+                let mut resolved_elements = Vec::new();
+                for element in elements {
+                    resolved_elements.push(self.resolve_node(element)?);
+                }
+                Ok(AstNode::new(
+                    AstValue::Array {
+                        elements: resolved_elements,
+                        multiline: *multiline,
+                        trailing_comma: *trailing_comma,
+                    },
+                    node.span,
+                ))
+            }
+
+            AstValue::Table { entries, inline } => {
+                let mut resolved_entries = Vec::new();
+                for entry in entries {
+                    let resolved_value = self.resolve_node(&entry.value)?;
+                    resolved_entries.push(TableEntry {
+                        key: entry.key.clone(),
+                        value: resolved_value,
+                        comments: entry.comments.clone(),
+                    });
+                }
+                Ok(AstNode::new(
+                    AstValue::Table {
+                        entries: resolved_entries,
+                        inline: *inline,
+                    },
+                    node.span,
+                ))
+            }
+
+            AstValue::FunctionCall { name, args } => {
+                let value = self.call_function(name, args, &node.span)?;
+                self.value_to_ast_node(value, node.span)
+            }
+
+            AstValue::Native { type_name, args } => {
+                self.resolve_native_type(type_name, args, &node.span)
+            }
+
+            AstValue::Interpolation { path, fallback } => {
+                let value = self.resolve_reference(path, fallback.as_ref())?;
+                self.value_to_ast_node(value, node.span)
+            }
+
+            // Pass through literal values unchanged
+            _ => Ok(node.clone()),
+        }
+    }
+
+    fn resolve_native_type(
+        &self,
+        type_name: &str,
+        args: &[AstNode],
+        span: &Span,
+    ) -> Result<AstNode> {
+        // Convert args to values
+        let arg_values: Result<Vec<Value>> = args
+            .iter()
+            .map(|arg| self.extract_value(arg.clone()))
+            .collect();
+        let arg_values = arg_values?;
+
+        // Look up the resolver
+        let resolver = self.config.native_resolvers.get(type_name).ok_or_else(|| {
+            NomlError::unknown_native_type(type_name, span.start_line, span.start_column)
+        })?;
+
+        // Check the arguments; the value itself is produced on extraction
+        resolver.validate(&arg_values)?;
+
+        // Create a native value node
+        let native_value = AstValue::Native {
+            type_name: type_name.to_string(),
+            args: args.to_vec(),
+        };
+
+        Ok(AstNode::new(native_value, *span))
+    }
+
+    /// Set a variable in the interpolation context
+    ///
+    /// Variables are consulted when a `${...}` path is not found in the document.
+    pub fn set_variable(&mut self, name: String, value: Value) {
+        self.variables.insert(name, value);
+    }
+
+    /// Get all variables in the current context
+    pub fn variables(&self) -> &IndexMap<String, Value> {
+        &self.variables
+    }
+
+    /// Clear all variables
+    pub fn clear_variables(&mut self) {
+        self.variables.clear();
+    }
+
+    /// Resolve a document; equivalent to [`Resolver::resolve`]
+    ///
+    /// Interpolations always see the whole document now, so there is no
+    /// separate context-building pass.
+    pub fn resolve_with_context(&mut self, document: &Document) -> Result<Value> {
+        self.resolve(document)
+    }
+
+    /// Look up an interpolation path in the document, then in the variables
+    fn lookup_variable(&self, path: &str) -> Option<&Value> {
+        let target = graph::target(path, &self.scope, &self.selections, &self.context);
+        if let Some(value) = target.and_then(|target| graph::get_path(&self.context, &target)) {
+            return Some(value);
+        }
+        if let Some(value) = self.variables.get(path) {
+            return Some(value);
+        }
+        let (name, rest) = path.split_once('.')?;
+        self.variables.get(name)?.get(rest)
+    }
+
+    /// Resolve an interpolation path to a copy of the referenced value
+    fn resolve_variable_path(&self, path: &str) -> Result<Value> {
+        let mut value = self.lookup_variable(path).cloned().ok_or_else(|| {
+            NomlError::interpolation(
+                format!("Variable '{path}' not found in current context"),
+                path.to_string(),
+            )
+        })?;
+        // A copy of a table holding selective includes takes only their
+        // selected parts, as the document will once they are applied
+        if let Some(target) = graph::target(path, &self.scope, &self.selections, &self.context) {
+            for selection in &self.selections {
+                if !self.scope.starts_with(&selection.mount) {
+                    selection.apply(&target, &mut value);
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Resolve a whole-value reference, applying its fallback
+    fn resolve_reference(&self, path: &str, fallback: Option<&Fallback>) -> Result<Value> {
+        match self.fallback_for(path, fallback)? {
+            Some(text) => Ok(interpolation::default_value(text)),
+            None => self.resolve_variable_path(path),
+        }
+    }
+
+    /// The `:-` default text to use in place of `path`, if it applies;
+    /// fails with the `:?` message when the value is missing
+    fn fallback_for<'f>(
+        &self,
+        path: &str,
+        fallback: Option<&'f Fallback>,
+    ) -> Result<Option<&'f str>> {
+        match fallback {
+            Some(fallback) if interpolation::is_unset(self.lookup_variable(path)) => match fallback
+            {
+                Fallback::Default(text) => Ok(Some(text)),
+                Fallback::Required(message) => Err(NomlError::interpolation(message.trim(), path)),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolve an include statement, mounting the file's values at `path`;
+    /// globs and directories mount every matching file
+    fn build_include(
+        &mut self,
+        include_path: &str,
+        directory: bool,
+        pin: Option<&str>,
+        span: &Span,
+        path: &mut Vec<String>,
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        // Check include depth
+        if self.include_stack.len() >= self.config.max_include_depth {
+            return Err(NomlError::parse(
+                format!(
+                    "Maximum include depth ({}) exceeded",
+                    self.config.max_include_depth
+                ),
+                span.start_line,
+                span.start_column,
+            ));
+        }
+
+        self.config.security.check_include(include_path)?;
+
+        // Check if this is an HTTP include
+        if security::is_remote(include_path) {
+            #[cfg(feature = "async")]
+            {
+                return Err(NomlError::parse(
+                    "HTTP includes require async resolver. Use resolve_document_async() instead."
+                        .to_string(),
+                    span.start_line,
+                    span.start_column,
+                ));
+            }
+            #[cfg(not(feature = "async"))]
+            {
+                return Err(NomlError::parse(
+                    "HTTP includes require the 'async' feature to be enabled".to_string(),
+                    span.start_line,
+                    span.start_column,
+                ));
+            }
+        }
+
+        if directory || include_path.contains('*') {
+            if pin.is_some() {
+                return Err(NomlError::import(
+                    include_path,
+                    "a sha256 pin needs a single file, not a pattern",
+                ));
+            }
+            return self.build_include_all(include_path, directory, span, path, pending);
+        }
+        let resolved_path = self.resolve_include_path(include_path);
+        self.build_file(resolved_path, pin, span, path, pending)
+    }
+
+    /// Load, parse and build one included file, checking its contents
+    /// against `pin`
+    fn build_file(
+        &mut self,
+        resolved_path: PathBuf,
+        pin: Option<&str>,
+        span: &Span,
+        path: &mut Vec<String>,
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        self.config.security.check_root(&resolved_path)?;
+
+        // Check for circular includes
+        if self.include_stack.contains(&resolved_path) {
+            return Err(NomlError::parse(
+                format!("Circular include detected: {resolved_path:?}"),
+                span.start_line,
+                span.start_column,
+            ));
+        }
+
+        // Load and parse the included file, or take it from the cache
+        let loader = self.config.loader.as_ref();
+        self.budget.check_size(
+            &self.config.security,
+            &resolved_path.display().to_string(),
+            loader.size(&resolved_path),
+        )?;
+        let included = match &self.config.include_cache {
+            Some(cache) => cache.load(loader, &resolved_path, self.current_file()),
+            None => cache::Entry::read(loader, &resolved_path, self.current_file()),
+        };
+        let included = included.map_err(|e| {
+            NomlError::parse(
+                format!(
+                    "Failed to parse include '{}': {}",
+                    resolved_path.display(),
+                    e
+                ),
+                span.start_line,
+                span.start_column,
+            )
+        })?;
+        self.manifest
+            .lock()
+            .unwrap()
+            .record_hash(&resolved_path, included.hash.clone());
+        let name = resolved_path.display().to_string();
+        check_pin(&name, pin, &included.hash)?;
+        self.budget
+            .charge(&self.config.security, &name, included.len)?;
+        if let (Some(value), None) = (&included.value, &self.provenance) {
+            return Ok(value.clone());
+        }
+
+        // Resolve the included document; its interpolations are scoped to
+        // where it is mounted
+        self.include_stack.push(resolved_path);
+        let scope = path.clone();
+        let resolved_include = self.build_document(&included.document.root, path, &scope, pending);
+        self.include_stack.pop();
+
+        if let (Ok(value), Some(cache)) = (&resolved_include, &self.config.include_cache) {
+            cache.store_value(&included, value);
+        }
+        resolved_include
+    }
+
+    /// Replace selectively included values with their selected parts,
+    /// innermost first, moving their origins along
+    fn apply_selections(&mut self, root: &mut Value) {
+        for selection in std::mem::take(&mut self.selections) {
+            selection.apply(&[], root);
+            if let Some(provenance) = &mut self.provenance {
+                let mount = selection.mount.len();
+                provenance.remap(|key| {
+                    let segments: Vec<String> = key.split('.').map(str::to_string).collect();
+                    if segments.len() <= mount || !segments.starts_with(&selection.mount) {
+                        return Some(key.to_string());
+                    }
+                    selection
+                        .collapse(&segments)
+                        .filter(|moved| moved.len() > mount)
+                        .map(|moved| moved.join("."))
+                });
+            }
+        }
+    }
+
+    /// Build every file matched by a glob or directory include and merge
+    /// them in sorted order
+    fn build_include_all(
+        &mut self,
+        include_path: &str,
+        directory: bool,
+        span: &Span,
+        path: &mut Vec<String>,
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        let mut merged = BTreeMap::new();
+        for file in self.matching_files(include_path, directory, span)? {
+            let name = file.display().to_string();
+            let built = self.build_apart(path, name.clone(), |resolver, path, pending| {
+                resolver.build_file(file, None, span, path, pending)
+            });
+            let built = match built {
+                Ok(built) => built,
+                Err(err @ NomlError::Security { .. }) => return Err(err),
+                Err(err) => return Err(NomlError::import(name, err.to_string())),
+            };
+            self.merge_apart(
+                &mut merged,
+                built,
+                self.config.merge_strategy,
+                &HashMap::new(),
+                path,
+                pending,
+            )?;
+        }
+        Ok(Value::Table(merged))
+    }
+
+    /// Build a document's root, merged over the documents named by its
+    /// top-level `extends` key and with the active profile laid over it
+    fn build_document(
+        &mut self,
+        root: &AstNode,
+        path: &mut Vec<String>,
+        scope: &[String],
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        let AstValue::Table { entries, .. } = &root.value else {
+            return self.build(root, path, scope, pending);
+        };
+        let position = entries.iter().position(
+            |entry| matches!(entry.key.segments.as_slice(), [segment] if segment.name == "extends"),
+        );
+        if position.is_none() && !entries.iter().any(TableEntry::is_profile) {
+            return self.build(root, path, scope, pending);
+        }
+
+        // The document without its `extends` key and profile sections
+        let mut own = root.clone();
+        let mut profile = Vec::new();
+        if let AstValue::Table { entries, .. } = &mut own.value {
+            if let Some(position) = position {
+                entries.remove(position);
+            }
+            for entry in entries.iter().filter(|entry| entry.is_profile()) {
+                self.profile_entries(entry, &entry.key.segments[1..], &mut profile)?;
+            }
+            entries.retain(|entry| !entry.is_profile());
+        }
+        let name = self
+            .current_file()
+            .map_or_else(|| "document".to_string(), |file| file.display().to_string());
+
+        let mut merged = match position {
+            Some(position) => {
+                let mut merged = self.build_bases(&entries[position].value, path, pending)?;
+                self.merge_marked(&mut merged, &own, name.clone(), path, scope, pending)?;
+                merged
+            }
+            None => match self.build(&own, path, scope, pending)? {
+                Value::Table(table) => table,
+                _ => BTreeMap::new(),
+            },
+        };
+        if !profile.is_empty() {
+            let overlay = AstNode::new(
+                AstValue::Table {
+                    entries: profile,
+                    inline: false,
+                },
+                root.span,
+            );
+            self.merge_marked(&mut merged, &overlay, name, path, scope, pending)?;
+        }
+        Ok(Value::Table(merged))
+    }
+
+    /// Build and deep-merge the documents named by an `extends` value, in order
+    fn build_bases(
+        &mut self,
+        extends: &AstNode,
+        path: &mut Vec<String>,
+        pending: &mut Vec<Pending>,
+    ) -> Result<BTreeMap<String, Value>> {
+        let span = extends.span;
+        let bases: Vec<String> = match &extends.value {
+            AstValue::String { value, .. } => vec![value.clone()],
+            AstValue::Array { elements, .. } => elements
+                .iter()
+                .map(|element| match &element.value {
+                    AstValue::String { value, .. } => Ok(value.clone()),
+                    _ => Err(NomlError::parse(
+                        "'extends' must list file paths",
+                        element.span.start_line,
+                        element.span.start_column,
+                    )),
+                })
+                .collect::<Result<_>>()?,
+            _ => {
+                return Err(NomlError::parse(
+                    "'extends' must be a file path or an array of file paths",
+                    span.start_line,
+                    span.start_column,
+                ))
+            }
+        };
+
+        let mut merged = BTreeMap::new();
+        for base in &bases {
+            self.config.security.check_include(base)?;
+            if security::is_remote(base) {
+                return Err(NomlError::import(base, "'extends' needs a local file"));
+            }
+            let resolved = self.resolve_include_path(base);
+            self.check_extends_cycle(&resolved)?;
+            let name = resolved.display().to_string();
+            let built = self.build_apart(path, name, |resolver, path, pending| {
+                resolver.build_file(resolved, None, &span, path, pending)
+            })?;
+            self.merge_apart(
+                &mut merged,
+                built,
+                MergeStrategy::Deep,
+                &HashMap::new(),
+                path,
+                pending,
+            )?;
+        }
+        Ok(merged)
+    }
+
+    /// Deep-merge `node` over `merged`, honouring the merge markers it holds
+    fn merge_marked(
+        &mut self,
+        merged: &mut BTreeMap<String, Value>,
+        node: &AstNode,
+        name: String,
+        path: &mut Vec<String>,
+        scope: &[String],
+        pending: &mut Vec<Pending>,
+    ) -> Result<()> {
+        let outer = self.key_merges.replace(HashMap::new());
+        let built = self.build_apart(path, name, |resolver, path, pending| {
+            resolver.build(node, path, scope, pending)
+        });
+        let keys = std::mem::replace(&mut self.key_merges, outer).unwrap_or_default();
+        self.merge_apart(merged, built?, MergeStrategy::Deep, &keys, path, pending)
+    }
+
+    /// Collect the entries the active profile sets from a `profile` entry
+    /// whose key continues with `rest`, recording every profile declared
+    fn profile_entries(
+        &mut self,
+        entry: &TableEntry,
+        rest: &[KeySegment],
+        out: &mut Vec<TableEntry>,
+    ) -> Result<()> {
+        let span = entry.value.span;
+        let not_table = || {
+            NomlError::parse(
+                "profile sections must be tables, as in [@profile.production]",
+                span.start_line,
+                span.start_column,
+            )
+        };
+        let Some((name, keys)) = rest.split_first() else {
+            // `[@profile]` itself, holding one table per profile
+            let AstValue::Table { entries, .. } = &entry.value.value else {
+                return Err(not_table());
+            };
+            for inner in entries {
+                self.profile_entries(inner, &inner.key.segments, out)?;
+            }
+            return Ok(());
+        };
+        self.profiles.insert(name.name.clone());
+        if self.config.profile.as_deref() != Some(&name.name) {
+            return Ok(());
+        }
+        if !keys.is_empty() {
+            out.push(TableEntry {
+                key: Key {
+                    segments: keys.to_vec(),
+                    span: entry.key.span,
+                },
+                ..entry.clone()
+            });
+            return Ok(());
+        }
+        match &entry.value.value {
+            AstValue::Table { entries, .. } => out.extend(entries.iter().cloned()),
+            _ => return Err(not_table()),
+        }
+        Ok(())
+    }
+
+    /// Fail if extending `base` would extend a file already being resolved
+    fn check_extends_cycle(&self, base: &Path) -> Result<()> {
+        let files: Vec<&PathBuf> = self.source_file.iter().chain(&self.include_stack).collect();
+        let Some(start) = files.iter().position(|file| file.as_path() == base) else {
+            return Ok(());
+        };
+        let chain: Vec<String> = files[start..]
+            .iter()
+            .map(|file| file.display().to_string())
+            .chain(std::iter::once(base.display().to_string()))
+            .collect();
+        Err(NomlError::circular_reference(chain.join(" -> ")))
+    }
+
+    /// Build the value of a key marked `@replace`, `@append`, `@prepend` or
+    /// `@unset`, recording how it merges over the base document
+    fn build_key_merge(
+        &mut self,
+        type_name: &str,
+        args: &[AstNode],
+        span: &Span,
+        path: &mut Vec<String>,
+        scope: &[String],
+        pending: &mut Vec<Pending>,
+    ) -> Result<Value> {
+        let (Some(rule), Some(keys)) = (KeyMerge::from_name(type_name), &mut self.key_merges)
+        else {
+            return Err(NomlError::parse(
+                format!(
+                    "@{type_name} can only be used in a profile or a document that extends another"
+                ),
+                span.start_line,
+                span.start_column,
+            ));
+        };
+        keys.insert(path.clone(), rule);
+        match (rule, args) {
+            (KeyMerge::Unset, []) => Ok(Value::Null),
+            (KeyMerge::Unset, _) => Err(NomlError::parse(
+                "@unset takes no arguments",
+                span.start_line,
+                span.start_column,
+            )),
+            (_, [value]) => self.build(value, path, scope, pending),
+            _ => Err(NomlError::parse(
+                format!("@{type_name} takes exactly one value"),
+                span.start_line,
+                span.start_column,
+            )),
+        }
+    }
+
+    /// Build a value with its own interpolations and origins, so they can
+    /// follow the values a later merge moves
+    fn build_apart(
+        &mut self,
+        path: &mut Vec<String>,
+        name: String,
+        build: impl FnOnce(&mut Self, &mut Vec<String>, &mut Vec<Pending>) -> Result<Value>,
+    ) -> Result<Built> {
+        let selections = self.selections.len();
+        let mut pending = Vec::new();
+        let outer = self.provenance.as_mut().map(|provenance| {
+            let mut own = Provenance::new();
+            if let Some(origin) = provenance.get(&path.join(".")) {
+                own.insert(path.join("."), origin.clone());
+            }
+            std::mem::replace(provenance, own)
+        });
+        let value = build(self, path, &mut pending);
+        let provenance = std::mem::replace(&mut self.provenance, outer);
+        Ok(Built {
+            name,
+            value: value?,
+            pending,
+            provenance,
+            selections,
+        })
+    }
+
+    /// Merge a value built apart into `merged`, moving the interpolations,
+    /// origins and selective includes of both to where their values end up
+    fn merge_apart(
+        &mut self,
+        merged: &mut BTreeMap<String, Value>,
+        built: Built,
+        strategy: MergeStrategy,
+        keys: &HashMap<Vec<String>, KeyMerge>,
+        path: &[String],
+        pending: &mut Vec<Pending>,
+    ) -> Result<()> {
+        let Built {
+            name,
+            value,
+            pending: mut own_pending,
+            provenance: mut own_provenance,
+            selections,
+        } = built;
+        let mut own_selections = self.selections.split_off(selections);
+        let Value::Table(table) = value else {
+            return Ok(());
+        };
+
+        let mut moved = Vec::new();
+        merge::merge(
+            merged,
+            table,
+            strategy,
+            keys,
+            &mut path.to_vec(),
+            &mut moved,
+        )
+        .map_err(|key| {
+            NomlError::import(
+                &name,
+                format!("'{}' is already set by an earlier file", key.join(".")),
+            )
+        })?;
+
+        for change in moved {
+            match change {
+                Moved::Replaced(replaced) => {
+                    pending.retain(|item| !item.path.starts_with(&replaced));
+                    self.selections
+                        .retain(|selection| !selection.mount.starts_with(&replaced));
+                    if let Some(provenance) = &mut self.provenance {
+                        provenance.remove(&replaced.join("."));
+                    }
+                }
+                Moved::Removed(removed) => {
+                    pending.retain(|item| !item.path.starts_with(&removed));
+                    own_pending.retain(|item| !item.path.starts_with(&removed));
+                    self.selections
+                        .retain(|selection| !selection.mount.starts_with(&removed));
+                    own_selections.retain(|selection| !selection.mount.starts_with(&removed));
+                    for provenance in [&mut self.provenance, &mut own_provenance]
+                        .into_iter()
+                        .flatten()
+                    {
+                        provenance.remove(&removed.join("."));
+                    }
+                }
+                Moved::Shifted { array, offset } => {
+                    for item in &mut own_pending {
+                        merge::shift_index(&mut item.path, &array, offset);
+                    }
+                    for selection in &mut own_selections {
+                        merge::shift_index(&mut selection.mount, &array, offset);
+                    }
+                    if let Some(provenance) = &mut own_provenance {
+                        provenance.shift_elements(&array, offset);
+                    }
+                }
+                Moved::Prepended { array, count } => {
+                    for item in pending.iter_mut() {
+                        merge::shift_index(&mut item.path, &array, count);
+                    }
+                    for selection in &mut self.selections {
+                        merge::shift_index(&mut selection.mount, &array, count);
+                    }
+                    if let Some(provenance) = &mut self.provenance {
+                        provenance.shift_elements(&array, count);
+                    }
+                }
+            }
+        }
+        pending.append(&mut own_pending);
+        self.selections.append(&mut own_selections);
+        if let (Some(provenance), Some(own)) = (&mut self.provenance, own_provenance) {
+            provenance.extend(own);
+        }
+        Ok(())
+    }
+
+    /// Files matched by a glob or directory include, in sorted order;
+    /// hidden files only match patterns starting with `.`
+    fn matching_files(
+        &self,
+        include_path: &str,
+        directory: bool,
+        span: &Span,
+    ) -> Result<Vec<PathBuf>> {
+        let resolved = self.resolve_include_path(include_path);
+        let (dir, pattern) = if directory {
+            (resolved.as_path(), "*.noml")
+        } else {
+            (
+                resolved.parent().unwrap_or(Path::new("")),
+                resolved.file_name().and_then(OsStr::to_str).unwrap_or(""),
+            )
+        };
+        if dir.to_string_lossy().contains('*') {
+            return Err(NomlError::parse(
+                format!("Include '{include_path}': '*' is only supported in the file name"),
+                span.start_line,
+                span.start_column,
+            ));
+        }
+
+        let listed = self.config.loader.list(dir)?;
+        self.manifest.lock().unwrap().record_dir(dir, &listed);
+        let mut files: Vec<PathBuf> = listed
+            .into_iter()
+            .filter(|file| {
+                file.file_name()
+                    .and_then(OsStr::to_str)
+                    .is_some_and(|name| {
+                        (!name.starts_with('.') || pattern.starts_with('.'))
+                            && security::wildcard_match(pattern, name)
+                    })
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Resolve an include path relative to the current file or base path
+    fn resolve_include_path(&self, include_path: &str) -> PathBuf {
+        self.config.loader.resolve(include_path, self.current_dir())
+    }
+
+    /// The file being resolved, if known
+    fn current_file(&self) -> Option<&Path> {
+        self.include_stack
+            .last()
+            .or(self.source_file.as_ref())
+            .map(PathBuf::as_path)
+    }
+
+    /// Directory of the file being resolved, or the configured base path
+    fn current_dir(&self) -> Option<&Path> {
+        match self.include_stack.last() {
+            Some(current_file) => Some(current_file.parent().unwrap_or(Path::new("."))),
+            None => self.config.base_path.as_deref(),
+        }
+    }
+
+    /// Resolve interpolation patterns in strings like "Hello ${name}!", also
+    /// returning whether a secret went into the result
+    fn resolve_interpolation_in_string(&self, text: &str, span: &Span) -> Result<(String, bool)> {
+        let parts = interpolation::split(text).map_err(|_| {
+            NomlError::parse(
+                "Unclosed interpolation in string: expected '}'",
+                span.start_line,
+                span.start_column,
+            )
+        })?;
+
+        let mut result = String::new();
+        let mut secret = false;
+        for part in parts {
+            let reference = match part {
+                Part::Text(text) => {
+                    result.push_str(&text);
+                    continue;
+                }
+                Part::Reference(reference) => reference,
+            };
+            if let Some(text) = self.fallback_for(&reference.path, reference.fallback.as_ref())? {
+                result.push_str(text);
+                continue;
+            }
+            match self.resolve_variable_path(&reference.path)? {
+                Value::String(text) => result.push_str(&text),
+                Value::Secret(text) => {
+                    secret = true;
+                    result.push_str(text.expose());
+                }
+                Value::Table(_) => {
+                    return Err(NomlError::interpolation(
+                        "Cannot interpolate a table into a string; use the reference as the whole value instead",
+                        reference.path,
+                    ));
+                }
+                other => result.push_str(&stdlib::text(&other)),
+            }
+        }
+
+        Ok((result, secret))
+    }
+
+    /// Extract a runtime Value from an AST node
+    fn extract_value(&self, node: AstNode) -> Result<Value> {
+        match node.value {
+            AstValue::String { value, .. } => Ok(Value::String(value)),
+            AstValue::Integer { value, .. } => Ok(Value::Integer(value)),
+            AstValue::Float { value, .. } => Ok(Value::Float(value)),
+            AstValue::Bool(value) => Ok(Value::Bool(value)),
+            AstValue::Null => Ok(Value::Null),
+            AstValue::Table { entries, .. } => {
+                let mut result = Value::Table(BTreeMap::new());
+                for entry in entries {
+                    let key = entry.key.to_string();
+                    let value = self.extract_value(entry.value)?;
+                    result.set(&key, value)?;
+                }
+                Ok(result)
+            }
+            AstValue::Array { elements, .. } => {
+                let mut arr = Vec::new();
+                for element in elements {
+                    arr.push(self.extract_value(element.clone())?);
+                }
+                Ok(Value::Array(arr))
+            }
+            AstValue::Native { type_name, args } => {
+                // Convert args to values
+                let arg_values: Result<Vec<Value>> = args
+                    .iter()
+                    .map(|arg| self.extract_value(arg.clone()))
+                    .collect();
+                let arg_values = arg_values?;
+
+                // Resolve the native type
+                let resolver = self
+                    .config
+                    .native_resolvers
+                    .get(&type_name)
+                    .ok_or_else(|| {
+                        NomlError::parse(format!("Unknown native type: @{type_name}"), 0, 0)
+                    })?;
+
+                let resolved_value = resolver.resolve(&arg_values)?;
+
+                // No Value::Native variant exists, so just return the resolved value
+                Ok(resolved_value)
+            }
+            _ => Err(NomlError::parse(
+                "Cannot extract value from unresolved AST node".to_string(),
+                0,
+                0,
+            )),
+        }
+    }
+
+    /// Convert a runtime Value back to an AST node
+    fn value_to_ast_node(&self, value: Value, span: Span) -> Result<AstNode> {
+        // `AstNode::from_value` refuses secrets; here they stay in memory
+        let mut node = node_from_value(&value, &|secret| {
+            Ok(secret_node(secret.expose().to_string(), span))
+        })?;
+        node.span = span;
+        Ok(node)
+    }
+
+    /// Async version of resolve_document for HTTP includes support
+    #[cfg(feature = "async")]
+    pub async fn resolve_document_async(&mut self, document: &Document) -> Result<Value> {
+        // First, resolve HTTP includes non-recursively to build the complete AST
+        self.start()?;
+        let resolved_doc = self.resolve_http_includes_simple(document).await?;
+
+        // Then use the regular sync resolver on the complete AST
+        self.resolve_document(&resolved_doc)
+    }
+
+    /// Simple non-recursive HTTP include resolution (does not support nested HTTP includes)
+    #[cfg(feature = "async")]
+    async fn resolve_http_includes_simple(&mut self, document: &Document) -> Result<Document> {
+        // Collect all HTTP includes first
+        let http_includes = self.collect_http_includes(&document.root);
+
+        // Fetch the content of each, or take it from the lockfile offline
+        let mut http_content = HashMap::new();
+        for (url, pin) in http_includes {
+            self.config.security.check_include(&url)?;
+            let content = if self.config.offline {
+                self.locked_content(&url)?
+            } else {
+                self.fetch_http_content(&url, &Span::default()).await?
+            };
+            self.accept_remote(&url, pin.as_deref(), &content)?;
+            http_content.insert(url, content);
+        }
+
+        // Replace HTTP includes with their content
+        self.with_remote_contents(document, &http_content)
+    }
+
+    /// Collect all HTTP include URLs and their `sha256` pins from an AST
+    /// node (recursive but sync)
+    #[allow(clippy::only_used_in_recursion)]
+    fn collect_http_includes(&self, node: &AstNode) -> Vec<(String, Option<String>)> {
+        let mut includes = Vec::new();
+
+        match &node.value {
+            AstValue::Include { path, sha256, .. } if security::is_remote(path) => {
+                includes.push((path.clone(), sha256.clone()));
+            }
+            AstValue::Table { entries, .. } => {
+                for entry in entries {
+                    includes.extend(self.collect_http_includes(&entry.value));
+                }
+            }
+            AstValue::Array { elements, .. } => {
+                for element in elements {
+                    includes.extend(self.collect_http_includes(element));
+                }
+            }
+            _ => {}
+        }
+
+        includes
+    }
+
+    /// Replace HTTP includes with their content (sync recursion is fine)
+    #[allow(clippy::only_used_in_recursion)]
+    fn replace_http_includes_with_content(
+        &self,
+        node: &AstNode,
+        content_map: &HashMap<String, String>,
+    ) -> Result<AstNode> {
+        let span = node.span;
+        let comments = node.comments.clone();
+
+        let ast_value = match &node.value {
+            AstValue::Include {
+                path,
+                subpath,
+                only,
+                ..
+            } => {
+                if (subpath.is_some() || only.is_some()) && security::is_remote(path) {
+                    return Err(NomlError::import(
+                        path,
+                        "selecting part of an HTTP include is not supported",
+                    ));
+                }
+                if path.starts_with("http://") || path.starts_with("https://") {
+                    if let Some(content) = content_map.get(path) {
+                        let doc = crate::parser::parse(content).map_err(|e| {
+                            NomlError::parse(
+                                format!("Failed to parse HTTP include '{path}': {e}"),
+                                span.start_line,
+                                span.start_column,
+                            )
+                        })?;
+                        return Ok(doc.root);
+                    } else {
+                        return Err(NomlError::parse(
+                            format!("HTTP include '{path}' not found in content map"),
+                            span.start_line,
+                            span.start_column,
+                        ));
+                    }
+                } else {
+                    node.value.clone()
+                }
+            }
+            AstValue::Table { entries, inline } => {
+                let mut resolved_entries = Vec::new();
+                for entry in entries {
+                    let resolved_value =
+                        self.replace_http_includes_with_content(&entry.value, content_map)?;
+                    resolved_entries.push(TableEntry {
+                        key: entry.key.clone(),
+                        value: resolved_value,
+                        comments: entry.comments.clone(),
+                    });
+                }
+                AstValue::Table {
+                    entries: resolved_entries,
+                    inline: *inline,
+                }
+            }
+            AstValue::Array {
+                elements,
+                multiline,
+                trailing_comma,
+            } => {
+                let mut resolved_elements = Vec::new();
+                for element in elements {
+                    resolved_elements
+                        .push(self.replace_http_includes_with_content(element, content_map)?);
+                }
+                AstValue::Array {
+                    elements: resolved_elements,
+                    multiline: *multiline,
+                    trailing_comma: *trailing_comma,
+                }
+            }
+            _ => node.value.clone(),
+        };
+
+        Ok(AstNode {
+            value: ast_value,
+            span,
+            comments,
+            format: crate::parser::ast::FormatMetadata::default(),
+        })
+    }
+
+    /// Fetch content from HTTP URL with caching
+    #[cfg(feature = "async")]
+    async fn fetch_http_content(&mut self, url: &str, span: &Span) -> Result<String> {
+        // Check cache first
+        let cache = self.config.http_cache.clone();
+        if let Some(cached_content) = cache.as_ref().and_then(|cache| cache.get(url)) {
+            return Ok(cached_content);
+        }
+
+        // Create HTTP client with timeout
+        let client = reqwest::Client::builder()
+            .timeout(self.config.http_timeout)
+            .build()
+            .map_err(|e| {
+                NomlError::parse(
+                    format!("Failed to create HTTP client: {e}"),
+                    span.start_line,
+                    span.start_column,
+                )
+            })?;
+
+        // Fetch the content, revalidating a stale cached copy by its ETag
+        let mut request = client.get(url);
+        if let Some(etag) = cache.as_ref().and_then(|cache| cache.etag(url)) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await.map_err(|e| {
+            NomlError::parse(
+                format!("Failed to fetch HTTP include '{url}': {e}"),
+                span.start_line,
+                span.start_column,
+            )
+        })?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(content) = cache.as_ref().and_then(|cache| cache.revalidate(url)) {
+                return Ok(content);
+            }
+        }
+        if !response.status().is_success() {
+            return Err(NomlError::parse(
+                format!(
+                    "HTTP include '{url}' returned status: {}",
+                    response.status()
+                ),
+                span.start_line,
+                span.start_column,
+            ));
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let content = response.text().await.map_err(|e| {
+            NomlError::parse(
+                format!("Failed to read HTTP include '{url}': {e}"),
+                span.start_line,
+                span.start_column,
+            )
+        })?;
+
+        // Cache the content
+        if let Some(cache) = cache {
+            cache.insert(url, content.clone(), etag);
+        }
+
+        Ok(content)
+    }
+} // <-- Close impl Resolver
+
+/// Fail unless contents hashing to `hash` match the `sha256` pin of the
+/// include `name`, if it has one
+fn check_pin(name: &str, pin: Option<&str>, hash: &str) -> Result<()> {
+    match pin {
+        Some(pin) if pin != hash => Err(NomlError::security(
+            format!("Include has sha256 {hash}, but is pinned to {pin}"),
+            name,
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Built-in native type resolvers
+
+fn resolve_size(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@size() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let size_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@size() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+    match parse_size(size_str) {
+        Some(n) => Ok(Value::Size(n as u64)),
+        None => Err(NomlError::parse(
+            format!("Invalid size format: {size_str}"),
+            0,
+            0,
+        )),
+    }
+}
+
+/// Parse size strings like "10MB", "1.5GB", etc.
+pub(crate) fn parse_size(size_str: &str) -> Option<i64> {
+    let size_str = size_str.trim().to_uppercase();
+    let (number_part, unit_part) =
+        if let Some(pos) = size_str.find(|c: char| !char::is_numeric(c) && c != '.') {
+            (&size_str[..pos], &size_str[pos..])
+        } else {
+            (size_str.as_str(), "")
+        };
+
+    let number: f64 = number_part.parse().ok()?;
+
+    let multiplier = match unit_part.trim() {
+        "" => 1,
+        "B" => 1,
+        "KB" => 1_024,
+        "MB" => 1_024 * 1_024,
+        "GB" => 1_024 * 1_024 * 1_024,
+        "TB" => 1_024_i64.pow(4),
+        "PB" => 1_024_i64.pow(5),
+        _ => return None,
+    };
+
+    Some((number * multiplier as f64) as i64)
+}
+
+fn resolve_duration(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@duration() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let duration_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@duration() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+    match parse_duration(duration_str) {
+        Some(n) => Ok(Value::Duration(n)),
+        None => Err(NomlError::parse(
+            format!("Invalid duration format: {duration_str}"),
+            0,
+            0,
+        )),
+    }
+}
+
+/// Parse duration strings like "30s", "5m", "2h", etc.
+pub(crate) fn parse_duration(duration_str: &str) -> Option<f64> {
+    let duration_str = duration_str.trim().to_lowercase();
+
+    let (number_part, unit_part) = if let Some(pos) = duration_str.find(|c: char| c.is_alphabetic())
+    {
+        (&duration_str[..pos], &duration_str[pos..])
+    } else {
+        (duration_str.as_str(), "s")
+    };
+
+    let number: f64 = number_part.parse().ok()?;
+
+    let multiplier = match unit_part {
+        "ns" => 1e-9,
+        "us" | "μs" => 1e-6,
+        "ms" => 1e-3,
+        "" | "s" => 1.0,
+        "m" | "min" => 60.0,
+        "h" | "hr" | "hour" => 3600.0,
+        "d" | "day" => 86400.0,
+        "w" | "week" => 604800.0,
+        _ => return None,
+    };
+
+    Some(number * multiplier)
+}
+
+fn resolve_regex(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@regex() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let regex_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@regex() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    // Validate the regex (in a real implementation, you'd use the regex crate)
+    // For now, just return the string
+    Ok(Value::String(regex_str.to_string()))
+}
+
+fn resolve_url(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@url() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let url_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@url() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    if is_valid_url(url_str) {
+        Ok(Value::String(url_str.to_string()))
+    } else {
+        Err(NomlError::parse(
+            format!("Invalid URL format: {url_str}"),
+            0,
+            0,
+        ))
+    }
+}
+
+fn resolve_ip(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@ip() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let ip_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@ip() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    if is_valid_ip(ip_str) {
+        Ok(Value::String(ip_str.to_string()))
+    } else {
+        Err(NomlError::parse(
+            format!("Invalid IP address format: {ip_str}"),
+            0,
+            0,
+        ))
+    }
+}
+
+fn resolve_semver(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@semver() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let version_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@semver() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    if is_valid_semver(version_str) {
+        Ok(Value::String(version_str.to_string()))
+    } else {
+        Err(NomlError::parse(
+            format!("Invalid semver format: {version_str}"),
+            0,
+            0,
+        ))
+    }
+}
+
+fn resolve_base64(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@base64() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let base64_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@base64() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    // Simple base64 validation - check if it's valid base64
+    if base64_str.len() % 4 == 0
+        && base64_str
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=')
+    {
+        Ok(Value::String(base64_str.to_string()))
+    } else {
+        Err(NomlError::parse(
+            format!("Invalid base64 format: {base64_str}"),
+            0,
+            0,
+        ))
+    }
+}
+
+/// An `@secret("...")` node holding `text`
+fn secret_node(text: String, span: Span) -> AstNode {
+    let arg = AstNode::new(
+        AstValue::String {
+            has_escapes: text.contains(['"', '\\', '\n', '\r', '\t']),
+            value: text,
+            style: StringStyle::Double,
+        },
+        span,
+    );
+    AstNode::new(
+        AstValue::Native {
+            type_name: "secret".to_string(),
+            args: vec![arg],
+        },
+        span,
+    )
+}
+
+/// `@secret("...")` keeps its text out of `Display`, `Debug` and saved files
+fn resolve_secret(args: &[Value]) -> Result<Value> {
+    match args {
+        [Value::String(text)] => Ok(Value::secret(text.clone())),
+        // `@secret(@encrypted("..."))` is already one
+        [Value::Secret(secret)] => Ok(Value::Secret(secret.clone())),
+        _ => Err(NomlError::parse(
+            "@secret() requires exactly 1 string argument",
+            0,
+            0,
+        )),
+    }
+}
+
+fn resolve_uuid(args: &[Value]) -> Result<Value> {
+    if args.len() != 1 {
+        return Err(NomlError::parse(
+            "@uuid() requires exactly 1 argument".to_string(),
+            0,
+            0,
+        ));
+    }
+    let uuid_str = match args[0].as_string() {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(NomlError::parse(
+                format!("@uuid() argument must be a string: {e}"),
+                0,
+                0,
+            ));
+        }
+    };
+
+    if is_valid_uuid(uuid_str) {
+        Ok(Value::String(uuid_str.to_string()))
+    } else {
+        Err(NomlError::parse(
+            format!("Invalid UUID format: {uuid_str}"),
+            0,
+            0,
+        ))
+    }
+}
+
+/// Basic URL validation (in a real implementation, you'd use the url crate)
+pub(crate) fn is_valid_url(url_str: &str) -> bool {
+    url_str.starts_with("http://") || url_str.starts_with("https://")
+}
+
+/// Check that a string is an IPv4 or IPv6 address
+pub(crate) fn is_valid_ip(ip_str: &str) -> bool {
+    ip_str.parse::<std::net::IpAddr>().is_ok()
+}
+
+/// Basic semver validation (`MAJOR.MINOR` or `MAJOR.MINOR.PATCH`)
+pub(crate) fn is_valid_semver(version_str: &str) -> bool {
+    let parts: Vec<&str> = version_str.split('.').collect();
+    (2..=3).contains(&parts.len()) && parts.iter().all(|part| part.parse::<u32>().is_ok())
+}
+
+/// Basic UUID validation (format: 8-4-4-4-12)
+pub(crate) fn is_valid_uuid(uuid_str: &str) -> bool {
+    let parts: Vec<&str> = uuid_str.split('-').collect();
+    parts.len() == 5
+        && parts[0].len() == 8
+        && parts[1].len() == 4
+        && parts[2].len() == 4
+        && parts[3].len() == 4
+        && parts[4].len() == 12
+        && parts
+            .iter()
+            .all(|part| part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Check that a string looks like an ISO-8601 date or date-time
+/// (`2024-01-15`, `2024-01-15T10:30:00Z`, `2024-01-15 10:30:00.5+02:00`)
+pub(crate) fn is_valid_datetime(datetime_str: &str) -> bool {
+    fn digits(s: &str, n: usize) -> bool {
+        s.len() == n && s.chars().all(|c| c.is_ascii_digit())
+    }
+    /// Two digits from `min` to `max`
+    fn field(s: &str, min: u32, max: u32) -> bool {
+        digits(s, 2) && s.parse().is_ok_and(|n: u32| (min..=max).contains(&n))
+    }
+
+    let (date, time) = match datetime_str.find(['T', 't', ' ']) {
+        Some(pos) => (&datetime_str[..pos], Some(&datetime_str[pos + 1..])),
+        None => (datetime_str, None),
+    };
+
+    let date_parts: Vec<&str> = date.split('-').collect();
+    if date_parts.len() != 3 || !digits(date_parts[0], 4) || !field(date_parts[1], 1, 12) {
+        return false;
+    }
+    let year: u32 = date_parts[0].parse().unwrap_or_default();
+    let days = match date_parts[1] {
+        "02" if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        "02" => 28,
+        "04" | "06" | "09" | "11" => 30,
+        _ => 31,
+    };
+    if !field(date_parts[2], 1, days) {
+        return false;
+    }
+
+    let Some(time) = time else {
+        return true;
+    };
+
+    // Strip the UTC offset, if any
+    let time = if let Some(rest) = time.strip_suffix(['Z', 'z']) {
+        rest
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        let offset: Vec<&str> = time[pos + 1..].split(':').collect();
+        if offset.len() != 2 || !field(offset[0], 0, 23) || !field(offset[1], 0, 59) {
+            return false;
+        }
+        &time[..pos]
+    } else {
+        time
+    };
+
+    let (clock, fraction) = match time.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (time, None),
+    };
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+    }
+
+    // Seconds go up to 60 for leap seconds
+    match clock.split(':').collect::<Vec<_>>()[..] {
+        [hour, minute] => field(hour, 0, 23) && field(minute, 0, 59),
+        [hour, minute, second] => {
+            field(hour, 0, 23) && field(minute, 0, 59) && field(second, 0, 60)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1KB"), Some(1024));
+        assert_eq!(parse_size("1MB"), Some(1024 * 1024));
+        assert_eq!(
+            parse_size("1.5GB"),
+            Some((1.5 * 1024.0 * 1024.0 * 1024.0) as i64)
+        );
+        assert_eq!(parse_size("invalid"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Some(30.0));
+        assert_eq!(parse_duration("5m"), Some(300.0));
+        assert_eq!(parse_duration("2h"), Some(7200.0));
+        assert_eq!(parse_duration("1d"), Some(86400.0));
+        assert_eq!(parse_duration("invalid"), None);
+    }
+
+    #[test]
+    fn test_resolve_size_duration_url() {
+        let size_result = resolve_size(&[Value::String("10MB".to_string())]).unwrap();
+        assert_eq!(size_result.as_integer().unwrap(), 10 * 1024 * 1024);
+
+        let duration_result = resolve_duration(&[Value::String("30s".to_string())]).unwrap();
+        let duration_val = duration_result.as_float().unwrap();
+        assert!(
+            (duration_val - 30.0).abs() < f64::EPSILON,
+            "Expected 30.0, got {duration_val}"
+        );
+
+        let url_result = resolve_url(&[Value::String("https://example.com".to_string())]).unwrap();
+        assert_eq!(url_result.as_string().unwrap(), "https://example.com");
+
+        let url_result = resolve_url(&[Value::String("https://example.com".to_string())]).unwrap();
+        assert_eq!(url_result.as_string().unwrap(), "https://example.com");
+    }
+
+    #[test]
+    fn test_dependency_ordered_interpolation() {
+        let document = crate::parser::parse(
+            r#"
+            first = ${second}
+            second = "${servers.1.name}!"
+            servers = [{ name = "a" }, { name = "${base}-b" }]
+            base = "node"
+            self_ref = "${self_ref}"
+            "#,
+        )
+        .unwrap();
+
+        let err = Resolver::new().resolve(&document).unwrap_err();
+        assert!(matches!(
+            err,
+            NomlError::CircularReference { ref chain } if chain == "self_ref -> self_ref"
+        ));
+
+        let document = crate::parser::parse(
+            r#"
+            first = ${second}
+            second = "${servers.1.name}!"
+            servers = [{ name = "a" }, { name = "${base}-b" }]
+            base = "node"
+            "#,
+        )
+        .unwrap();
+        let value = Resolver::new().resolve(&document).unwrap();
+        assert_eq!(value.get("first").unwrap().as_string().unwrap(), "node-b!");
+        assert_eq!(
+            value.get("servers.1.name").unwrap().as_string().unwrap(),
+            "node-b"
+        );
+    }
+
+    #[test]
+    fn test_whole_value_interpolation_keeps_type() {
+        let document = crate::parser::parse(
+            r#"
+            timeout = @duration("30s")
+            limit = @size("10MB")
+            ports = [80, 443]
+            database = { host = "db", port = 5432 }
+            copy_timeout = ${timeout}
+            copy_limit = ${limit}
+            copy_ports = ${ports}
+            copy_database = ${database}
+            message = "wait ${timeout} for ${limit}"
+            exact = "${long} ${odd}"
+            long = @duration("100s")
+            odd = @size("1500")
+            "#,
+        )
+        .unwrap();
+        let value = Resolver::new().resolve(&document).unwrap();
+
+        assert_eq!(value.get("copy_timeout"), Some(&Value::Duration(30.0)));
+        assert_eq!(
+            value.get("copy_limit"),
+            Some(&Value::Size(10 * 1024 * 1024))
+        );
+        assert_eq!(value.get("copy_ports"), value.get("ports"));
+        assert_eq!(value.get("copy_database"), value.get("database"));
+        assert_eq!(
+            value.get("message").unwrap().as_string().unwrap(),
+            "wait 30s for 10MB"
+        );
+        assert_eq!(
+            value.get("exact").unwrap().as_string().unwrap(),
+            "100s 1500B"
+        );
+        assert_eq!(value.get("long").unwrap().as_integer().unwrap(), 100);
+        assert_eq!(value.get("odd").unwrap().as_integer().unwrap(), 1500);
+
+        let document = crate::parser::parse(
+            r#"
+            database = { host = "db" }
+            url = "postgres://${database}"
+            "#,
+        )
+        .unwrap();
+        let err = Resolver::new().resolve(&document).unwrap_err();
+        assert!(err.to_string().contains("Cannot interpolate a table"));
+    }
+
+    #[test]
+    fn test_interpolation_escapes_and_fallbacks() {
+        let document = crate::parser::parse(
+            r#"
+            name = "svc"
+            empty = ""
+            shell = "echo $${HOME} for ${name}"
+            escaped = "\${PATH}"
+            ttl = ${cache.ttl:-60}
+            label = "${missing:-default label} / ${empty:-none} / ${name:-unused}"
+            "#,
+        )
+        .unwrap();
+        let value = Resolver::new().resolve(&document).unwrap();
+
+        assert_eq!(
+            value.get("shell").unwrap().as_string().unwrap(),
+            "echo ${HOME} for svc"
+        );
+        assert_eq!(
+            value.get("escaped").unwrap().as_string().unwrap(),
+            "${PATH}"
+        );
+        assert_eq!(value.get("ttl"), Some(&Value::Integer(60)));
+        assert_eq!(
+            value.get("label").unwrap().as_string().unwrap(),
+            "default label / none / svc"
+        );
+
+        let document =
+            crate::parser::parse("token = \"${api.token:?set api.token first}\"").unwrap();
+        let err = Resolver::new().resolve(&document).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Interpolation error in 'token' at line 1, column 9: set api.token first"
+        );
+
+        let document = crate::parser::parse("\n\nurl = \"http://${host}\"").unwrap();
+        let err = Resolver::new().resolve(&document).unwrap_err();
+        assert!(err.to_string().contains("in 'url' at line 3"), "{err}");
+
+        let document = crate::parser::parse("\nurl = \"http://${host\"").unwrap();
+        let err = Resolver::new().resolve(&document).unwrap_err();
+        assert!(matches!(err, NomlError::Parse { line: 2, .. }), "{err}");
+    }
+}
@@ -0,0 +1,320 @@
+//! # Native Types
+//!
+//! `@name(args)` values are resolved by a [`NativeType`] registered under
+//! `name` in [`ResolverConfig::native_resolvers`](super::ResolverConfig).
+//! Besides resolving, a native type can validate its arguments, describe
+//! how a value is written back to source and which schema type it maps to.
+//!
+//! ```rust
+//! use noml::{parse_string, FieldType, NativeResolver, NativeType, Resolver, ResolverConfig, Value};
+//!
+//! struct Port;
+//!
+//! impl NativeType for Port {
+//!     fn resolve(&self, args: &[Value]) -> noml::Result<Value> {
+//!         let port = args.first().map(Value::as_integer).transpose()?.unwrap_or(0);
+//!         if (1..=65535).contains(&port) {
+//!             Ok(Value::Integer(port))
+//!         } else {
+//!             Err(noml::NomlError::validation(format!("Invalid port: {port}")))
+//!         }
+//!     }
+//!
+//!     fn field_type(&self) -> Option<FieldType> {
+//!         Some(FieldType::Integer)
+//!     }
+//! }
+//!
+//! let mut config = ResolverConfig::default();
+//! config
+//!     .native_resolvers
+//!     .insert("port".to_string(), NativeResolver::from_type(Port));
+//!
+//! // Custom types survive cloning the configuration
+//! let document = parse_string("listen = @port(8080)", None)?;
+//! let value = Resolver::with_config(config.clone()).resolve(&document)?;
+//! assert_eq!(value.get("listen"), Some(&Value::Integer(8080)));
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use crate::error::Result;
+use crate::parser::ast::{AstNode, AstValue, Span, StringStyle};
+use crate::schema::FieldType;
+use crate::value::{format_size_exact, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A custom `@name(...)` value type
+pub trait NativeType: Send + Sync {
+    /// Resolve the arguments of `@name(...)` to a value
+    fn resolve(&self, args: &[Value]) -> Result<Value>;
+
+    /// Check the arguments without keeping the result; resolves by default
+    fn validate(&self, args: &[Value]) -> Result<()> {
+        self.resolve(args).map(|_| ())
+    }
+
+    /// The argument that reproduces `value` when written as `@name("...")`,
+    /// or `None` if the value can't be written back
+    fn format(&self, value: &Value) -> Option<String> {
+        let _ = value;
+        None
+    }
+
+    /// The schema type resolved values satisfy, if any
+    fn field_type(&self) -> Option<FieldType> {
+        None
+    }
+}
+
+/// Type alias for native resolver functions
+type NativeResolverFn = Box<dyn Fn(&[Value]) -> Result<Value> + Send + Sync>;
+
+/// A native type made from a resolve function
+struct FnNative(NativeResolverFn);
+
+impl NativeType for FnNative {
+    fn resolve(&self, args: &[Value]) -> Result<Value> {
+        (self.0)(args)
+    }
+}
+
+/// One of the built-in native types
+struct Builtin {
+    resolve: fn(&[Value]) -> Result<Value>,
+    field_type: FieldType,
+}
+
+impl NativeType for Builtin {
+    fn resolve(&self, args: &[Value]) -> Result<Value> {
+        (self.resolve)(args)
+    }
+
+    fn format(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::Size(bytes) => Some(format_size_exact(*bytes)),
+            Value::Duration(seconds) => Some(format!("{seconds}s")),
+            // A plain string written into `@secret(...)` would become a secret
+            Value::String(s) if self.field_type != FieldType::Secret => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn field_type(&self) -> Option<FieldType> {
+        Some(self.field_type.clone())
+    }
+}
+
+/// A registered native type resolver
+///
+/// Cloning is cheap and keeps custom types, so a cloned
+/// [`ResolverConfig`](super::ResolverConfig) resolves the same documents.
+#[derive(Clone)]
+pub struct NativeResolver {
+    native: Arc<dyn NativeType>,
+}
+
+impl NativeResolver {
+    /// Creates a new `NativeResolver` from the given resolver function.
+    pub fn new<F>(resolver: F) -> Self
+    where
+        F: Fn(&[Value]) -> Result<Value> + Send + Sync + 'static,
+    {
+        Self::from_type(FnNative(Box::new(resolver)))
+    }
+
+    /// Creates a `NativeResolver` from a [`NativeType`] implementation
+    pub fn from_type<T: NativeType + 'static>(native: T) -> Self {
+        Self {
+            native: Arc::new(native),
+        }
+    }
+
+    /// Resolves the native type using the provided arguments.
+    pub fn resolve(&self, args: &[Value]) -> Result<Value> {
+        self.native.resolve(args)
+    }
+
+    /// Checks the arguments without keeping the resolved value
+    pub fn validate(&self, args: &[Value]) -> Result<()> {
+        self.native.validate(args)
+    }
+
+    /// The argument that reproduces `value`, if it can be written back
+    pub fn format(&self, value: &Value) -> Option<String> {
+        self.native.format(value)
+    }
+
+    /// The schema type resolved values satisfy, if declared
+    pub fn field_type(&self) -> Option<FieldType> {
+        self.native.field_type()
+    }
+
+    /// Build the `@type_name("...")` node that writes `value` back to source,
+    /// or `None` if the type can't write it
+    ///
+    /// The argument is checked with [`NativeResolver::validate`] first, so a
+    /// value the type rejects fails here rather than on the next load.
+    pub fn to_node(&self, type_name: &str, value: &Value) -> Result<Option<AstNode>> {
+        let Some(text) = self.format(value) else {
+            return Ok(None);
+        };
+        self.validate(&[Value::String(text.clone())])?;
+        let arg = AstNode::new(
+            AstValue::String {
+                has_escapes: text.contains(['"', '\\', '\n', '\r', '\t']),
+                value: text,
+                style: StringStyle::Double,
+            },
+            Span::default(),
+        );
+        Ok(Some(AstNode::new(
+            AstValue::Native {
+                type_name: type_name.to_string(),
+                args: vec![arg],
+            },
+            Span::default(),
+        )))
+    }
+}
+
+impl fmt::Debug for NativeResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("NativeResolver");
+        if let Some(field_type) = self.field_type() {
+            debug.field("field_type", &field_type);
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+/// The built-in native types, by name
+pub(crate) fn builtins() -> HashMap<String, NativeResolver> {
+    let builtin = |resolve, field_type| {
+        NativeResolver::from_type(Builtin {
+            resolve,
+            field_type,
+        })
+    };
+    #[allow(unused_mut)]
+    let mut builtins = HashMap::from([
+        (
+            "size".to_string(),
+            builtin(super::resolve_size, FieldType::Size),
+        ),
+        (
+            "duration".to_string(),
+            builtin(super::resolve_duration, FieldType::Duration),
+        ),
+        (
+            "regex".to_string(),
+            builtin(super::resolve_regex, FieldType::String),
+        ),
+        (
+            "url".to_string(),
+            builtin(super::resolve_url, FieldType::Url),
+        ),
+        ("ip".to_string(), builtin(super::resolve_ip, FieldType::Ip)),
+        (
+            "semver".to_string(),
+            builtin(super::resolve_semver, FieldType::Semver),
+        ),
+        (
+            "base64".to_string(),
+            builtin(super::resolve_base64, FieldType::String),
+        ),
+        (
+            "uuid".to_string(),
+            builtin(super::resolve_uuid, FieldType::Uuid),
+        ),
+        (
+            "secret".to_string(),
+            builtin(super::resolve_secret, FieldType::Secret),
+        ),
+    ]);
+    #[cfg(feature = "encryption")]
+    builtins.insert(
+        "encrypted".to_string(),
+        super::encryption::native(std::sync::Arc::new(super::encryption::EnvKey::default())),
+    );
+    builtins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_string;
+    use crate::resolver::Resolver;
+    use crate::serializer::serialize_document;
+
+    /// `@percent("50%")` as a float between 0 and 1
+    struct Percent;
+
+    impl NativeType for Percent {
+        fn resolve(&self, args: &[Value]) -> Result<Value> {
+            let text = args[0].as_string()?;
+            let number = text
+                .strip_suffix('%')
+                .and_then(|n| n.parse::<f64>().ok())
+                .ok_or_else(|| crate::NomlError::validation(format!("Invalid percent: {text}")))?;
+            Ok(Value::Float(number / 100.0))
+        }
+
+        fn format(&self, value: &Value) -> Option<String> {
+            Some(format!("{}%", value.as_float().ok()? * 100.0))
+        }
+
+        fn field_type(&self) -> Option<FieldType> {
+            Some(FieldType::Float)
+        }
+    }
+
+    #[test]
+    fn test_custom_native_types_survive_clone() {
+        let resolver = Resolver::new()
+            .with_native_resolver("percent", NativeResolver::from_type(Percent))
+            .with_native_resolver(
+                "twice",
+                NativeResolver::new(|args| Ok(Value::Integer(args[0].as_integer()? * 2))),
+            );
+        let config = resolver.config.clone();
+        assert_eq!(config.native_field_type("percent"), Some(FieldType::Float));
+        assert_eq!(config.native_field_type("size"), Some(FieldType::Size));
+        assert_eq!(config.native_field_type("twice"), None);
+
+        let mut document = parse_string(
+            r#"ratio = @percent("25%")
+count = @twice(21)
+limit = @size("1MB")"#,
+            None,
+        )
+        .unwrap();
+        let value = Resolver::with_config(config.clone())
+            .resolve(&document)
+            .unwrap();
+        assert_eq!(value.get("ratio"), Some(&Value::Float(0.25)));
+        assert_eq!(value.get("count"), Some(&Value::Integer(42)));
+
+        let bad = parse_string(r#"ratio = @percent("lots")"#, None).unwrap();
+        assert!(Resolver::with_config(config.clone()).resolve(&bad).is_err());
+
+        // The format hook writes values back as native constructors
+        let percent = &config.native_resolvers["percent"];
+        let node = percent
+            .to_node("percent", &Value::Float(0.5))
+            .unwrap()
+            .unwrap();
+        document.set_node("ratio", node).unwrap();
+        let size = config.native_resolvers["size"]
+            .to_node("size", &Value::Size(2 * 1024 * 1024))
+            .unwrap()
+            .unwrap();
+        document.set_node("limit", size).unwrap();
+        assert_eq!(
+            serialize_document(&document).unwrap(),
+            "ratio = @percent(\"50%\")\ncount = @twice(21)\nlimit = @size(\"2MB\")\n"
+        );
+    }
+}
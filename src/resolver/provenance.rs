@@ -0,0 +1,222 @@
+//! # Provenance
+//!
+//! Where every resolved value came from: the file and span it was written
+//! at, the include chain that pulled that file in, and whether the value is
+//! a literal, an `env()` lookup, an interpolation or a default. Recorded by
+//! [`Resolver::resolve_with_provenance`](super::Resolver::resolve_with_provenance)
+//! and kept by [`Config`](crate::Config), which answers
+//! [`Config::origin`](crate::Config::origin).
+
+use super::merge::shift_index;
+use crate::parser::ast::Span;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// How a value was produced
+#[derive(Debug, Clone, PartialEq)]
+pub enum OriginKind {
+    /// Written directly in the source
+    Literal,
+    /// Read by `env("VAR")`; `found` is false when the default was used
+    Env {
+        /// Name of the environment variable
+        var: String,
+        /// Whether the variable was set
+        found: bool,
+    },
+    /// Returned by another function call
+    Function {
+        /// Name of the function
+        name: String,
+    },
+    /// Built by a native type such as `@size()`
+    Native {
+        /// Name of the native type
+        type_name: String,
+    },
+    /// Built from `${...}` references to other keys
+    Interpolation {
+        /// The paths referenced
+        references: Vec<String>,
+    },
+    /// Mounted by an `include` statement
+    Include {
+        /// The included path, as written
+        path: String,
+    },
+    /// Filled in by a default value when the key was missing
+    Default,
+    /// Set through the API, e.g. [`Config::set`](crate::Config::set)
+    Assigned,
+}
+
+/// Where a single value came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    /// How the value was produced
+    pub kind: OriginKind,
+    /// File the value was written in, if the source came from a file
+    pub file: Option<PathBuf>,
+    /// Location of the value in that file
+    pub span: Option<Span>,
+    /// Files whose includes led to `file`, outermost first
+    pub included_from: Vec<PathBuf>,
+}
+
+impl Origin {
+    /// An origin without a source location, such as a default
+    pub fn detached(kind: OriginKind) -> Self {
+        Self {
+            kind,
+            file: None,
+            span: None,
+            included_from: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for OriginKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OriginKind::Literal => f.write_str("literal"),
+            OriginKind::Env { var, found: true } => write!(f, "env(\"{var}\")"),
+            OriginKind::Env { var, found: false } => {
+                write!(f, "env(\"{var}\") default, variable not set")
+            }
+            OriginKind::Function { name } => write!(f, "{name}()"),
+            OriginKind::Native { type_name } => write!(f, "@{type_name}()"),
+            OriginKind::Interpolation { references } => {
+                let references: Vec<String> =
+                    references.iter().map(|r| format!("${{{r}}}")).collect();
+                write!(f, "interpolation of {}", references.join(", "))
+            }
+            OriginKind::Include { path } => write!(f, "include \"{path}\""),
+            OriginKind::Default => f.write_str("default value"),
+            OriginKind::Assigned => f.write_str("set programmatically"),
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    /// e.g. `db.noml:3:7 (env("DATABASE_URL")), included from main.noml`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, &self.span) {
+            (Some(file), Some(span)) => write!(
+                f,
+                "{}:{}:{} ({})",
+                file.display(),
+                span.start_line,
+                span.start_column,
+                self.kind
+            )?,
+            (None, Some(span)) => write!(
+                f,
+                "line {}, column {} ({})",
+                span.start_line, span.start_column, self.kind
+            )?,
+            (Some(file), None) => write!(f, "{} ({})", file.display(), self.kind)?,
+            (None, None) => write!(f, "{}", self.kind)?,
+        }
+        if !self.included_from.is_empty() {
+            let chain: Vec<String> = self
+                .included_from
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            write!(f, ", included from {}", chain.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Origins of resolved values, by dotted key path
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Provenance {
+    origins: BTreeMap<String, Origin>,
+}
+
+impl Provenance {
+    /// Create an empty provenance map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The origin of the value at `path`
+    ///
+    /// Values without an entry of their own, such as elements of an array
+    /// copied by interpolation, report the origin of the nearest parent.
+    pub fn get(&self, path: &str) -> Option<&Origin> {
+        let mut path = path;
+        loop {
+            if let Some(origin) = self.origins.get(path) {
+                return Some(origin);
+            }
+            path = &path[..path.rfind('.')?];
+        }
+    }
+
+    /// Record the origin of the value at `path`
+    pub(crate) fn insert(&mut self, path: impl Into<String>, origin: Origin) {
+        self.origins.insert(path.into(), origin);
+    }
+
+    /// Record `origin` at `path` unless it already has one
+    pub(crate) fn insert_missing(&mut self, path: String, origin: Origin) {
+        self.origins.entry(path).or_insert(origin);
+    }
+
+    /// Record `origin` at `path`, dropping everything recorded below it
+    pub(crate) fn replace(&mut self, path: &str, origin: Origin) {
+        self.remove(path);
+        self.insert(path, origin);
+    }
+
+    /// Add every origin recorded in `other`
+    pub(crate) fn extend(&mut self, other: Provenance) {
+        self.origins.extend(other.origins);
+    }
+
+    /// Move the origins of elements of the array at `array` along by `offset`
+    pub(crate) fn shift_elements(&mut self, array: &[String], offset: usize) {
+        self.remap(|key| {
+            let mut segments: Vec<String> = key.split('.').map(str::to_string).collect();
+            shift_index(&mut segments, array, offset);
+            Some(segments.join("."))
+        });
+    }
+
+    /// Move every recorded origin to the path `map` returns for it,
+    /// forgetting those it returns `None` for
+    pub(crate) fn remap(&mut self, mut map: impl FnMut(&str) -> Option<String>) {
+        let origins = std::mem::take(&mut self.origins);
+        self.origins = origins
+            .into_iter()
+            .filter_map(|(key, origin)| Some((map(&key)?, origin)))
+            .collect();
+    }
+
+    /// Forget the origins of `path` and everything below it
+    pub(crate) fn remove(&mut self, path: &str) {
+        let nested = format!("{path}.");
+        self.origins
+            .retain(|key, _| key != path && !key.starts_with(&nested));
+    }
+
+    /// Every recorded path and origin, in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Origin)> {
+        self.origins
+            .iter()
+            .map(|(path, origin)| (path.as_str(), origin))
+    }
+
+    /// Number of recorded origins
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+}
@@ -0,0 +1,145 @@
+//! # Secret Providers
+//!
+//! `secret("provider", "key")` looks `key` up in the [`SecretProvider`]
+//! registered under `provider` in
+//! [`ResolverConfig::secret_providers`](super::ResolverConfig) and resolves
+//! to a [`Secret`] value, redacted wherever it is displayed. No provider is
+//! registered by default:
+//!
+//! - [`EnvSecretProvider`] reads environment variables
+//! - [`FileSecretProvider`] reads one file per secret, as Docker and
+//!   Kubernetes mount them under `/run/secrets`
+//! - [`InMemorySecretProvider`] serves secrets from a map, for tests
+//!
+//! ```rust
+//! use noml::{parse_string, InMemorySecretProvider, Resolver};
+//!
+//! let vault = InMemorySecretProvider::new().with_secret("db/password", "hunter2");
+//!
+//! let document = parse_string(r#"password = secret("vault", "db/password")"#, None)?;
+//! let value = Resolver::new()
+//!     .with_secret_provider("vault", vault)
+//!     .resolve(&document)?;
+//!
+//! let password = value.get("password").unwrap();
+//! assert_eq!(password.to_string(), "[redacted]");
+//! assert_eq!(password.as_secret()?.expose(), "hunter2");
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use crate::error::{NomlError, Result};
+use crate::value::Secret;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Where `secret("provider", "key")` finds its secrets
+pub trait SecretProvider: fmt::Debug + Send + Sync {
+    /// The secret stored under `key`, or `None` if there is none
+    fn get(&self, key: &str) -> Result<Option<Secret>>;
+}
+
+/// Reads secrets from environment variables named by the key
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn get(&self, key: &str) -> Result<Option<Secret>> {
+        Ok(std::env::var(key).ok().map(Secret::new))
+    }
+}
+
+/// Reads each secret from the file named by the key inside a directory,
+/// without its trailing newline
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    dir: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Read secrets from the files in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Default for FileSecretProvider {
+    /// Read secrets from `/run/secrets`, where Docker and Kubernetes mount them
+    fn default() -> Self {
+        Self::new("/run/secrets")
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn get(&self, key: &str) -> Result<Option<Secret>> {
+        if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\']) {
+            return Err(NomlError::security(
+                "secret names must be plain file names",
+                key,
+            ));
+        }
+        let path = self.dir.join(key);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let text = text.strip_suffix('\n').unwrap_or(&text);
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                Ok(Some(Secret::new(text)))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(NomlError::io(path.display().to_string(), e)),
+        }
+    }
+}
+
+/// Serves secrets from a map of key to text
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySecretProvider {
+    secrets: HashMap<String, Secret>,
+}
+
+impl InMemorySecretProvider {
+    /// Create a provider without any secrets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a secret
+    pub fn insert(&mut self, key: impl Into<String>, text: impl Into<String>) {
+        self.secrets.insert(key.into(), Secret::new(text));
+    }
+
+    /// Builder-style [`InMemorySecretProvider::insert`]
+    pub fn with_secret(mut self, key: impl Into<String>, text: impl Into<String>) -> Self {
+        self.insert(key, text);
+        self
+    }
+}
+
+impl SecretProvider for InMemorySecretProvider {
+    fn get(&self, key: &str) -> Result<Option<Secret>> {
+        Ok(self.secrets.get(key).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_secrets() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("db_password"), "hunter2\n").unwrap();
+        let provider = FileSecretProvider::new(dir.path());
+
+        let secret = provider.get("db_password").unwrap().unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+        assert!(provider.get("missing").unwrap().is_none());
+        assert!(matches!(
+            provider.get("../db_password"),
+            Err(NomlError::Security { .. })
+        ));
+    }
+}
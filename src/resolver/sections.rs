@@ -0,0 +1,261 @@
+//! # Top-Level Sections
+//!
+//! A configuration loaded lazily resolves each top-level key on its own,
+//! the first time something under it is read. The document resolved for a
+//! section keeps only that section, the sections its `${...}` references
+//! reach, and the matching parts of the active profile, so an `env()` or
+//! `include` elsewhere is never evaluated.
+
+use super::graph;
+use crate::parser::ast::{AstNode, AstValue, Document, TableEntry, PROFILE_KEY};
+use std::collections::BTreeSet;
+
+/// The top-level keys of `document` with `profile` active, or `None` when
+/// they can't be known without resolving it: a document that `extends`
+/// another takes keys from its bases
+pub(crate) fn names(document: &Document, profile: Option<&str>) -> Option<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for entry in entries(document) {
+        if entry.is_profile() {
+            if let Some(profile) = profile {
+                names_under(entry, &[PROFILE_KEY, profile], &mut names);
+            }
+        } else if top(entry) == "extends" {
+            return None;
+        } else {
+            names.insert(top(entry).to_string());
+        }
+    }
+    Some(names)
+}
+
+/// The profiles `document` declares
+pub(crate) fn profiles(document: &Document) -> BTreeSet<String> {
+    let mut profiles = BTreeSet::new();
+    for entry in entries(document).filter(|entry| entry.is_profile()) {
+        names_under(entry, &[PROFILE_KEY], &mut profiles);
+    }
+    profiles
+}
+
+/// `document` cut down to what resolving the top-level key `name` needs
+pub(crate) fn section(document: &Document, name: &str, profile: Option<&str>) -> Document {
+    // Follow references until no new section is reached
+    let mut needed = BTreeSet::from([name.to_string()]);
+    loop {
+        let kept = keep(document, &needed, profile);
+        let mut grown = needed.clone();
+        for entry in &kept {
+            for reference in graph::references(&entry.value) {
+                let first = reference.split(['.', '[']).next().unwrap_or_default();
+                grown.insert(first.to_string());
+            }
+        }
+        if grown == needed {
+            return rebuild(document, kept, profile);
+        }
+        needed = grown;
+    }
+}
+
+/// The entries of the document root
+fn entries(document: &Document) -> impl Iterator<Item = &TableEntry> {
+    match &document.root.value {
+        AstValue::Table { entries, .. } => entries.iter(),
+        _ => [].iter(),
+    }
+}
+
+/// First segment of an entry's key
+fn top(entry: &TableEntry) -> &str {
+    entry
+        .key
+        .segments
+        .first()
+        .map_or("", |segment| segment.name.as_str())
+}
+
+/// Collect the names found directly below `prefix` in `entry`, whether
+/// written as a longer dotted key or as a table at part of the prefix
+fn names_under(entry: &TableEntry, prefix: &[&str], names: &mut BTreeSet<String>) {
+    let segments = &entry.key.segments;
+    let shared = segments
+        .iter()
+        .zip(prefix)
+        .take_while(|(segment, part)| segment.name == **part)
+        .count();
+    if shared < segments.len().min(prefix.len()) {
+        return;
+    }
+    if segments.len() > prefix.len() {
+        names.insert(segments[prefix.len()].name.clone());
+    } else if let AstValue::Table { entries, .. } = &entry.value.value {
+        for inner in entries {
+            names_under(inner, &prefix[segments.len()..], names);
+        }
+    }
+}
+
+/// The root entries belonging to the `needed` sections, with profile
+/// entries cut down to them
+fn keep(document: &Document, needed: &BTreeSet<String>, profile: Option<&str>) -> Vec<TableEntry> {
+    let mut kept = Vec::new();
+    for entry in entries(document) {
+        if entry.is_profile() {
+            if let Some(profile) = profile {
+                kept.extend(filter(entry, &[PROFILE_KEY, profile], needed));
+            }
+        } else if needed.contains(top(entry)) {
+            kept.push(entry.clone());
+        }
+    }
+    kept
+}
+
+/// `entry` without the parts below `prefix` that aren't `needed`, or
+/// `None` if nothing is left
+fn filter(entry: &TableEntry, prefix: &[&str], needed: &BTreeSet<String>) -> Option<TableEntry> {
+    let segments = &entry.key.segments;
+    let shared = segments
+        .iter()
+        .zip(prefix)
+        .take_while(|(segment, part)| segment.name == **part)
+        .count();
+    if shared < segments.len().min(prefix.len()) {
+        return None;
+    }
+    if segments.len() > prefix.len() {
+        return needed
+            .contains(&segments[prefix.len()].name)
+            .then(|| entry.clone());
+    }
+    let AstValue::Table { entries, inline } = &entry.value.value else {
+        return None;
+    };
+    let entries: Vec<TableEntry> = entries
+        .iter()
+        .filter_map(|inner| filter(inner, &prefix[segments.len()..], needed))
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    let mut entry = entry.clone();
+    entry.value.value = AstValue::Table {
+        entries,
+        inline: *inline,
+    };
+    Some(entry)
+}
+
+/// A document of `kept` entries that still declares the active profile
+fn rebuild(document: &Document, mut kept: Vec<TableEntry>, profile: Option<&str>) -> Document {
+    let declares = |entry: &TableEntry| {
+        profile.is_some_and(|profile| entry.is_profile() && profiles_of(entry).contains(profile))
+    };
+    if let (Some(profile), false) = (profile, kept.iter().any(declares)) {
+        if let Some(declared) = entries(document).find(|entry| declares(entry)) {
+            // An empty `[@profile.<name>]`, so the profile stays declared
+            let mut marker = declared.clone();
+            let mut name = marker.key.segments[0].clone();
+            name.name = profile.to_string();
+            marker.key.segments = vec![marker.key.segments[0].clone(), name];
+            marker.value = AstNode::new(
+                AstValue::Table {
+                    entries: Vec::new(),
+                    inline: false,
+                },
+                declared.value.span,
+            );
+            kept.push(marker);
+        }
+    }
+    let mut root = document.root.clone();
+    if let AstValue::Table { entries, .. } = &mut root.value {
+        *entries = kept;
+    }
+    Document {
+        root,
+        source_path: document.source_path.clone(),
+        source_text: document.source_text.clone(),
+    }
+}
+
+fn profiles_of(entry: &TableEntry) -> BTreeSet<String> {
+    let mut profiles = BTreeSet::new();
+    names_under(entry, &[PROFILE_KEY], &mut profiles);
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_string;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_sections() {
+        let document = parse_string(
+            r#"
+name = "app"
+
+[server]
+url = "http://${network.host}:8080"
+
+[network]
+host = "localhost"
+
+[tool_b]
+token = env("NOML_TEST_SECTIONS_UNSET")
+
+[@profile.production.server]
+workers = 16
+
+[@profile.production.tool_b]
+token = "fixed"
+
+[@profile.staging]
+cache = true
+"#,
+            None,
+        )
+        .unwrap();
+
+        let names = names(&document, Some("production")).unwrap();
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            ["name", "network", "server", "tool_b"]
+        );
+        let staging = super::names(&document, Some("staging")).unwrap();
+        assert!(staging.contains("cache"));
+        assert_eq!(
+            profiles(&document).into_iter().collect::<Vec<_>>(),
+            ["production", "staging"]
+        );
+
+        // Only the section and what it references are resolved
+        let server = section(&document, "server", Some("production"));
+        let value = Resolver::new()
+            .with_profile("production")
+            .resolve(&server)
+            .unwrap();
+        assert_eq!(
+            value.get("server.url").unwrap().as_string().unwrap(),
+            "http://localhost:8080"
+        );
+        assert_eq!(
+            value.get("server.workers").unwrap().as_integer().unwrap(),
+            16
+        );
+        assert!(value.get("tool_b").is_none() && value.get("name").is_none());
+
+        // The profile stays declared even when none of it is kept
+        let name = section(&document, "name", Some("staging"));
+        assert!(Resolver::new()
+            .with_profile("staging")
+            .resolve(&name)
+            .is_ok());
+
+        let extends = parse_string("extends = \"base.noml\"\nx = 1", None).unwrap();
+        assert!(super::names(&extends, None).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! # Security Policy
+//!
+//! A [`SecurityPolicy`] limits what a document may reach while it is
+//! resolved: which directories `include` and `file()` may read, whether
+//! absolute paths, `..` and remote URLs are accepted, which environment
+//! variables `env()` may see, and how many files and bytes may be loaded in
+//! total. Anything outside the policy fails with [`NomlError::Security`].
+//!
+//! The default policy allows everything, as before. For documents from
+//! untrusted sources, start from [`SecurityPolicy::sandboxed`]:
+//!
+//! ```rust
+//! use noml::{parse_string, EnvAccess, NomlError, Resolver, SecurityPolicy};
+//!
+//! let mut policy = SecurityPolicy::sandboxed("/srv/tenants/acme");
+//! policy.env_access = EnvAccess::Allow(vec!["ACME_*".to_string()]);
+//!
+//! let document = parse_string(r#"secrets = include "/etc/passwd""#, None)?;
+//! let err = Resolver::new()
+//!     .with_security_policy(policy)
+//!     .resolve(&document)
+//!     .unwrap_err();
+//! assert!(matches!(err, NomlError::Security { .. }));
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use super::loader::normalize;
+use crate::error::{NomlError, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Which environment variables `env()` may read
+///
+/// Names are matched exactly or against patterns where `*` stands for any
+/// run of characters, such as `APP_*`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum EnvAccess {
+    /// Every variable
+    #[default]
+    All,
+    /// Only variables matching one of the patterns; an empty list allows none
+    Allow(Vec<String>),
+    /// Every variable except those matching one of the patterns
+    Deny(Vec<String>),
+}
+
+impl EnvAccess {
+    /// Whether the variable `name` may be read
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            EnvAccess::All => true,
+            EnvAccess::Allow(patterns) => patterns.iter().any(|p| wildcard_match(p, name)),
+            EnvAccess::Deny(patterns) => !patterns.iter().any(|p| wildcard_match(p, name)),
+        }
+    }
+}
+
+/// What a document may access while it is resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    /// Directories included files must be inside; empty allows any.
+    /// Paths are canonicalized, so symlinks pointing outside are refused.
+    pub allowed_roots: Vec<PathBuf>,
+    /// Whether includes may use absolute paths
+    pub allow_absolute_paths: bool,
+    /// Whether includes may contain `..` components
+    pub allow_parent_traversal: bool,
+    /// Which environment variables `env()` may read
+    pub env_access: EnvAccess,
+    /// Whether `http://` and `https://` includes are allowed
+    pub allow_remote_includes: bool,
+    /// Most bytes all included files may add up to
+    pub max_include_bytes: Option<u64>,
+    /// Most files that may be included
+    pub max_include_files: Option<usize>,
+}
+
+impl Default for SecurityPolicy {
+    /// Allow everything
+    fn default() -> Self {
+        Self {
+            allowed_roots: Vec::new(),
+            allow_absolute_paths: true,
+            allow_parent_traversal: true,
+            env_access: EnvAccess::All,
+            allow_remote_includes: true,
+            max_include_bytes: None,
+            max_include_files: None,
+        }
+    }
+}
+
+impl SecurityPolicy {
+    /// Allow everything; the same as [`SecurityPolicy::default`]
+    pub fn permissive() -> Self {
+        Self::default()
+    }
+
+    /// Confine documents to `root`: relative includes inside it only, no
+    /// environment variables, no remote includes, and at most 100 included
+    /// files totalling 10 MiB
+    pub fn sandboxed(root: impl Into<PathBuf>) -> Self {
+        Self {
+            allowed_roots: vec![root.into()],
+            allow_absolute_paths: false,
+            allow_parent_traversal: false,
+            env_access: EnvAccess::Allow(Vec::new()),
+            allow_remote_includes: false,
+            max_include_bytes: Some(10 * 1024 * 1024),
+            max_include_files: Some(100),
+        }
+    }
+
+    /// Check an include path as written in the document
+    pub(crate) fn check_include(&self, path: &str) -> Result<()> {
+        if is_remote(path) {
+            return if self.allow_remote_includes {
+                Ok(())
+            } else {
+                Err(NomlError::security(
+                    format!("remote include '{path}' is not allowed"),
+                    path,
+                ))
+            };
+        }
+        let written = Path::new(path);
+        if !self.allow_absolute_paths && (written.is_absolute() || written.has_root()) {
+            return Err(NomlError::security(
+                format!("absolute include path '{path}' is not allowed"),
+                path,
+            ));
+        }
+        if !self.allow_parent_traversal && written.components().any(|c| c == Component::ParentDir) {
+            return Err(NomlError::security(
+                format!("include path '{path}' may not contain '..'"),
+                path,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that a resolved include path lies inside an allowed root
+    pub(crate) fn check_root(&self, path: &Path) -> Result<()> {
+        if self.allowed_roots.is_empty() {
+            return Ok(());
+        }
+        let target = canonical(path);
+        if self
+            .allowed_roots
+            .iter()
+            .any(|root| target.starts_with(canonical(root)))
+        {
+            Ok(())
+        } else {
+            Err(NomlError::security(
+                format!(
+                    "'{}' is outside the allowed include directories",
+                    path.display()
+                ),
+                path.display().to_string(),
+            ))
+        }
+    }
+
+    /// Check that `env()` may read the variable `name`
+    pub(crate) fn check_env(&self, name: &str) -> Result<()> {
+        if self.env_access.allows(name) {
+            Ok(())
+        } else {
+            Err(NomlError::security(
+                format!("environment variable '{name}' is not allowed"),
+                name,
+            ))
+        }
+    }
+}
+
+/// Files and bytes loaded so far while resolving one document
+#[derive(Debug, Default)]
+pub(crate) struct LoadBudget {
+    files: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl LoadBudget {
+    /// Count a loaded file of `len` bytes against the policy's limits
+    pub(crate) fn charge(&self, policy: &SecurityPolicy, path: &str, len: usize) -> Result<()> {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = self.bytes.fetch_add(len as u64, Ordering::Relaxed) + len as u64;
+
+        if let Some(max) = policy.max_include_files.filter(|&max| files > max) {
+            return Err(NomlError::security(
+                format!("including '{path}' exceeds the limit of {max} included files"),
+                path,
+            ));
+        }
+        if let Some(max) = policy.max_include_bytes.filter(|&max| bytes > max) {
+            return Err(NomlError::security(
+                format!("including '{path}' exceeds the limit of {max} included bytes"),
+                path,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuse a file of `len` bytes before it is read when it wouldn't fit
+    /// in what is left of the policy's byte limit
+    pub(crate) fn check_size(
+        &self,
+        policy: &SecurityPolicy,
+        path: &str,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let (Some(max), Some(len)) = (policy.max_include_bytes, len) else {
+            return Ok(());
+        };
+        if self.bytes.load(Ordering::Relaxed).saturating_add(len) > max {
+            return Err(NomlError::security(
+                format!("including '{path}' exceeds the limit of {max} included bytes"),
+                path,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether an include path is an `http://` or `https://` URL
+pub(crate) fn is_remote(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// `path` with symlinks resolved when it exists on disk, otherwise
+/// normalized lexically against the current directory
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    let absolute = match std::env::current_dir() {
+        Ok(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    // The directory may exist even when the file does not
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => match parent.canonicalize() {
+            Ok(parent) => parent.join(name),
+            Err(_) => normalize(&absolute),
+        },
+        _ => normalize(&absolute),
+    }
+}
+
+/// Match `name` against `pattern`, where `*` matches any run of characters
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(mut remaining) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return remaining.ends_with(part);
+        }
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_patterns() {
+        assert!(wildcard_match("APP_*", "APP_PORT"));
+        assert!(wildcard_match("*_URL", "DATABASE_URL"));
+        assert!(wildcard_match("A*B*C", "AxxBxxC"));
+        assert!(!wildcard_match("A*B*C", "AxxCxxB"));
+        assert!(wildcard_match("HOME", "HOME") && !wildcard_match("HOME", "HOMEPATH"));
+
+        let deny = EnvAccess::Deny(vec!["*SECRET*".to_string(), "AWS_*".to_string()]);
+        assert!(deny.allows("APP_PORT"));
+        assert!(!deny.allows("DB_SECRET_KEY") && !deny.allows("AWS_ACCESS_KEY_ID"));
+        assert!(!EnvAccess::Allow(Vec::new()).allows("HOME"));
+    }
+}
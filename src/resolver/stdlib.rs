@@ -0,0 +1,324 @@
+//! # Standard Library
+//!
+//! Built-in functions registered by [`FunctionRegistry::new`]:
+//!
+//! | Function | Result |
+//! |----------|--------|
+//! | `env(name, default?)` | Environment variable, or `default` |
+//! | `upper(s)`, `lower(s)`, `trim(s)` | Changed string |
+//! | `replace(s, from, to)` | `s` with every `from` replaced by `to` |
+//! | `split(s, sep)` | Array of the parts of `s` |
+//! | `join(array, sep)` | Elements of `array` joined with `sep` |
+//! | `format(template, args...)` | `template` with `{}` / `{0}` placeholders filled |
+//! | `len(x)` | Length of a string, array, table or binary value |
+//! | `concat(a, b, ...)` | Arrays or strings joined end to end |
+//! | `merge(a, b, ...)` | Tables deep-merged, later values winning |
+//! | `keys(table)` | Array of a table's keys |
+//! | `contains(x, item)` | Whether an array holds `item`, a string holds a substring or a table holds a key |
+//! | `to_int(x)`, `to_string(x)` | Converted value |
+//! | `min(...)`, `max(...)` | Smallest or largest number, from arguments or one array |
+//! | `coalesce(a, b, ...)` | First argument that is not `null` |
+//! | `default(x, fallback)` | `x`, or `fallback` when `x` is `null` or `""` |
+//! | `file(path)` | Text of a file, relative to the current file |
+//! | `hash(algorithm, x)` | Hex digest; `sha224`, `sha256`, `sha384` or `sha512` |
+//! | `secret(provider, key)` | Secret from a registered [`SecretProvider`](super::SecretProvider) |
+//!
+//! Strings built from a secret, such as `format("{}:{}", ${user}, secret(...))`,
+//! are secrets too.
+
+use super::functions::{FunctionRegistry, ResolveContext};
+use crate::error::{NomlError, Result};
+use crate::value::{format_size_exact, Value};
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Add every built-in function to `registry`
+pub(crate) fn register(registry: &mut FunctionRegistry) {
+    registry
+        .register("env", 1..=2, env)
+        .register("upper", 1..=1, |args, ctx| {
+            Ok(Value::String(string(&args[0], ctx)?.to_uppercase()))
+        })
+        .register("lower", 1..=1, |args, ctx| {
+            Ok(Value::String(string(&args[0], ctx)?.to_lowercase()))
+        })
+        .register("trim", 1..=1, |args, ctx| {
+            Ok(Value::String(string(&args[0], ctx)?.trim().to_string()))
+        })
+        .register("replace", 3..=3, |args, ctx| {
+            let text = string(&args[0], ctx)?;
+            Ok(Value::String(
+                text.replace(string(&args[1], ctx)?, string(&args[2], ctx)?),
+            ))
+        })
+        .register("split", 2..=2, |args, ctx| {
+            let text = string(&args[0], ctx)?;
+            let parts = text
+                .split(string(&args[1], ctx)?)
+                .map(|part| Value::String(part.to_string()))
+                .collect();
+            Ok(Value::Array(parts))
+        })
+        .register("join", 2..=2, |args, ctx| {
+            let items: Vec<String> = array(&args[0], ctx)?.iter().map(text).collect();
+            Ok(Value::String(items.join(string(&args[1], ctx)?)))
+        })
+        .register("format", 1.., format)
+        .register("len", 1..=1, |args, ctx| {
+            let len = match &args[0] {
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Table(table) => table.len(),
+                Value::Binary(data) => data.len(),
+                other => {
+                    return Err(
+                        ctx.error(format!("cannot take the length of {}", other.type_name()))
+                    )
+                }
+            };
+            Ok(Value::Integer(len as i64))
+        })
+        .register("concat", 1.., concat)
+        .register("merge", 1.., |args, ctx| {
+            let mut merged = BTreeMap::new();
+            for arg in args {
+                let Value::Table(table) = arg else {
+                    return Err(ctx.error(format!("expected tables, got {}", arg.type_name())));
+                };
+                deep_merge(&mut merged, table);
+            }
+            Ok(Value::Table(merged))
+        })
+        .register("keys", 1..=1, |args, ctx| match &args[0] {
+            Value::Table(table) => Ok(Value::Array(
+                table.keys().map(|key| Value::String(key.clone())).collect(),
+            )),
+            other => Err(ctx.error(format!("expected a table, got {}", other.type_name()))),
+        })
+        .register("contains", 2..=2, |args, ctx| {
+            let found = match (&args[0], &args[1]) {
+                (Value::Array(items), item) => items.contains(item),
+                (Value::String(s), Value::String(part)) => s.contains(part.as_str()),
+                (Value::Table(table), Value::String(key)) => table.contains_key(key),
+                (collection, item) => {
+                    return Err(ctx.error(format!(
+                        "cannot look for {} in {}",
+                        item.type_name(),
+                        collection.type_name()
+                    )))
+                }
+            };
+            Ok(Value::Bool(found))
+        })
+        .register("to_int", 1..=1, |args, ctx| {
+            let value = match &args[0] {
+                Value::Float(f) if f.is_finite() => Ok(f.trunc() as i64),
+                Value::String(s) => s.trim().parse::<i64>().or_else(|_| {
+                    s.trim()
+                        .parse::<f64>()
+                        .map(|f| f.trunc() as i64)
+                        .map_err(|_| ctx.error(format!("'{s}' is not a number")))
+                }),
+                other => other.as_integer().map_err(|e| ctx.error(e.to_string())),
+            }?;
+            Ok(Value::Integer(value))
+        })
+        .register("to_string", 1..=1, |args, _| {
+            Ok(Value::String(text(&args[0])))
+        })
+        .register("min", 1.., |args, ctx| extreme(args, ctx, |a, b| a < b))
+        .register("max", 1.., |args, ctx| extreme(args, ctx, |a, b| a > b))
+        .register("coalesce", 1.., |args, _| {
+            Ok(args
+                .iter()
+                .find(|arg| !arg.is_null())
+                .cloned()
+                .unwrap_or(Value::Null))
+        })
+        .register("default", 2..=2, |args, _| {
+            let unset = matches!(&args[0], Value::Null) || args[0].as_string().ok() == Some("");
+            Ok(if unset { &args[1] } else { &args[0] }.clone())
+        })
+        .register("file", 1..=1, |args, ctx| {
+            let path = string(&args[0], ctx)?;
+            ctx.read_file(path).map(Value::String).map_err(|e| match e {
+                NomlError::Io { .. } => ctx.error(format!("cannot read '{path}': {e}")),
+                other => other,
+            })
+        })
+        .register("hash", 2..=2, hash)
+        .register("secret", 2..=2, |args, ctx| {
+            let (provider, key) = (string(&args[0], ctx)?, string(&args[1], ctx)?);
+            match ctx.secret(provider, key)? {
+                Some(secret) => Ok(Value::Secret(secret)),
+                None => {
+                    Err(ctx.error(format!("secret '{key}' not found in provider '{provider}'")))
+                }
+            }
+        });
+}
+
+/// The text of a value: strings and secrets as-is, sizes and durations
+/// exactly (`1500B`, `100s`) and everything else as NOML would print it
+pub(super) fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Secret(secret) => secret.expose().to_string(),
+        // `Display` rounds these to a readable unit
+        Value::Size(bytes) => format_size_exact(*bytes),
+        Value::Duration(seconds) => format!("{seconds}s"),
+        other => other.to_string(),
+    }
+}
+
+fn string<'v>(value: &'v Value, ctx: &ResolveContext<'_>) -> Result<&'v str> {
+    match value {
+        Value::String(s) => Ok(s),
+        Value::Secret(secret) => Ok(secret.expose()),
+        other => Err(ctx.error(format!("expected a string, got {}", other.type_name()))),
+    }
+}
+
+fn array<'v>(value: &'v Value, ctx: &ResolveContext<'_>) -> Result<&'v [Value]> {
+    match value {
+        Value::Array(items) => Ok(items),
+        other => Err(ctx.error(format!("expected an array, got {}", other.type_name()))),
+    }
+}
+
+/// `env("NAME")` or `env("NAME", default)`
+fn env(args: &[Value], ctx: &ResolveContext<'_>) -> Result<Value> {
+    let Value::String(name) = &args[0] else {
+        return Err(ctx.error("first argument must be a string"));
+    };
+
+    if let Some(value) = ctx.env_var(name)? {
+        Ok(Value::String(value))
+    } else if let Some(default) = args.get(1) {
+        Ok(default.clone())
+    } else if ctx.allow_missing_env() {
+        Ok(Value::Null)
+    } else {
+        Err(ctx.error(format!(
+            "Environment variable '{name}' not found and no default provided"
+        )))
+    }
+}
+
+/// `format("{}:{}", host, port)`; `{0}` picks an argument by index and
+/// `{{` / `}}` are literal braces
+fn format(args: &[Value], ctx: &ResolveContext<'_>) -> Result<Value> {
+    let template = string(&args[0], ctx)?;
+    let values = &args[1..];
+    let mut result = String::new();
+    let mut next = 0;
+    let mut chars = template.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| ctx.error("unclosed '{' in template"))?;
+                let index = match rest[..end].trim() {
+                    "" => {
+                        next += 1;
+                        next - 1
+                    }
+                    n => n
+                        .parse::<usize>()
+                        .map_err(|_| ctx.error(format!("invalid placeholder '{{{n}}}'")))?,
+                };
+                let value = values.get(index).ok_or_else(|| {
+                    ctx.error(format!("placeholder {index} has no matching argument"))
+                })?;
+                result.push_str(&text(value));
+                chars = rest[end + 1..].chars();
+            }
+            _ => result.push(ch),
+        }
+    }
+    Ok(Value::String(result))
+}
+
+/// Arrays concatenate into an array, anything else into a string
+fn concat(args: &[Value], ctx: &ResolveContext<'_>) -> Result<Value> {
+    if matches!(args[0], Value::Array(_)) {
+        let mut items = Vec::new();
+        for arg in args {
+            items.extend_from_slice(array(arg, ctx)?);
+        }
+        Ok(Value::Array(items))
+    } else {
+        Ok(Value::String(args.iter().map(text).collect()))
+    }
+}
+
+fn deep_merge(target: &mut BTreeMap<String, Value>, source: &BTreeMap<String, Value>) {
+    for (key, value) in source {
+        match (target.get_mut(key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => deep_merge(existing, table),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// The argument (or element of a single array argument) that `wins` over
+/// every other; integers stay integers unless a float is involved
+fn extreme(args: &[Value], ctx: &ResolveContext<'_>, wins: fn(f64, f64) -> bool) -> Result<Value> {
+    let values = match args {
+        [Value::Array(items)] => items.as_slice(),
+        _ => args,
+    };
+    let mut best: Option<&Value> = None;
+    for value in values {
+        let number = match value {
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            other => return Err(ctx.error(format!("expected numbers, got {}", other.type_name()))),
+        };
+        let beats = best.is_none_or(|best| wins(number, best.as_float().unwrap_or(f64::NAN)));
+        if beats {
+            best = Some(value);
+        }
+    }
+    best.cloned()
+        .ok_or_else(|| ctx.error("expected at least one number"))
+}
+
+/// `hash("sha256", value)` as lowercase hex
+fn hash(args: &[Value], ctx: &ResolveContext<'_>) -> Result<Value> {
+    let algorithm = string(&args[0], ctx)?;
+    let data = match &args[1] {
+        Value::Binary(data) => data.clone(),
+        other => text(other).into_bytes(),
+    };
+    let digest = match algorithm.to_ascii_lowercase().as_str() {
+        "sha224" => Sha224::digest(&data).to_vec(),
+        "sha256" => Sha256::digest(&data).to_vec(),
+        "sha384" => Sha384::digest(&data).to_vec(),
+        "sha512" => Sha512::digest(&data).to_vec(),
+        other => {
+            return Err(ctx.error(format!(
+                "unsupported algorithm '{other}'; use sha224, sha256, sha384 or sha512"
+            )))
+        }
+    };
+    let mut hex = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    Ok(Value::String(hex))
+}
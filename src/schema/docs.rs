@@ -0,0 +1,345 @@
+//! # Schema Documentation
+//!
+//! Render a [`Schema`] as reference documentation (Markdown or HTML) or as a
+//! commented `.noml` template with every key at its default.
+
+use super::rules::join_path;
+use super::{FieldSchema, FieldType, Schema};
+use crate::value::{format_size_exact, Value};
+use std::fmt::Write;
+
+/// Output format for [`Schema::render_docs`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    /// GitHub-flavoured Markdown
+    Markdown,
+    /// Standalone HTML fragment
+    Html,
+}
+
+/// One documented key, flattened to its dotted path
+struct Row {
+    key: String,
+    field_type: String,
+    required: bool,
+    default: Option<String>,
+    description: Option<String>,
+}
+
+impl Schema {
+    /// Render reference documentation for this schema
+    ///
+    /// The output lists every key (nested tables flattened to dotted paths)
+    /// with its type, whether it is required, its default and description,
+    /// followed by the cross-field rules and an annotated example config.
+    pub fn render_docs(&self, format: DocFormat) -> String {
+        let mut rows = Vec::new();
+        let mut rules = Vec::new();
+        self.collect_docs("", &mut rows, &mut rules);
+        let example = self.render_template();
+
+        match format {
+            DocFormat::Markdown => render_markdown(self, &rows, &rules, &example),
+            DocFormat::Html => render_html(self, &rows, &rules, &example),
+        }
+    }
+
+    /// Render a commented `.noml` template with every key at its default
+    ///
+    /// Required keys without a default get a placeholder value for their
+    /// type; optional keys without a default are commented out.
+    pub fn render_template(&self) -> String {
+        let mut out = String::new();
+        write_template(self, "", &mut out);
+        let len = out.trim_end().len();
+        out.truncate(len);
+        out.push('\n');
+        out
+    }
+
+    fn collect_docs(&self, prefix: &str, rows: &mut Vec<Row>, rules: &mut Vec<(String, String)>) {
+        for rule in &self.rules {
+            let markdown = rule.describe(prefix, &|text| format!("`{text}`"));
+            let html = rule.describe(prefix, &|text| {
+                format!("<code>{}</code>", escape_html(text))
+            });
+            rules.push((markdown, html));
+        }
+
+        for (name, field) in sorted_fields(self) {
+            let key = join_path(prefix, name);
+            rows.push(Row {
+                key: key.clone(),
+                field_type: field.field_type.to_string(),
+                required: field.required,
+                default: field
+                    .default
+                    .as_ref()
+                    .map(|default| noml_literal(default).unwrap_or_else(|| "(secret)".to_string())),
+                description: describe_field(field),
+            });
+            if let Some((nested, is_array)) = nested_schema(&field.field_type) {
+                let nested_prefix = if is_array { format!("{key}[]") } else { key };
+                nested.collect_docs(&nested_prefix, rows, rules);
+            }
+        }
+    }
+}
+
+fn render_markdown(
+    schema: &Schema,
+    rows: &[Row],
+    rules: &[(String, String)],
+    example: &str,
+) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+
+    let mut out = String::from("# Configuration Reference\n\n");
+    if !schema.allow_additional {
+        out.push_str("Keys not listed below are rejected.\n\n");
+    }
+    out.push_str("| Key | Type | Required | Default | Description |\n");
+    out.push_str("| --- | --- | --- | --- | --- |\n");
+    for row in rows {
+        let _ = writeln!(
+            out,
+            "| `{}` | `{}` | {} | {} | {} |",
+            cell(&row.key),
+            cell(&row.field_type),
+            if row.required { "yes" } else { "no" },
+            row.default
+                .as_deref()
+                .map(|d| format!("`{}`", cell(d)))
+                .unwrap_or_default(),
+            row.description.as_deref().map(cell).unwrap_or_default(),
+        );
+    }
+
+    if !rules.is_empty() {
+        out.push_str("\n## Rules\n\n");
+        for (markdown, _) in rules {
+            let _ = writeln!(out, "- {markdown}");
+        }
+    }
+
+    let _ = write!(out, "\n## Example\n\n```noml\n{example}```\n");
+    out
+}
+
+fn render_html(schema: &Schema, rows: &[Row], rules: &[(String, String)], example: &str) -> String {
+    let mut out = String::from("<h1>Configuration Reference</h1>\n");
+    if !schema.allow_additional {
+        out.push_str("<p>Keys not listed below are rejected.</p>\n");
+    }
+    out.push_str("<table>\n<thead>\n<tr><th>Key</th><th>Type</th><th>Required</th><th>Default</th><th>Description</th></tr>\n</thead>\n<tbody>\n");
+    for row in rows {
+        let _ = writeln!(
+            out,
+            "<tr><td><code>{}</code></td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&row.key),
+            escape_html(&row.field_type),
+            if row.required { "yes" } else { "no" },
+            row.default
+                .as_deref()
+                .map(|d| format!("<code>{}</code>", escape_html(d)))
+                .unwrap_or_default(),
+            row.description.as_deref().map(escape_html).unwrap_or_default(),
+        );
+    }
+    out.push_str("</tbody>\n</table>\n");
+
+    if !rules.is_empty() {
+        out.push_str("<h2>Rules</h2>\n<ul>\n");
+        for (_, html) in rules {
+            let _ = writeln!(out, "<li>{html}</li>");
+        }
+        out.push_str("</ul>\n");
+    }
+
+    let _ = write!(
+        out,
+        "<h2>Example</h2>\n<pre><code>{}</code></pre>\n",
+        escape_html(example)
+    );
+    out
+}
+
+fn write_template(schema: &Schema, prefix: &str, out: &mut String) {
+    // Deprecated keys are left out so new configs start on the current layout
+    let fields: Vec<_> = sorted_fields(schema)
+        .into_iter()
+        .filter(|(_, field)| field.deprecated.is_none())
+        .collect();
+
+    // Plain keys first so they stay inside the current section
+    for (name, field) in &fields {
+        if nested_schema(&field.field_type).is_some() {
+            continue;
+        }
+        write_field_comment(field, out);
+        let (value, active) = match field.default.as_ref().map(noml_literal) {
+            Some(Some(default)) => (default, true),
+            // A secret default is never written out
+            Some(None) => (placeholder(&field.field_type), false),
+            None => (placeholder(&field.field_type), field.required),
+        };
+        let line = format!("{} = {value}", bare_key(name));
+        if active {
+            let _ = writeln!(out, "{line}\n");
+        } else {
+            let _ = writeln!(out, "# {line}\n");
+        }
+    }
+
+    for (name, field) in &fields {
+        let Some((nested, is_array)) = nested_schema(&field.field_type) else {
+            continue;
+        };
+        let key = join_path(prefix, &bare_key(name));
+        write_field_comment(field, out);
+        if is_array {
+            let _ = writeln!(out, "[[{key}]]");
+        } else {
+            let _ = writeln!(out, "[{key}]");
+        }
+        write_template(nested, &key, out);
+    }
+}
+
+/// Description column text, with a deprecation notice in front
+fn describe_field(field: &FieldSchema) -> Option<String> {
+    let Some(deprecation) = &field.deprecated else {
+        return field.description.clone();
+    };
+    let mut text = String::from("Deprecated");
+    if !deprecation.message.is_empty() {
+        text.push_str(": ");
+        text.push_str(&deprecation.message);
+    }
+    if let Some(replacement) = &deprecation.replacement {
+        text.push_str(&format!(" (use {replacement} instead)"));
+    }
+    if let Some(description) = &field.description {
+        text.push_str(". ");
+        text.push_str(description);
+    }
+    Some(text)
+}
+
+fn write_field_comment(field: &FieldSchema, out: &mut String) {
+    if let Some(description) = &field.description {
+        for line in description.lines() {
+            let _ = writeln!(out, "# {line}");
+        }
+    }
+    let presence = if field.required {
+        "required"
+    } else {
+        "optional"
+    };
+    let _ = writeln!(out, "# type: {} ({presence})", field.field_type);
+}
+
+/// Example value for a type, used when a field has no default
+fn placeholder(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::String => "\"\"".to_string(),
+        FieldType::Integer => "0".to_string(),
+        FieldType::Float => "0.0".to_string(),
+        FieldType::Bool => "false".to_string(),
+        FieldType::DateTime => "\"2024-01-01T00:00:00Z\"".to_string(),
+        FieldType::Size => "@size(\"1MB\")".to_string(),
+        FieldType::Duration => "@duration(\"30s\")".to_string(),
+        FieldType::Url => "\"https://example.com\"".to_string(),
+        FieldType::Ip => "\"127.0.0.1\"".to_string(),
+        FieldType::Semver => "\"1.0.0\"".to_string(),
+        FieldType::Uuid => "\"00000000-0000-0000-0000-000000000000\"".to_string(),
+        FieldType::Secret => "secret(\"env\", \"NAME\")".to_string(),
+        FieldType::Array(_) => "[]".to_string(),
+        FieldType::Table(_) => "{}".to_string(),
+        FieldType::Optional(inner) => placeholder(inner),
+        FieldType::Union(types) => types
+            .first()
+            .map(placeholder)
+            .unwrap_or_else(|| "null".to_string()),
+        FieldType::Binary | FieldType::Null | FieldType::Any => "null".to_string(),
+    }
+}
+
+/// Nested table schema of a field, and whether it is an array of tables
+fn nested_schema(field_type: &FieldType) -> Option<(&Schema, bool)> {
+    match field_type {
+        FieldType::Table(schema) if !schema.fields.is_empty() => Some((schema, false)),
+        FieldType::Optional(inner) => nested_schema(inner),
+        FieldType::Array(inner) => match inner.as_ref() {
+            FieldType::Table(schema) if !schema.fields.is_empty() => Some((schema, true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn sorted_fields(schema: &Schema) -> Vec<(&String, &FieldSchema)> {
+    let mut fields: Vec<_> = schema.fields.iter().collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+    fields
+}
+
+fn bare_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+/// Render a value as NOML source, or `None` if it holds a secret (whose
+/// text is never written out)
+fn noml_literal(value: &Value) -> Option<String> {
+    let literal = match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) if f.fract() == 0.0 && f.is_finite() => format!("{f:.1}"),
+        Value::Float(f) => f.to_string(),
+        Value::String(s) => quote(s),
+        Value::Array(items) => {
+            let items = items.iter().map(noml_literal).collect::<Option<Vec<_>>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Table(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .map(|(k, v)| Some(format!("{} = {}", bare_key(k), noml_literal(v)?)))
+                .collect::<Option<_>>()?;
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Value::Size(bytes) => format!("@size(\"{}\")", format_size_exact(*bytes)),
+        Value::Duration(seconds) => format!("@duration(\"{seconds}s\")"),
+        Value::Secret(_) => return None,
+        Value::Binary(_) => "null".to_string(),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(dt) => format!("\"{}\"", dt.to_rfc3339()),
+    };
+    Some(literal)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,267 @@
+//! # Schema Files
+//!
+//! Load a [`Schema`] from a NOML document, so schemas can live next to the
+//! configuration they describe:
+//!
+//! ```noml
+//! allow_additional = false
+//!
+//! [fields.name]
+//! type = "string"
+//! required = true
+//! description = "Service name shown in logs"
+//!
+//! [fields.database]
+//! type = "table"
+//! required = true
+//!
+//! [fields.database.fields.port]
+//! type = "integer"
+//! default = 5432
+//!
+//! [[rules]]
+//! kind = "compare"
+//! left = "pool.min"
+//! op = "<="
+//! right = "pool.max"
+//! ```
+//!
+//! A field can be marked `deprecated = "message"` (or `deprecated = true`)
+//! with an optional `replacement = "new.path"`.
+//!
+//! Nested tables take the same keys as the root (`fields`, `rules`,
+//! `allow_additional`). Rule kinds are `required_if` (`field`, `when`,
+//! `equals`), `mutually_exclusive`, `exactly_one_of` and `at_least_one_of`
+//! (`fields`), and `compare` (`left`, `op`, `right`).
+
+use super::rules::join_path;
+use super::{CompareOp, Deprecation, FieldSchema, FieldType, Rule, Schema};
+use crate::error::{NomlError, Result};
+use crate::value::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+impl Schema {
+    /// Build a schema from a parsed schema document
+    pub fn from_value(value: &Value) -> Result<Schema> {
+        match value {
+            Value::Table(table) => schema_from_table(table, ""),
+            _ => Err(NomlError::schema("", "Schema definition must be a table")),
+        }
+    }
+
+    /// Parse a schema from NOML source text
+    pub fn from_string(source: &str) -> Result<Schema> {
+        Self::from_value(&crate::parse(source)?)
+    }
+
+    /// Load a schema from a NOML schema file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Schema> {
+        Self::from_value(&crate::parse_from_file(path)?)
+    }
+}
+
+fn schema_from_table(table: &BTreeMap<String, Value>, path: &str) -> Result<Schema> {
+    let mut schema = Schema::new();
+
+    if let Some(allow) = table.get("allow_additional") {
+        schema.allow_additional = allow.as_bool().map_err(|_| {
+            NomlError::schema(join_path(path, "allow_additional"), "Expected a bool")
+        })?;
+    }
+
+    if let Some(fields) = table.get("fields") {
+        let fields_path = join_path(path, "fields");
+        let Value::Table(fields) = fields else {
+            return Err(NomlError::schema(fields_path, "Expected a table of fields"));
+        };
+        for (name, definition) in fields {
+            let field_path = join_path(&fields_path, name);
+            let Value::Table(definition) = definition else {
+                return Err(NomlError::schema(
+                    field_path,
+                    "Expected a field definition table",
+                ));
+            };
+            let field = field_from_table(definition, &field_path)?;
+            schema.fields.insert(name.clone(), field);
+        }
+    }
+
+    if let Some(rules) = table.get("rules") {
+        let rules_path = join_path(path, "rules");
+        let Value::Array(rules) = rules else {
+            return Err(NomlError::schema(rules_path, "Expected an array of rules"));
+        };
+        for (i, rule) in rules.iter().enumerate() {
+            let rule_path = format!("{rules_path}[{i}]");
+            let Value::Table(rule) = rule else {
+                return Err(NomlError::schema(rule_path, "Expected a rule table"));
+            };
+            schema.rules.push(rule_from_table(rule, &rule_path)?);
+        }
+    }
+
+    Ok(schema)
+}
+
+fn field_from_table(definition: &BTreeMap<String, Value>, path: &str) -> Result<FieldSchema> {
+    let has_nested = ["fields", "rules", "allow_additional"]
+        .iter()
+        .any(|key| definition.contains_key(*key));
+
+    let mut field_type = match definition.get("type") {
+        Some(type_name) => {
+            let type_path = join_path(path, "type");
+            let type_name = type_name
+                .as_string()
+                .map_err(|_| NomlError::schema(type_path.clone(), "Expected a type name"))?;
+            type_name
+                .parse::<FieldType>()
+                .map_err(|_| NomlError::schema(type_path, format!("Unknown type '{type_name}'")))?
+        }
+        None if has_nested => FieldType::Table(Schema::new()),
+        None => FieldType::Any,
+    };
+
+    if has_nested {
+        let nested = schema_from_table(definition, path)?;
+        if !attach_table_schema(&mut field_type, nested) {
+            return Err(NomlError::schema(
+                path,
+                format!("Nested fields require a table type, found '{field_type}'"),
+            ));
+        }
+    }
+
+    let required = match definition.get("required") {
+        Some(required) => required
+            .as_bool()
+            .map_err(|_| NomlError::schema(join_path(path, "required"), "Expected a bool"))?,
+        None => false,
+    };
+
+    let description = match definition.get("description") {
+        Some(description) => Some(
+            description
+                .as_string()
+                .map_err(|_| {
+                    NomlError::schema(join_path(path, "description"), "Expected a string")
+                })?
+                .to_string(),
+        ),
+        None => None,
+    };
+
+    let deprecated = match definition.get("deprecated") {
+        None | Some(Value::Bool(false)) => None,
+        Some(Value::Bool(true)) => Some(String::new()),
+        Some(Value::String(message)) => Some(message.clone()),
+        Some(_) => {
+            return Err(NomlError::schema(
+                join_path(path, "deprecated"),
+                "Expected a bool or a message string",
+            ))
+        }
+    };
+    let replacement = match definition.get("replacement") {
+        Some(replacement) => Some(
+            replacement
+                .as_string()
+                .map_err(|_| {
+                    NomlError::schema(join_path(path, "replacement"), "Expected a string")
+                })?
+                .to_string(),
+        ),
+        None => None,
+    };
+    if replacement.is_some() && deprecated.is_none() {
+        return Err(NomlError::schema(
+            join_path(path, "replacement"),
+            "Only deprecated fields can name a replacement",
+        ));
+    }
+
+    Ok(FieldSchema {
+        field_type,
+        required,
+        description,
+        default: definition.get("default").cloned(),
+        deprecated: deprecated.map(|message| Deprecation {
+            message,
+            replacement,
+        }),
+    })
+}
+
+/// Put `schema` into the table type inside `field_type`, looking through
+/// arrays, optionals and unions. Returns false if there is no table type.
+fn attach_table_schema(field_type: &mut FieldType, schema: Schema) -> bool {
+    match field_type {
+        FieldType::Table(existing) => {
+            *existing = schema;
+            true
+        }
+        FieldType::Array(inner) | FieldType::Optional(inner) => attach_table_schema(inner, schema),
+        FieldType::Union(types) => types
+            .iter_mut()
+            .find(|t| matches!(t, FieldType::Table(_)))
+            .is_some_and(|t| attach_table_schema(t, schema)),
+        _ => false,
+    }
+}
+
+fn rule_from_table(rule: &BTreeMap<String, Value>, path: &str) -> Result<Rule> {
+    let string = |key: &str| -> Result<String> {
+        rule.get(key)
+            .and_then(|v| v.as_string().ok())
+            .map(str::to_string)
+            .ok_or_else(|| NomlError::schema(join_path(path, key), "Expected a string"))
+    };
+    let strings = |key: &str| -> Result<Vec<String>> {
+        let error = || NomlError::schema(join_path(path, key), "Expected an array of strings");
+        let Some(Value::Array(items)) = rule.get(key) else {
+            return Err(error());
+        };
+        items
+            .iter()
+            .map(|item| item.as_string().map(str::to_string).map_err(|_| error()))
+            .collect()
+    };
+
+    match string("kind")?.as_str() {
+        "required_if" => Ok(Rule::RequiredIf {
+            field: string("field")?,
+            when: string("when")?,
+            equals: rule.get("equals").cloned().unwrap_or(Value::Bool(true)),
+        }),
+        "mutually_exclusive" => Ok(Rule::MutuallyExclusive(strings("fields")?)),
+        "exactly_one_of" => Ok(Rule::ExactlyOneOf(strings("fields")?)),
+        "at_least_one_of" => Ok(Rule::AtLeastOneOf(strings("fields")?)),
+        "compare" => {
+            let op = match string("op")?.as_str() {
+                "<" => CompareOp::Lt,
+                "<=" => CompareOp::Le,
+                ">" => CompareOp::Gt,
+                ">=" => CompareOp::Ge,
+                "==" => CompareOp::Eq,
+                "!=" => CompareOp::Ne,
+                other => {
+                    return Err(NomlError::schema(
+                        join_path(path, "op"),
+                        format!("Unknown comparison operator '{other}'"),
+                    ))
+                }
+            };
+            Ok(Rule::Compare {
+                left: string("left")?,
+                op,
+                right: string("right")?,
+            })
+        }
+        other => Err(NomlError::schema(
+            join_path(path, "kind"),
+            format!("Unknown rule kind '{other}'"),
+        )),
+    }
+}
@@ -0,0 +1,1370 @@
+//! # NOML Schema Validation
+//!
+//! Comprehensive schema validation system for NOML configurations. Provides
+//! type-safe validation with detailed error reporting to catch configuration
+//! errors early in development and deployment.
+//!
+//! ## Overview
+//!
+//! The schema system enables you to define expected structure and types for
+//! configuration files, ensuring data integrity and providing clear error
+//! messages when validation fails.
+//!
+//! ## Quick Start
+//!
+//! ```rust
+//! use noml::{Config, SchemaBuilder, FieldType};
+//!
+//! // Define schema using builder pattern
+//! let schema = SchemaBuilder::new()
+//!     .require_string("app_name")
+//!     .require_integer("port")
+//!     .optional_bool("debug")
+//!     .build();
+//!
+//! // Load and validate configuration
+//! let config = Config::from_string(r#"
+//!     app_name = "my-service"
+//!     port = 8080
+//!     debug = true
+//! "#)?;
+//!
+//! config.validate_schema(&schema)?;
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Advanced Schema Definition
+//!
+//! ```rust
+//! use noml::{Schema, FieldSchema, FieldType, Value};
+//! use std::collections::HashMap;
+//!
+//! // Create nested schema for database configuration
+//! let mut db_schema = Schema::new()
+//!     .required_field("host", FieldType::String)
+//!     .required_field("port", FieldType::Integer)
+//!     .optional_field("ssl", FieldType::Bool)
+//!     .field_with_default("timeout", FieldType::Integer, Value::integer(30));
+//!
+//! // Main application schema
+//! let app_schema = Schema::new()
+//!     .required_field("name", FieldType::String)
+//!     .required_field("database", FieldType::Table(db_schema))
+//!     .allow_additional(false);  // Strict validation
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Cross-Field Rules
+//!
+//! ```rust
+//! use noml::{parse, CompareOp, SchemaBuilder, Value};
+//!
+//! let schema = SchemaBuilder::new()
+//!     .required_if("tls.cert_path", "tls.enabled", Value::Bool(true))
+//!     .exactly_one_of(&["password", "password_file"])
+//!     .compare("pool.min", CompareOp::Le, "pool.max")
+//!     .build();
+//!
+//! let config = parse(r#"
+//!     password = "hunter2"
+//!     password_file = "/run/secrets/db"
+//!     tls = { enabled = true }
+//!     pool = { min = 10, max = 5 }
+//! "#)?;
+//!
+//! // Every failure is collected in a single report
+//! let report = schema.validate_all(&config);
+//! assert_eq!(report.errors.len(), 3);
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Validation Features
+//!
+//! - **🔍 Type Checking** - Ensure values match expected types
+//! - **📋 Required Fields** - Validate presence of mandatory configuration
+//! - **🔧 Default Values** - Automatic insertion of missing optional fields
+//! - **🏗️ Nested Validation** - Deep validation of table structures
+//! - **📝 Descriptive Errors** - Clear messages with field paths
+//! - **🔓 Flexible Schemas** - Allow or reject additional fields
+//! - **🔗 Cross-Field Rules** - Conditional requirements, exclusive groups and comparisons
+//! - **📊 Full Reports** - Collect every error at once with [`Schema::validate_all`]
+//! - **📚 Generated Docs** - Markdown/HTML references and commented templates from a schema
+//! - **📄 Schema Files** - Define schemas in NOML with [`Schema::from_file`]
+//! - **⚠️ Deprecations** - Warn on old keys and read them through their replacement
+
+mod docs;
+mod loader;
+mod rules;
+
+pub use docs::DocFormat;
+pub use rules::{CompareOp, Rule};
+
+use crate::error::{NomlError, Result};
+use crate::resolver::{
+    is_valid_datetime, is_valid_ip, is_valid_semver, is_valid_url, is_valid_uuid, parse_duration,
+    parse_size,
+};
+use crate::value::Value;
+use rules::join_path;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Schema definition for validating NOML configurations
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// Field definitions
+    pub fields: HashMap<String, FieldSchema>,
+    /// Whether to allow additional fields not defined in schema
+    pub allow_additional: bool,
+    /// Cross-field rules checked after the individual fields
+    pub rules: Vec<Rule>,
+}
+
+/// Schema definition for a field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSchema {
+    /// Expected type of the field
+    pub field_type: FieldType,
+    /// Whether this field is required
+    pub required: bool,
+    /// Optional description for documentation
+    pub description: Option<String>,
+    /// Default value if field is missing
+    pub default: Option<Value>,
+    /// Set when the field is deprecated; using it produces a warning
+    pub deprecated: Option<Deprecation>,
+}
+
+/// Deprecation notice for a schema field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deprecation {
+    /// Why the field is deprecated or what to do instead
+    pub message: String,
+    /// Dotted path of the field replacing it, relative to the same table
+    pub replacement: Option<String>,
+}
+
+/// Supported field types for validation
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    /// String value
+    String,
+    /// Integer value
+    Integer,
+    /// Float value
+    Float,
+    /// Boolean value
+    Bool,
+    /// Binary data
+    Binary,
+    /// DateTime value (or an ISO-8601 date-time string)
+    DateTime,
+    /// Size value, such as `@size("10MB")`, or a non-negative integer byte count
+    Size,
+    /// Duration value, such as `@duration("30s")`, or a number of seconds
+    Duration,
+    /// HTTP or HTTPS URL string, as produced by `@url()`
+    Url,
+    /// IPv4 or IPv6 address string, as produced by `@ip()`
+    Ip,
+    /// Semantic version string, as produced by `@semver()`
+    Semver,
+    /// UUID string, as produced by `@uuid()`
+    Uuid,
+    /// Secret text, as produced by `@secret()` or `secret()`
+    Secret,
+    /// Null value
+    Null,
+    /// Array of specific type
+    Array(Box<FieldType>),
+    /// Table/object with nested schema
+    Table(Schema),
+    /// Any type (no validation)
+    Any,
+    /// One of several types
+    Union(Vec<FieldType>),
+    /// Nullable wrapper: accepts `null` or the inner type
+    Optional(Box<FieldType>),
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::String => f.write_str("string"),
+            FieldType::Integer => f.write_str("integer"),
+            FieldType::Float => f.write_str("float"),
+            FieldType::Bool => f.write_str("bool"),
+            FieldType::Binary => f.write_str("binary"),
+            FieldType::DateTime => f.write_str("datetime"),
+            FieldType::Size => f.write_str("size"),
+            FieldType::Duration => f.write_str("duration"),
+            FieldType::Url => f.write_str("url"),
+            FieldType::Ip => f.write_str("ip"),
+            FieldType::Semver => f.write_str("semver"),
+            FieldType::Uuid => f.write_str("uuid"),
+            FieldType::Secret => f.write_str("secret"),
+            FieldType::Null => f.write_str("null"),
+            FieldType::Array(element_type) => write!(f, "array<{element_type}>"),
+            FieldType::Table(_) => f.write_str("table"),
+            FieldType::Any => f.write_str("any"),
+            FieldType::Union(types) => {
+                for (i, field_type) in types.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{field_type}")?;
+                }
+                Ok(())
+            }
+            FieldType::Optional(inner_type) => write!(f, "optional<{inner_type}>"),
+        }
+    }
+}
+
+/// Parse type names as written in schema files: `"string"`, `"array<int>"`,
+/// `"optional<url>"` or `"string | integer"`. A `"table"` parses to a table
+/// with an empty schema.
+impl FromStr for FieldType {
+    type Err = NomlError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        // Split unions on top-level `|` only
+        let mut depth = 0;
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                '|' if depth == 0 => {
+                    parts.push(&s[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if !parts.is_empty() {
+            parts.push(&s[start..]);
+            return parts
+                .into_iter()
+                .map(FieldType::from_str)
+                .collect::<Result<Vec<_>>>()
+                .map(FieldType::Union);
+        }
+
+        let generic = |name: &str| {
+            s.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('<'))
+                .and_then(|rest| rest.strip_suffix('>'))
+        };
+        if let Some(inner) = generic("array") {
+            return Ok(FieldType::Array(Box::new(inner.parse()?)));
+        }
+        if let Some(inner) = generic("optional") {
+            return Ok(FieldType::Optional(Box::new(inner.parse()?)));
+        }
+
+        match s.to_ascii_lowercase().as_str() {
+            "string" | "str" => Ok(FieldType::String),
+            "integer" | "int" => Ok(FieldType::Integer),
+            "float" => Ok(FieldType::Float),
+            "bool" | "boolean" => Ok(FieldType::Bool),
+            "binary" => Ok(FieldType::Binary),
+            "datetime" => Ok(FieldType::DateTime),
+            "size" => Ok(FieldType::Size),
+            "duration" => Ok(FieldType::Duration),
+            "url" => Ok(FieldType::Url),
+            "ip" => Ok(FieldType::Ip),
+            "semver" => Ok(FieldType::Semver),
+            "uuid" => Ok(FieldType::Uuid),
+            "secret" => Ok(FieldType::Secret),
+            "null" => Ok(FieldType::Null),
+            "array" => Ok(FieldType::Array(Box::new(FieldType::Any))),
+            "table" => Ok(FieldType::Table(Schema::new())),
+            "any" => Ok(FieldType::Any),
+            _ => Err(NomlError::validation(format!("Unknown field type '{s}'"))),
+        }
+    }
+}
+
+/// A single problem found during schema validation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// Dotted path of the offending field (empty for the root)
+    pub path: String,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Every problem found while validating a value against a [`Schema`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Field and rule errors, in the order they were found
+    pub errors: Vec<ValidationIssue>,
+    /// Non-fatal findings such as deprecated fields
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether validation found no errors
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Record an error at `path`
+    pub(crate) fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(ValidationIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Record a warning at `path`
+    pub(crate) fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(ValidationIssue {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Convert into a `Result`, failing with the first error found
+    ///
+    /// Warnings are discarded; read them from [`ValidationReport::warnings`] first.
+    pub fn into_result(self) -> Result<()> {
+        match self.errors.into_iter().next() {
+            None => Ok(()),
+            Some(issue) if issue.path.is_empty() => Err(NomlError::validation(issue.message)),
+            Some(issue) => Err(NomlError::validation_at(issue.message, issue.path)),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.errors {
+            writeln!(f, "error: {}", issue.message)?;
+        }
+        for issue in &self.warnings {
+            writeln!(f, "warning: {}", issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl Schema {
+    /// Create a new empty schema
+    pub fn new() -> Self {
+        Self {
+            fields: HashMap::new(),
+            allow_additional: true,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Add a required field to the schema
+    pub fn required_field(mut self, name: &str, field_type: FieldType) -> Self {
+        self.fields.insert(
+            name.to_string(),
+            FieldSchema {
+                field_type,
+                required: true,
+                description: None,
+                default: None,
+                deprecated: None,
+            },
+        );
+        self
+    }
+
+    /// Add an optional field to the schema
+    pub fn optional_field(mut self, name: &str, field_type: FieldType) -> Self {
+        self.fields.insert(
+            name.to_string(),
+            FieldSchema {
+                field_type,
+                required: false,
+                description: None,
+                default: None,
+                deprecated: None,
+            },
+        );
+        self
+    }
+
+    /// Add a field with a default value
+    pub fn field_with_default(mut self, name: &str, field_type: FieldType, default: Value) -> Self {
+        self.fields.insert(
+            name.to_string(),
+            FieldSchema {
+                field_type,
+                required: false,
+                description: None,
+                default: Some(default),
+                deprecated: None,
+            },
+        );
+        self
+    }
+
+    /// Attach a description to a field already in the schema
+    pub fn describe(mut self, name: &str, description: &str) -> Self {
+        if let Some(field) = self.fields.get_mut(name) {
+            field.description = Some(description.to_string());
+        }
+        self
+    }
+
+    /// Mark a field as deprecated, optionally naming the field replacing it
+    ///
+    /// Fields not yet in the schema are added as optional fields of any type,
+    /// so old keys stay accepted when additional fields are rejected.
+    pub fn deprecate(mut self, name: &str, message: &str, replacement: Option<&str>) -> Self {
+        let deprecation = Deprecation {
+            message: message.to_string(),
+            replacement: replacement.map(str::to_string),
+        };
+        self.fields
+            .entry(name.to_string())
+            .or_insert_with(|| FieldSchema {
+                field_type: FieldType::Any,
+                required: false,
+                description: None,
+                default: None,
+                deprecated: None,
+            })
+            .deprecated = Some(deprecation);
+        self
+    }
+
+    /// Copy deprecated fields to their replacement when only the old key is set
+    ///
+    /// This lets code read the new key while older configs still use the old
+    /// one. Nested table schemas are handled too.
+    pub fn apply_compat(&self, value: &mut Value) {
+        let Value::Table(table) = value else {
+            return;
+        };
+
+        // Nested tables first, so their own replacements take precedence
+        for (name, field) in &self.fields {
+            if let Some(child) = table.get_mut(name) {
+                field.field_type.apply_compat(child);
+            }
+        }
+
+        let mut field_names: Vec<&String> = self.fields.keys().collect();
+        field_names.sort();
+        for name in field_names {
+            let Some(Deprecation {
+                replacement: Some(replacement),
+                ..
+            }) = &self.fields[name].deprecated
+            else {
+                continue;
+            };
+            let old = match value {
+                Value::Table(table) => table.get(name).filter(|old| !old.is_null()).cloned(),
+                _ => None,
+            };
+            if let Some(old) = old {
+                if value.get(replacement).is_none() {
+                    let _ = value.set(replacement, old);
+                }
+            }
+        }
+    }
+
+    /// Set whether to allow additional fields
+    pub fn allow_additional(mut self, allow: bool) -> Self {
+        self.allow_additional = allow;
+        self
+    }
+
+    /// Add a cross-field rule to the schema
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Require `field` whenever the value at `when` equals `equals`
+    pub fn required_if(self, field: &str, when: &str, equals: Value) -> Self {
+        self.rule(Rule::RequiredIf {
+            field: field.to_string(),
+            when: when.to_string(),
+            equals,
+        })
+    }
+
+    /// Allow at most one of `fields` to be set
+    pub fn mutually_exclusive(self, fields: &[&str]) -> Self {
+        self.rule(Rule::MutuallyExclusive(to_strings(fields)))
+    }
+
+    /// Require exactly one of `fields` to be set
+    pub fn exactly_one_of(self, fields: &[&str]) -> Self {
+        self.rule(Rule::ExactlyOneOf(to_strings(fields)))
+    }
+
+    /// Require at least one of `fields` to be set
+    pub fn at_least_one_of(self, fields: &[&str]) -> Self {
+        self.rule(Rule::AtLeastOneOf(to_strings(fields)))
+    }
+
+    /// Require `left <op> right` to hold whenever both fields are set
+    pub fn compare(self, left: &str, op: CompareOp, right: &str) -> Self {
+        self.rule(Rule::Compare {
+            left: left.to_string(),
+            op,
+            right: right.to_string(),
+        })
+    }
+
+    /// Validate a value against this schema, returning the first error found
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        self.validate_all(value).into_result()
+    }
+
+    /// Validate a value against this schema, collecting every field and rule error
+    pub fn validate_all(&self, value: &Value) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        match value {
+            Value::Table(_) => self.validate_table(value, "", &mut report),
+            _ => report.error("", "Schema validation requires a table/object at the root"),
+        }
+        report
+    }
+
+    /// Validate the fields and rules of a table located at `prefix`
+    fn validate_table(&self, value: &Value, prefix: &str, report: &mut ValidationReport) {
+        let Value::Table(table) = value else {
+            return;
+        };
+
+        // Check required fields
+        let mut field_names: Vec<&String> = self.fields.keys().collect();
+        field_names.sort();
+        for field_name in field_names {
+            if self.fields[field_name].required && !table.contains_key(field_name) {
+                let path = join_path(prefix, field_name);
+                report.error(path.clone(), format!("Required field '{path}' is missing"));
+            }
+        }
+
+        // Validate existing fields
+        for (key, val) in table {
+            let path = join_path(prefix, key);
+            if let Some(field_schema) = self.fields.get(key) {
+                if let Some(deprecation) = &field_schema.deprecated {
+                    report.warning(path.clone(), deprecation.warning(&path, prefix));
+                }
+                self.validate_field_type(val, &field_schema.field_type, &path, report);
+            } else if !self.allow_additional {
+                report.error(
+                    path.clone(),
+                    format!("Additional field '{path}' is not allowed"),
+                );
+            }
+        }
+
+        for rule in &self.rules {
+            rule.check(value, prefix, report);
+        }
+    }
+
+    /// Validate a field against its expected type
+    fn validate_field_type(
+        &self,
+        value: &Value,
+        expected_type: &FieldType,
+        field_path: &str,
+        report: &mut ValidationReport,
+    ) {
+        match (value, expected_type) {
+            (Value::Null, FieldType::Optional(_)) => {}
+            (val, FieldType::Optional(inner_type)) => {
+                self.validate_field_type(val, inner_type, field_path, report)
+            }
+
+            (Value::Array(arr), FieldType::Array(element_type)) => {
+                for (i, item) in arr.iter().enumerate() {
+                    let item_path = format!("{field_path}[{i}]");
+                    self.validate_field_type(item, element_type, &item_path, report);
+                }
+            }
+
+            (Value::Table(_), FieldType::Table(nested_schema)) => {
+                nested_schema.validate_table(value, field_path, report)
+            }
+
+            (val, FieldType::Union(types)) => {
+                let matches_any = types.iter().any(|field_type| {
+                    let mut attempt = ValidationReport::default();
+                    self.validate_field_type(val, field_type, field_path, &mut attempt);
+                    attempt.is_valid()
+                });
+                if !matches_any {
+                    report.error(
+                        field_path,
+                        format!("Field '{field_path}' does not match any of the expected types"),
+                    );
+                }
+            }
+
+            _ if Self::matches_scalar_type(value, expected_type) => {}
+
+            _ => report.error(
+                field_path,
+                format!(
+                    "Field '{field_path}' has incorrect type. Expected {expected_type:?}, got {:?}",
+                    self.value_type_name(value)
+                ),
+            ),
+        }
+    }
+
+    /// Check a value against a non-container field type
+    fn matches_scalar_type(value: &Value, expected_type: &FieldType) -> bool {
+        match (value, expected_type) {
+            (Value::String(_), FieldType::String) => true,
+            (Value::Integer(_), FieldType::Integer) => true,
+            (Value::Size(_), FieldType::Integer) => true,
+            (Value::Float(_), FieldType::Float) => true,
+            (Value::Duration(_), FieldType::Float) => true,
+            (Value::Bool(_), FieldType::Bool) => true,
+            (Value::Binary(_), FieldType::Binary) => true,
+            #[cfg(feature = "chrono")]
+            (Value::DateTime(_), FieldType::DateTime) => true,
+            (Value::String(s), FieldType::DateTime) => is_valid_datetime(s),
+            (Value::Size(_), FieldType::Size) => true,
+            (Value::Integer(n), FieldType::Size) => *n >= 0,
+            (Value::String(s), FieldType::Size) => parse_size(s).is_some(),
+            (Value::Duration(_), FieldType::Duration) => true,
+            (Value::Integer(n), FieldType::Duration) => *n >= 0,
+            (Value::Float(f), FieldType::Duration) => f.is_finite() && *f >= 0.0,
+            (Value::String(s), FieldType::Duration) => parse_duration(s).is_some(),
+            (Value::String(s), FieldType::Url) => is_valid_url(s),
+            (Value::String(s), FieldType::Ip) => is_valid_ip(s),
+            (Value::String(s), FieldType::Semver) => is_valid_semver(s),
+            (Value::String(s), FieldType::Uuid) => is_valid_uuid(s),
+            (Value::Secret(_), FieldType::Secret | FieldType::String) => true,
+            (Value::Null, FieldType::Null) => true,
+            (_, FieldType::Any) => true,
+            _ => false,
+        }
+    }
+
+    /// Get a human-readable type name for a value
+    fn value_type_name(&self, value: &Value) -> &'static str {
+        match value {
+            Value::String(_) => "String",
+            Value::Integer(_) => "Integer",
+            Value::Float(_) => "Float",
+            Value::Bool(_) => "Bool",
+            Value::Array(_) => "Array",
+            Value::Table(_) => "Table",
+            Value::Null => "Null",
+            Value::Size(_) => "Size",
+            Value::Duration(_) => "Duration",
+            Value::Secret(_) => "Secret",
+            Value::Binary(_) => "Binary",
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => "DateTime",
+        }
+    }
+}
+
+impl FieldType {
+    /// Apply [`Schema::apply_compat`] to any table schemas inside this type
+    fn apply_compat(&self, value: &mut Value) {
+        match (self, value) {
+            (FieldType::Table(schema), value @ Value::Table(_)) => schema.apply_compat(value),
+            (FieldType::Optional(inner), value) => inner.apply_compat(value),
+            (FieldType::Array(inner), Value::Array(items)) => {
+                for item in items {
+                    inner.apply_compat(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Deprecation {
+    /// Warning text for a deprecated field found at `path`
+    fn warning(&self, path: &str, prefix: &str) -> String {
+        let mut message = format!("Field '{path}' is deprecated");
+        if !self.message.is_empty() {
+            message.push_str(": ");
+            message.push_str(&self.message);
+        }
+        if let Some(replacement) = &self.replacement {
+            message.push_str(&format!(
+                "; use '{}' instead",
+                join_path(prefix, replacement)
+            ));
+        }
+        message
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder for creating schemas more easily
+pub struct SchemaBuilder {
+    schema: Schema,
+}
+
+impl SchemaBuilder {
+    /// Create a new schema builder
+    pub fn new() -> Self {
+        Self {
+            schema: Schema::new(),
+        }
+    }
+
+    /// Add a required string field
+    pub fn require_string(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::String);
+        self
+    }
+
+    /// Add a required integer field
+    pub fn require_integer(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Integer);
+        self
+    }
+
+    /// Add an optional boolean field
+    pub fn optional_bool(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Bool);
+        self
+    }
+
+    /// Add an optional string field
+    pub fn optional_string(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::String);
+        self
+    }
+
+    /// Add an optional integer field
+    pub fn optional_integer(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Integer);
+        self
+    }
+
+    /// Add a required float field
+    pub fn require_float(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Float);
+        self
+    }
+
+    /// Add an optional float field
+    pub fn optional_float(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Float);
+        self
+    }
+
+    /// Add a required boolean field
+    pub fn require_bool(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Bool);
+        self
+    }
+
+    /// Add a required datetime field
+    pub fn require_datetime(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::DateTime);
+        self
+    }
+
+    /// Add an optional datetime field
+    pub fn optional_datetime(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::DateTime);
+        self
+    }
+
+    /// Add a required size field
+    pub fn require_size(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Size);
+        self
+    }
+
+    /// Add an optional size field
+    pub fn optional_size(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Size);
+        self
+    }
+
+    /// Add a required duration field
+    pub fn require_duration(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Duration);
+        self
+    }
+
+    /// Add an optional duration field
+    pub fn optional_duration(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Duration);
+        self
+    }
+
+    /// Add a required URL field
+    pub fn require_url(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Url);
+        self
+    }
+
+    /// Add an optional URL field
+    pub fn optional_url(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Url);
+        self
+    }
+
+    /// Add a required IP address field
+    pub fn require_ip(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Ip);
+        self
+    }
+
+    /// Add an optional IP address field
+    pub fn optional_ip(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Ip);
+        self
+    }
+
+    /// Add a required semver field
+    pub fn require_semver(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Semver);
+        self
+    }
+
+    /// Add an optional semver field
+    pub fn optional_semver(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Semver);
+        self
+    }
+
+    /// Add a required UUID field
+    pub fn require_uuid(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Uuid);
+        self
+    }
+
+    /// Add an optional UUID field
+    pub fn optional_uuid(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Uuid);
+        self
+    }
+
+    /// Add a required secret field
+    pub fn require_secret(mut self, name: &str) -> Self {
+        self.schema = self.schema.required_field(name, FieldType::Secret);
+        self
+    }
+
+    /// Add an optional secret field
+    pub fn optional_secret(mut self, name: &str) -> Self {
+        self.schema = self.schema.optional_field(name, FieldType::Secret);
+        self
+    }
+
+    /// Attach a description to a field already added
+    pub fn describe(mut self, name: &str, description: &str) -> Self {
+        self.schema = self.schema.describe(name, description);
+        self
+    }
+
+    /// Mark a field as deprecated, optionally naming the field replacing it
+    pub fn deprecate(mut self, name: &str, message: &str, replacement: Option<&str>) -> Self {
+        self.schema = self.schema.deprecate(name, message, replacement);
+        self
+    }
+
+    /// Add a cross-field rule
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.schema = self.schema.rule(rule);
+        self
+    }
+
+    /// Require `field` whenever the value at `when` equals `equals`
+    pub fn required_if(mut self, field: &str, when: &str, equals: Value) -> Self {
+        self.schema = self.schema.required_if(field, when, equals);
+        self
+    }
+
+    /// Allow at most one of `fields` to be set
+    pub fn mutually_exclusive(mut self, fields: &[&str]) -> Self {
+        self.schema = self.schema.mutually_exclusive(fields);
+        self
+    }
+
+    /// Require exactly one of `fields` to be set
+    pub fn exactly_one_of(mut self, fields: &[&str]) -> Self {
+        self.schema = self.schema.exactly_one_of(fields);
+        self
+    }
+
+    /// Require at least one of `fields` to be set
+    pub fn at_least_one_of(mut self, fields: &[&str]) -> Self {
+        self.schema = self.schema.at_least_one_of(fields);
+        self
+    }
+
+    /// Require `left <op> right` to hold whenever both fields are set
+    pub fn compare(mut self, left: &str, op: CompareOp, right: &str) -> Self {
+        self.schema = self.schema.compare(left, op, right);
+        self
+    }
+
+    /// Build the final schema
+    pub fn build(self) -> Schema {
+        self.schema
+    }
+}
+
+fn to_strings(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|field| field.to_string()).collect()
+}
+
+impl Default for SchemaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_basic_schema_validation() {
+        let schema = Schema::new()
+            .required_field("name", FieldType::String)
+            .required_field("port", FieldType::Integer)
+            .optional_field("debug", FieldType::Bool);
+
+        // Valid config
+        let mut config = BTreeMap::new();
+        config.insert("name".to_string(), Value::String("test".to_string()));
+        config.insert("port".to_string(), Value::Integer(8080));
+        config.insert("debug".to_string(), Value::Bool(true));
+
+        let valid_value = Value::Table(config);
+        assert!(schema.validate(&valid_value).is_ok());
+
+        // Missing required field
+        let mut invalid_config = BTreeMap::new();
+        invalid_config.insert("name".to_string(), Value::String("test".to_string()));
+        // missing port
+
+        let invalid_value = Value::Table(invalid_config);
+        assert!(schema.validate(&invalid_value).is_err());
+    }
+
+    #[test]
+    fn test_schema_builder() {
+        let schema = SchemaBuilder::new()
+            .require_string("app_name")
+            .require_integer("version")
+            .optional_bool("debug")
+            .build();
+
+        let mut config = BTreeMap::new();
+        config.insert("app_name".to_string(), Value::String("MyApp".to_string()));
+        config.insert("version".to_string(), Value::Integer(1));
+
+        let value = Value::Table(config);
+        assert!(schema.validate(&value).is_ok());
+    }
+
+    #[test]
+    fn test_array_validation() {
+        let schema =
+            Schema::new().required_field("tags", FieldType::Array(Box::new(FieldType::String)));
+
+        let mut config = BTreeMap::new();
+        config.insert(
+            "tags".to_string(),
+            Value::Array(vec![
+                Value::String("web".to_string()),
+                Value::String("api".to_string()),
+            ]),
+        );
+
+        let value = Value::Table(config);
+        assert!(schema.validate(&value).is_ok());
+
+        // Invalid array element type
+        let mut invalid_config = BTreeMap::new();
+        invalid_config.insert(
+            "tags".to_string(),
+            Value::Array(vec![
+                Value::String("web".to_string()),
+                Value::Integer(123), // Wrong type
+            ]),
+        );
+
+        let invalid_value = Value::Table(invalid_config);
+        assert!(schema.validate(&invalid_value).is_err());
+    }
+
+    #[test]
+    fn test_native_field_types() {
+        let schema = SchemaBuilder::new()
+            .require_size("max_upload")
+            .require_duration("timeout")
+            .require_url("endpoint")
+            .require_ip("bind")
+            .require_semver("version")
+            .require_uuid("instance_id")
+            .require_datetime("released")
+            .build();
+
+        let mut config = BTreeMap::new();
+        config.insert("max_upload".to_string(), Value::Size(10 * 1024 * 1024));
+        config.insert("timeout".to_string(), Value::Duration(30.0));
+        config.insert(
+            "endpoint".to_string(),
+            Value::String("https://api.example.com".to_string()),
+        );
+        config.insert("bind".to_string(), Value::String("::1".to_string()));
+        config.insert("version".to_string(), Value::String("1.2.3".to_string()));
+        config.insert(
+            "instance_id".to_string(),
+            Value::String("550e8400-e29b-41d4-a716-446655440000".to_string()),
+        );
+        config.insert(
+            "released".to_string(),
+            Value::String("2024-01-15T10:30:00Z".to_string()),
+        );
+        assert!(schema.validate(&Value::Table(config.clone())).is_ok());
+
+        // Resolved @size()/@duration() values are plain numbers
+        config.insert("max_upload".to_string(), Value::Integer(1024));
+        config.insert("timeout".to_string(), Value::Float(1.5));
+        assert!(schema.validate(&Value::Table(config.clone())).is_ok());
+
+        config.insert("max_upload".to_string(), Value::Integer(-1));
+        assert!(schema.validate(&Value::Table(config.clone())).is_err());
+        config.insert("max_upload".to_string(), Value::Size(1024));
+
+        for timeout in [
+            Value::Integer(-5),
+            Value::Float(-1.5),
+            Value::Float(f64::NAN),
+        ] {
+            config.insert("timeout".to_string(), timeout);
+            assert!(schema.validate(&Value::Table(config.clone())).is_err());
+        }
+        config.insert("timeout".to_string(), Value::Integer(30));
+
+        config.insert("bind".to_string(), Value::String("localhost".to_string()));
+        assert!(schema.validate(&Value::Table(config.clone())).is_err());
+        config.insert("bind".to_string(), Value::String("10.0.0.1".to_string()));
+
+        for released in [
+            "2024-02-29",
+            "2024-12-31T23:59:60Z",
+            "2024-01-15 10:30+02:00",
+        ] {
+            config.insert("released".to_string(), Value::String(released.to_string()));
+            assert!(schema.validate(&Value::Table(config.clone())).is_ok());
+        }
+        for released in [
+            "yesterday",
+            "2024-13-99T25:61:00Z",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-01-15T10:30:00+24:00",
+        ] {
+            config.insert("released".to_string(), Value::String(released.to_string()));
+            assert!(
+                schema.validate(&Value::Table(config.clone())).is_err(),
+                "{released}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_null_and_optional_types() {
+        let schema = Schema::new()
+            .required_field("proxy", FieldType::Optional(Box::new(FieldType::Url)))
+            .required_field("unused", FieldType::Null);
+
+        let mut config = BTreeMap::new();
+        config.insert("proxy".to_string(), Value::Null);
+        config.insert("unused".to_string(), Value::Null);
+        assert!(schema.validate(&Value::Table(config.clone())).is_ok());
+
+        config.insert(
+            "proxy".to_string(),
+            Value::String("http://proxy:3128".to_string()),
+        );
+        assert!(schema.validate(&Value::Table(config.clone())).is_ok());
+
+        config.insert("proxy".to_string(), Value::Integer(3128));
+        assert!(schema.validate(&Value::Table(config.clone())).is_err());
+
+        config.insert("proxy".to_string(), Value::Null);
+        config.insert("unused".to_string(), Value::Bool(false));
+        assert!(schema.validate(&Value::Table(config)).is_err());
+    }
+
+    #[test]
+    fn test_cross_field_rules() {
+        let schema = SchemaBuilder::new()
+            .required_if("tls.cert_path", "tls.enabled", Value::Bool(true))
+            .mutually_exclusive(&["password", "password_file"])
+            .at_least_one_of(&["host", "socket"])
+            .compare("pool.min", CompareOp::Le, "pool.max")
+            .build();
+
+        let valid = crate::parse(
+            r#"
+            host = "localhost"
+            password = "secret"
+            tls = { enabled = true, cert_path = "/etc/cert.pem" }
+            pool = { min = 1, max = 10 }
+            "#,
+        )
+        .unwrap();
+        assert!(schema.validate(&valid).is_ok());
+
+        let invalid = crate::parse(
+            r#"
+            password = "secret"
+            password_file = "/run/secrets/db"
+            tls = { enabled = true }
+            pool = { min = 20, max = 10 }
+            "#,
+        )
+        .unwrap();
+        let report = schema.validate_all(&invalid);
+        let paths: Vec<&str> = report.errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["tls.cert_path", "password", "host", "pool.min"]);
+        assert!(report.errors[3]
+            .message
+            .contains("'pool.min' must be <= 'pool.max'"));
+        assert!(schema.validate(&invalid).is_err());
+
+        // Conditions that don't trigger leave the dependent field optional
+        let disabled = crate::parse(
+            r#"
+            socket = "/var/run/db.sock"
+            tls = { enabled = false }
+            "#,
+        )
+        .unwrap();
+        assert!(schema.validate(&disabled).is_ok());
+    }
+
+    #[test]
+    fn test_validate_all_collects_field_and_rule_errors() {
+        let db_schema = Schema::new()
+            .required_field("host", FieldType::String)
+            .required_field("port", FieldType::Integer)
+            .exactly_one_of(&["password", "password_file"]);
+        let schema = Schema::new()
+            .required_field("name", FieldType::String)
+            .required_field("database", FieldType::Table(db_schema))
+            .allow_additional(false);
+
+        let config = crate::parse(
+            r#"
+            extra = 1
+            [database]
+            port = "5432"
+            "#,
+        )
+        .unwrap();
+
+        let report = schema.validate_all(&config);
+        let paths: Vec<&str> = report.errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "name",
+                "database.host",
+                "database.port",
+                "database.password",
+                "extra"
+            ]
+        );
+        assert!(!report.is_valid());
+        assert!(report
+            .to_string()
+            .contains("Required field 'database.host' is missing"));
+    }
+
+    #[test]
+    fn test_field_type_round_trip() {
+        for name in [
+            "string",
+            "array<optional<size>>",
+            "string | integer",
+            "optional<array<url | ip>>",
+        ] {
+            let field_type: FieldType = name.parse().unwrap();
+            assert_eq!(field_type.to_string(), name);
+        }
+        assert_eq!("int".parse::<FieldType>().unwrap(), FieldType::Integer);
+        assert!("strng".parse::<FieldType>().is_err());
+    }
+
+    #[test]
+    fn test_schema_from_string() {
+        let schema = Schema::from_string(
+            r#"
+            allow_additional = false
+
+            [fields.name]
+            type = "string"
+            required = true
+            description = "Service name"
+
+            [fields.database.fields.port]
+            type = "integer"
+            default = 5432
+
+            [[rules]]
+            kind = "at_least_one_of"
+            fields = ["name", "alias"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(!schema.allow_additional);
+        assert_eq!(
+            schema.fields["name"].description.as_deref(),
+            Some("Service name")
+        );
+        let FieldType::Table(db_schema) = &schema.fields["database"].field_type else {
+            panic!("database should be a table");
+        };
+        assert_eq!(db_schema.fields["port"].default, Some(Value::Integer(5432)));
+        assert_eq!(
+            schema.rules,
+            [Rule::AtLeastOneOf(vec![
+                "name".to_string(),
+                "alias".to_string()
+            ])]
+        );
+
+        let err = Schema::from_string("[fields.port]\ntype = \"integr\"").unwrap_err();
+        assert!(err.to_string().contains("fields.port.type"));
+    }
+
+    #[test]
+    fn test_render_docs_and_template() {
+        let db_schema = Schema::new()
+            .required_field("host", FieldType::String)
+            .field_with_default("port", FieldType::Integer, Value::Integer(5432))
+            .describe("port", "Database port");
+        let schema = Schema::new()
+            .required_field("name", FieldType::String)
+            .describe("name", "Service name")
+            .optional_field("debug", FieldType::Bool)
+            .optional_field("a|b", FieldType::Bool)
+            .field_with_default("token", FieldType::Secret, Value::secret("dev-token"))
+            .required_field("database", FieldType::Table(db_schema))
+            .compare("pool.min", CompareOp::Le, "pool.max");
+
+        let markdown = schema.render_docs(DocFormat::Markdown);
+        assert!(markdown.contains("| `a\\|b` | `bool` | no |  |  |"));
+        assert!(markdown.contains("| `database.port` | `integer` | no | `5432` | Database port |"));
+        assert!(markdown.contains("- `pool.min` must be `<=` `pool.max`"));
+        assert!(markdown.contains("| `token` | `secret` | no | `(secret)` |  |"));
+        assert!(markdown.contains("```noml\n"));
+
+        let html = schema.render_docs(DocFormat::Html);
+        assert!(html.contains("<td><code>name</code></td><td><code>string</code></td><td>yes</td>"));
+        assert!(
+            html.contains("<code>pool.min</code> must be <code>&lt;=</code> <code>pool.max</code>")
+        );
+
+        let template = schema.render_template();
+        assert!(template.contains("# Service name\n# type: string (required)\nname = \"\"\n"));
+        assert!(template.contains("# debug = false"));
+        assert!(template.contains("# token = secret(\"env\", \"NAME\")"));
+        assert!(!template.contains("dev-token") && !template.contains("[redacted]"));
+        assert!(template.contains("[database]\n"));
+
+        // The template is valid NOML and satisfies the schema
+        let value = crate::parse(&template).unwrap();
+        assert_eq!(value.get("database.port"), Some(&Value::Integer(5432)));
+        assert!(value.get("debug").is_none());
+        assert!(schema.validate(&value).is_ok());
+    }
+
+    #[test]
+    fn test_deprecated_fields() {
+        let db_schema = Schema::new()
+            .required_field("host", FieldType::String)
+            .deprecate("hostname", "", Some("host"));
+        let schema = Schema::new()
+            .optional_field("database", FieldType::Table(db_schema))
+            .deprecate("db_host", "moved into [database]", Some("database.host"))
+            .allow_additional(false);
+
+        let mut value = crate::parse(
+            r#"
+            db_host = "old.example.com"
+            database = { hostname = "db.example.com" }
+            "#,
+        )
+        .unwrap();
+
+        let report = schema.validate_all(&value);
+        assert_eq!(report.errors.len(), 1); // database.host is missing
+        assert_eq!(
+            report.warnings[0].message,
+            "Field 'database.hostname' is deprecated; use 'database.host' instead"
+        );
+        assert_eq!(
+            report.warnings[1].message,
+            "Field 'db_host' is deprecated: moved into [database]; use 'database.host' instead"
+        );
+
+        // Compatibility mode reads the old keys, innermost replacement first
+        schema.apply_compat(&mut value);
+        assert_eq!(
+            value.get("database.host"),
+            Some(&Value::String("db.example.com".to_string()))
+        );
+        let report = schema.validate_all(&value);
+        assert!(report.is_valid());
+        assert_eq!(report.warnings.len(), 2);
+        assert!(report.to_string().contains("warning: Field 'db_host'"));
+
+        // Deprecations load from schema files and show up in the docs
+        let schema = Schema::from_string(
+            r#"
+            [fields.db_host]
+            deprecated = "moved into [database]"
+            replacement = "database.host"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            schema.fields["db_host"].deprecated,
+            Some(Deprecation {
+                message: "moved into [database]".to_string(),
+                replacement: Some("database.host".to_string()),
+            })
+        );
+        assert!(schema
+            .render_docs(DocFormat::Markdown)
+            .contains("Deprecated: moved into [database] (use database.host instead)"));
+        assert!(!schema.render_template().contains("db_host"));
+    }
+}
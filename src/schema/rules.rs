@@ -0,0 +1,259 @@
+//! # Cross-Field Schema Rules
+//!
+//! Declarative rules that span more than one field, such as "if `tls.enabled`
+//! is true then `tls.cert_path` is required" or "`pool.min <= pool.max`".
+//! Rules are attached to a [`Schema`](super::Schema) and checked after the
+//! per-field validation, with failures collected in the same
+//! [`ValidationReport`](super::ValidationReport).
+
+use super::ValidationReport;
+use crate::value::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A validation rule spanning one or more fields
+///
+/// Field names are dotted paths relative to the table the owning schema
+/// validates. A field whose value is `null` counts as absent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// `field` is required when the value at `when` equals `equals`
+    RequiredIf {
+        /// Field that becomes required
+        field: String,
+        /// Field whose value is checked
+        when: String,
+        /// Value that triggers the requirement
+        equals: Value,
+    },
+    /// At most one of the fields may be set
+    MutuallyExclusive(Vec<String>),
+    /// Exactly one of the fields must be set
+    ExactlyOneOf(Vec<String>),
+    /// At least one of the fields must be set
+    AtLeastOneOf(Vec<String>),
+    /// Compare two fields, e.g. `pool.min <= pool.max`
+    ///
+    /// The rule is skipped when either side is absent; use required fields
+    /// to enforce presence.
+    Compare {
+        /// Left-hand field
+        left: String,
+        /// Comparison operator
+        op: CompareOp,
+        /// Right-hand field
+        right: String,
+    },
+}
+
+/// Comparison operator for [`Rule::Compare`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+}
+
+impl CompareOp {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+        };
+        f.write_str(symbol)
+    }
+}
+
+impl Rule {
+    /// Check this rule against `table`, recording failures under `prefix`
+    pub(crate) fn check(&self, table: &Value, prefix: &str, report: &mut ValidationReport) {
+        match self {
+            Rule::RequiredIf {
+                field,
+                when,
+                equals,
+            } => {
+                if lookup(table, when) == Some(equals) && lookup(table, field).is_none() {
+                    report.error(
+                        join_path(prefix, field),
+                        format!(
+                            "Field '{}' is required when '{}' is {equals}",
+                            join_path(prefix, field),
+                            join_path(prefix, when)
+                        ),
+                    );
+                }
+            }
+            Rule::MutuallyExclusive(fields) => {
+                let present = present_fields(table, fields);
+                if present.len() > 1 {
+                    report.error(
+                        join_path(prefix, present[0]),
+                        format!(
+                            "Fields {} are mutually exclusive",
+                            quote_list(prefix, &present)
+                        ),
+                    );
+                }
+            }
+            Rule::ExactlyOneOf(fields) => {
+                let present = present_fields(table, fields);
+                if present.len() != 1 {
+                    let all: Vec<&str> = fields.iter().map(String::as_str).collect();
+                    report.error(
+                        join_path(prefix, all.first().copied().unwrap_or_default()),
+                        format!(
+                            "Exactly one of {} must be set, found {}",
+                            quote_list(prefix, &all),
+                            present.len()
+                        ),
+                    );
+                }
+            }
+            Rule::AtLeastOneOf(fields) => {
+                if present_fields(table, fields).is_empty() {
+                    let all: Vec<&str> = fields.iter().map(String::as_str).collect();
+                    report.error(
+                        join_path(prefix, all.first().copied().unwrap_or_default()),
+                        format!("At least one of {} must be set", quote_list(prefix, &all)),
+                    );
+                }
+            }
+            Rule::Compare { left, op, right } => {
+                let (Some(left_val), Some(right_val)) = (lookup(table, left), lookup(table, right))
+                else {
+                    return;
+                };
+                let left_path = join_path(prefix, left);
+                let right_path = join_path(prefix, right);
+                match compare_values(left_val, right_val) {
+                    Some(ordering) if op.holds(ordering) => {}
+                    Some(_) => report.error(
+                        left_path.clone(),
+                        format!(
+                            "'{left_path}' must be {op} '{right_path}' (got {left_val} and {right_val})"
+                        ),
+                    ),
+                    None => report.error(
+                        left_path.clone(),
+                        format!(
+                            "Cannot compare '{left_path}' ({}) with '{right_path}' ({})",
+                            left_val.type_name(),
+                            right_val.type_name()
+                        ),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Describe this rule in prose, formatting field names and values with `code`
+    pub(crate) fn describe(&self, prefix: &str, code: &dyn Fn(&str) -> String) -> String {
+        let field = |name: &str| code(&join_path(prefix, name));
+        let list = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| field(name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Rule::RequiredIf {
+                field: required,
+                when,
+                equals,
+            } => format!(
+                "{} is required when {} is {}",
+                field(required),
+                field(when),
+                code(&equals.to_string())
+            ),
+            Rule::MutuallyExclusive(fields) => {
+                format!("At most one of {} may be set", list(fields))
+            }
+            Rule::ExactlyOneOf(fields) => format!("Exactly one of {} must be set", list(fields)),
+            Rule::AtLeastOneOf(fields) => format!("At least one of {} must be set", list(fields)),
+            Rule::Compare { left, op, right } => {
+                format!(
+                    "{} must be {} {}",
+                    field(left),
+                    code(&op.to_string()),
+                    field(right)
+                )
+            }
+        }
+    }
+}
+
+/// Look up a dotted path, treating `null` as absent
+fn lookup<'a>(table: &'a Value, path: &str) -> Option<&'a Value> {
+    table.get(path).filter(|value| !value.is_null())
+}
+
+fn present_fields<'a>(table: &Value, fields: &'a [String]) -> Vec<&'a str> {
+    fields
+        .iter()
+        .filter(|field| lookup(table, field).is_some())
+        .map(String::as_str)
+        .collect()
+}
+
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => numeric(left)?.partial_cmp(&numeric(right)?),
+    }
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        Value::Size(n) => Some(*n as f64),
+        Value::Duration(d) => Some(*d),
+        _ => None,
+    }
+}
+
+pub(crate) fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn quote_list(prefix: &str, fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("'{}'", join_path(prefix, field)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -7,7 +7,7 @@
 
 use crate::error::Result;
 use crate::parser::ast::{
-    AstNode, AstValue, Comment, Document, FormatMetadata, FormatStyle, Indentation, Key,
+    AstNode, AstValue, Comment, Comments, Document, FormatMetadata, FormatStyle, Indentation, Key,
     LineEnding, StringStyle, TableEntry,
 };
 use std::fmt::Write;
@@ -53,8 +53,21 @@ impl Serializer {
         self.output
             .push_str(&document.root.format.leading_whitespace);
 
+        for comment in &document.root.comments.before {
+            self.serialize_comment(comment);
+            self.add_line_ending();
+        }
+
         // Serialize the root node (typically a table)
-        self.serialize_ast_node(&document.root)?;
+        match &document.root.value {
+            AstValue::Table { entries, .. } => self.serialize_section_body(entries, "")?,
+            _ => self.serialize_ast_node(&document.root)?,
+        }
+
+        for comment in &document.root.comments.after {
+            self.serialize_comment(comment);
+            self.add_line_ending();
+        }
 
         // Add trailing whitespace from root format metadata
         self.output
@@ -111,6 +124,73 @@ impl Serializer {
         Ok(())
     }
 
+    /// Serialize the entries of a table section: plain key/value pairs first,
+    /// then `[section]` and `[[array]]` headers for nested tables
+    fn serialize_section_body(&mut self, entries: &[TableEntry], prefix: &str) -> Result<()> {
+        for entry in entries.iter().filter(|entry| !is_section(entry)) {
+            self.serialize_table_entry(entry)?;
+        }
+        for entry in entries.iter().filter(|entry| is_section(entry)) {
+            self.serialize_section(entry, prefix)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize a nested table as a `[header]` or array of tables as `[[header]]`
+    fn serialize_section(&mut self, entry: &TableEntry, prefix: &str) -> Result<()> {
+        let mut header = prefix.to_string();
+        if !header.is_empty() {
+            header.push('.');
+        }
+        let key_start = self.output.len();
+        self.serialize_key(&entry.key);
+        header.push_str(&self.output[key_start..]);
+        self.output.truncate(key_start);
+
+        match &entry.value.value {
+            AstValue::Table { entries, .. } => {
+                self.output.push_str(&entry.value.format.leading_whitespace);
+                self.serialize_header(&header, false, &entry.comments);
+                self.serialize_section_body(entries, &header)?;
+            }
+            AstValue::Array { elements, .. } => {
+                for (i, element) in elements.iter().enumerate() {
+                    let AstValue::Table { entries, .. } = &element.value else {
+                        continue;
+                    };
+                    self.output.push_str(&element.format.leading_whitespace);
+                    if i == 0 {
+                        for comment in &entry.comments.before {
+                            self.serialize_comment(comment);
+                            self.add_line_ending();
+                        }
+                    }
+                    self.serialize_header(&header, true, &element.comments);
+                    self.serialize_section_body(entries, &header)?;
+                }
+            }
+            _ => self.serialize_table_entry(entry)?,
+        }
+        Ok(())
+    }
+
+    /// Write a section header line with its comments
+    fn serialize_header(&mut self, header: &str, array: bool, comments: &Comments) {
+        for comment in &comments.before {
+            self.serialize_comment(comment);
+            self.add_line_ending();
+        }
+        let (open, close) = if array { ("[[", "]]") } else { ("[", "]") };
+        self.output.push_str(open);
+        self.output.push_str(header);
+        self.output.push_str(close);
+        if let Some(ref comment) = comments.inline {
+            self.output.push(' ');
+            self.serialize_comment(comment);
+        }
+        self.add_line_ending();
+    }
+
     /// Serialize a key with proper quoting and formatting
     fn serialize_key(&mut self, key: &Key) {
         for (i, segment) in key.segments.iter().enumerate() {
@@ -151,28 +231,58 @@ impl Serializer {
             AstValue::Array { elements, .. } => {
                 self.serialize_array(elements, &node.format)?;
             }
-            AstValue::Table { entries, inline } => {
-                if *inline {
-                    self.serialize_inline_table(entries)?;
-                } else {
-                    self.serialize_table(entries)?;
-                }
+            AstValue::Table { entries, .. } => {
+                // Sections are written by `serialize_section`; anything
+                // reaching here is a value position and must be inline
+                self.serialize_inline_table(entries)?;
             }
             AstValue::FunctionCall { name, args } => {
                 self.serialize_function_call(name, args)?;
             }
-            AstValue::Interpolation { path } => {
-                write!(self.output, "${{{path}}}").map_err(|e| {
-                    crate::error::NomlError::validation(format!(
-                        "Failed to write interpolation: {e}"
-                    ))
-                })?;
+            AstValue::Interpolation { path, fallback } => {
+                let fallback = fallback.as_ref().map(ToString::to_string);
+                write!(self.output, "${{{path}{}}}", fallback.unwrap_or_default()).map_err(
+                    |e| {
+                        crate::error::NomlError::validation(format!(
+                            "Failed to write interpolation: {e}"
+                        ))
+                    },
+                )?;
             }
-            AstValue::Include { path } => {
+            AstValue::Include {
+                path,
+                subpath: None,
+                only: None,
+                sha256: None,
+            } => {
                 write!(self.output, "include \"{path}\"").map_err(|e| {
                     crate::error::NomlError::validation(format!("Failed to write include: {e}"))
                 })?;
             }
+            AstValue::Include {
+                path,
+                subpath,
+                only,
+                sha256,
+            } => {
+                let mut args = vec![format!("\"{path}\"")];
+                args.extend(subpath.iter().map(|subpath| format!("\"{subpath}\"")));
+                if let Some(only) = only {
+                    let keys: Vec<String> = only.iter().map(|key| format!("\"{key}\"")).collect();
+                    args.push(format!("only = [{}]", keys.join(", ")));
+                }
+                if let Some(sha256) = sha256 {
+                    args.push(format!("sha256 = \"{sha256}\""));
+                }
+                write!(self.output, "include({})", args.join(", ")).map_err(|e| {
+                    crate::error::NomlError::validation(format!("Failed to write include: {e}"))
+                })?;
+            }
+            AstValue::IncludeDir { path } => {
+                write!(self.output, "include_dir(\"{path}\")").map_err(|e| {
+                    crate::error::NomlError::validation(format!("Failed to write include: {e}"))
+                })?;
+            }
             AstValue::Native { type_name, args } => {
                 write!(self.output, "@{type_name}(").map_err(|e| {
                     crate::error::NomlError::validation(format!("Failed to write native type: {e}"))
@@ -327,14 +437,6 @@ impl Serializer {
         Ok(())
     }
 
-    /// Serialize a regular table (not used for inline tables)
-    fn serialize_table(&mut self, entries: &[TableEntry]) -> Result<()> {
-        for entry in entries {
-            self.serialize_table_entry(entry)?;
-        }
-        Ok(())
-    }
-
     /// Serialize a function call
     fn serialize_function_call(&mut self, name: &str, args: &[AstNode]) -> Result<()> {
         write!(self.output, "{name}(").map_err(|e| {
@@ -387,6 +489,20 @@ impl Default for Serializer {
     }
 }
 
+/// Whether an entry is written as a `[section]` or `[[array]]` rather than `key = value`
+fn is_section(entry: &TableEntry) -> bool {
+    match &entry.value.value {
+        AstValue::Table { inline, .. } => !inline,
+        AstValue::Array { elements, .. } => {
+            !elements.is_empty()
+                && elements
+                    .iter()
+                    .all(|e| matches!(e.value, AstValue::Table { inline: false, .. }))
+        }
+        _ => false,
+    }
+}
+
 /// High-level function to serialize a document with format preservation
 pub fn serialize_document(document: &Document) -> Result<String> {
     let mut serializer = Serializer::new();
@@ -458,4 +574,29 @@ mod tests {
         serializer.serialize_ast_node(&string_node).unwrap();
         assert_eq!(serializer.output, "r\"no\\escapes\"");
     }
+
+    #[test]
+    fn test_round_trip_sections_and_comments() {
+        let source = r#"# App config
+name = "svc"
+
+# Upstream servers
+[[servers]]
+host = "a" # primary
+
+[[servers]]
+host = "b"
+
+[database.pool]
+max = 10
+"#;
+        let document = crate::parser::parse(source).unwrap();
+        assert_eq!(serialize_document(&document).unwrap(), source);
+
+        let source = r#"db = include("shared.noml", "database", only = ["host", "port"])
+conf = include_dir("conf.d")
+"#;
+        let document = crate::parser::parse(source).unwrap();
+        assert_eq!(serialize_document(&document).unwrap(), source);
+    }
 }
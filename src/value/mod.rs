@@ -12,6 +12,7 @@
 //! - **Primitives**: `null`, `bool`, `i64`, `f64`, `String`
 //! - **Collections**: `Array<Value>`, `Table<String, Value>`
 //! - **Native Types**: `Size`, `Duration`, `Binary`
+//! - **Secrets**: [`Secret`] text that is redacted when displayed
 //! - **Optional**: `DateTime` (with `chrono` feature)
 //!
 //! ## Type Conversions
@@ -94,6 +95,10 @@
 //! # Ok::<(), noml::error::NomlError>(())
 //! ```
 
+mod secret;
+
+pub use secret::Secret;
+
 use crate::error::{NomlError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -143,6 +148,13 @@ pub enum Value {
 
     /// Duration value (seconds, with human-readable format)
     Duration(f64),
+
+    /// Sensitive text, redacted wherever it is displayed
+    ///
+    /// Serializing writes `"[redacted]"` in place of the text, so it doesn't
+    /// round-trip: a `Value` reads the marker back as a plain string, and
+    /// deserializing it into a [`Secret`] is an error.
+    Secret(Secret),
 }
 
 impl Value {
@@ -196,6 +208,11 @@ impl Value {
         Value::Duration(seconds)
     }
 
+    /// Create a new secret value
+    pub fn secret(text: impl Into<String>) -> Self {
+        Value::Secret(Secret::new(text))
+    }
+
     /// Get the type name of this value
     #[inline]
     pub fn type_name(&self) -> &'static str {
@@ -212,6 +229,7 @@ impl Value {
             Value::Binary(_) => "binary",
             Value::Size(_) => "size",
             Value::Duration(_) => "duration",
+            Value::Secret(_) => "secret",
         }
     }
 
@@ -226,9 +244,12 @@ impl Value {
         matches!(self, Value::Bool(_))
     }
 
-    /// Check if this value is a number (integer or float)
+    /// Check if this value is a number (integer, float, size or duration)
     pub fn is_number(&self) -> bool {
-        matches!(self, Value::Integer(_) | Value::Float(_))
+        matches!(
+            self,
+            Value::Integer(_) | Value::Float(_) | Value::Size(_) | Value::Duration(_)
+        )
     }
 
     /// Check if this value is a string
@@ -247,6 +268,11 @@ impl Value {
         matches!(self, Value::Table(_))
     }
 
+    /// Check if this value is a secret
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Value::Secret(_))
+    }
+
     /// Convert value to boolean with intelligent string parsing
     ///
     /// Performs type conversion to boolean with support for common
@@ -334,6 +360,19 @@ impl Value {
                 .parse::<i64>()
                 .map_err(|_| NomlError::type_error(s, "integer", "string")),
             Value::Bool(b) => Ok(if *b { 1 } else { 0 }),
+            Value::Size(bytes) => i64::try_from(*bytes)
+                .map_err(|_| NomlError::type_error(bytes.to_string(), "integer", "size")),
+            Value::Duration(seconds) => {
+                if seconds.fract() == 0.0 && *seconds <= i64::MAX as f64 {
+                    Ok(*seconds as i64)
+                } else {
+                    Err(NomlError::type_error(
+                        seconds.to_string(),
+                        "integer",
+                        "duration",
+                    ))
+                }
+            }
             _ => Err(NomlError::type_error(
                 format!("<{}>", self.type_name()),
                 "integer",
@@ -348,6 +387,8 @@ impl Value {
         match self {
             Value::Float(f) => Ok(*f),
             Value::Integer(i) => Ok(*i as f64),
+            Value::Duration(seconds) => Ok(*seconds),
+            Value::Size(bytes) => Ok(*bytes as f64),
             Value::String(s) => s
                 .parse::<f64>()
                 .map_err(|_| NomlError::type_error(s, "float", "string")),
@@ -372,6 +413,19 @@ impl Value {
         }
     }
 
+    /// Try to get as a secret; its text is reachable through
+    /// [`Secret::expose`]
+    pub fn as_secret(&self) -> Result<&Secret> {
+        match self {
+            Value::Secret(secret) => Ok(secret),
+            _ => Err(NomlError::type_error(
+                format!("<{}>", self.type_name()),
+                "secret",
+                self.type_name(),
+            )),
+        }
+    }
+
     /// Convert to owned string
     pub fn into_string(self) -> Result<String> {
         match self {
@@ -591,16 +645,14 @@ impl fmt::Display for Value {
             Value::Binary(data) => write!(f, "<{} bytes>", data.len()),
             Value::Size(bytes) => write!(f, "{}", format_size(*bytes)),
             Value::Duration(seconds) => write!(f, "{}", format_duration(*seconds)),
+            Value::Secret(secret) => write!(f, "{secret}"),
         }
     }
 }
 
-/// Format a size in bytes to human-readable format
+/// Format a size in bytes to human-readable format, e.g. `1.5KB` or `10MB`
 fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
-    if bytes == 0 {
-        return "0B".to_string();
-    }
 
     let mut size = bytes as f64;
     let mut unit_index = 0;
@@ -613,22 +665,48 @@ fn format_size(bytes: u64) -> String {
     if unit_index == 0 {
         format!("{bytes}B")
     } else {
-        format!("{:.1}{}", size, UNITS[unit_index])
+        format!("{}{}", trim_fraction(size), UNITS[unit_index])
+    }
+}
+
+/// Format a byte count using the largest unit that divides it exactly, so
+/// the result parses back to the same size
+pub(crate) fn format_size_exact(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB", "PB"];
+    let mut size = bytes;
+    let mut unit = "B";
+    for candidate in UNITS {
+        if size == 0 || size % 1024 != 0 {
+            break;
+        }
+        size /= 1024;
+        unit = candidate;
     }
+    format!("{size}{unit}")
 }
 
 /// Format a duration in seconds to human-readable format
 fn format_duration(seconds: f64) -> String {
-    if seconds < 1.0 {
-        format!("{}ms", (seconds * 1000.0) as u64)
+    let (amount, unit) = if seconds < 1.0 {
+        (seconds * 1000.0, "ms")
     } else if seconds < 60.0 {
-        format!("{seconds:.1}s")
+        (seconds, "s")
     } else if seconds < 3600.0 {
-        format!("{:.1}m", seconds / 60.0)
+        (seconds / 60.0, "m")
     } else if seconds < 86400.0 {
-        format!("{:.1}h", seconds / 3600.0)
+        (seconds / 3600.0, "h")
     } else {
-        format!("{:.1}d", seconds / 86400.0)
+        (seconds / 86400.0, "d")
+    };
+    format!("{}{unit}", trim_fraction(amount))
+}
+
+/// Format with one decimal place, dropping it when it is zero (`30` not `30.0`)
+fn trim_fraction(amount: f64) -> String {
+    let text = format!("{amount:.1}");
+    match text.strip_suffix(".0") {
+        Some(whole) => whole.to_string(),
+        None => text,
     }
 }
 
@@ -687,6 +765,12 @@ impl From<BTreeMap<String, Value>> for Value {
     }
 }
 
+impl From<Secret> for Value {
+    fn from(secret: Secret) -> Self {
+        Value::Secret(secret)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -705,11 +789,26 @@ mod tests {
         assert!(int_val.is_number());
         assert_eq!(int_val.as_integer().unwrap(), 42);
 
+        let duration = Value::Duration(30.0);
+        assert!(duration.is_number());
+        assert_eq!(duration.as_integer().unwrap(), 30);
+        assert!(Value::Duration(0.5).as_integer().is_err());
+
         let str_val = Value::string("hello");
         assert!(str_val.is_string());
         assert_eq!(str_val.as_string().unwrap(), "hello");
     }
 
+    #[test]
+    fn secrets_serialize_one_way() {
+        use serde::de::value::{Error, StrDeserializer};
+
+        let read = |text| Secret::deserialize(StrDeserializer::<Error>::new(text));
+        assert_eq!(read("hunter2").unwrap().expose(), "hunter2");
+        let err = read("[redacted]").unwrap_err();
+        assert!(err.to_string().contains("redacted secret"), "{err}");
+    }
+
     #[test]
     fn nested_key_operations() {
         let mut value = Value::empty_table();
@@ -785,5 +884,19 @@ mod tests {
         let _: Value = "hello".into();
         let _: Value = String::from("world").into();
         let _: Value = vec![Value::integer(1), Value::integer(2)].into();
+        let _: Value = Secret::new("hunter2").into();
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let mut table = Value::empty_table();
+        table.set("db.password", Value::secret("hunter2")).unwrap();
+
+        assert_eq!(table.to_string(), "{db: {password: [redacted]}}");
+        assert!(!format!("{table:?}").contains("hunter2"));
+        let password = table.get("db.password").unwrap();
+        assert!(password.is_secret());
+        assert!(password.as_string().is_err());
+        assert_eq!(password.as_secret().unwrap().expose(), "hunter2");
     }
 }
@@ -0,0 +1,76 @@
+//! # Secret Values
+//!
+//! Passwords, tokens and keys are held in a [`Secret`] so they don't end up
+//! in logs: `Display`, `Debug`, serialization and error messages all show
+//! `[redacted]`, and the text is only reachable through
+//! [`Secret::expose`].
+//!
+//! ```rust
+//! use noml::Value;
+//!
+//! let password = Value::secret("hunter2");
+//! assert_eq!(password.to_string(), "[redacted]");
+//! assert!(!format!("{password:?}").contains("hunter2"));
+//! assert_eq!(password.as_secret()?.expose(), "hunter2");
+//!
+//! # Ok::<(), noml::NomlError>(())
+//! ```
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// What a secret is shown as in place of its text
+pub(crate) const REDACTED: &str = "[redacted]";
+
+/// Sensitive text that is redacted wherever it is displayed
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap sensitive text
+    pub fn new(text: impl Into<String>) -> Self {
+        Self(text.into())
+    }
+
+    /// The secret text itself
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwrap the secret text
+    pub fn into_exposed(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+/// Serialization is one-way: the serialized marker is refused rather than
+/// read back as the secret text
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if text == REDACTED {
+            return Err(de::Error::custom(
+                "cannot deserialize a redacted secret; its text was never serialized",
+            ));
+        }
+        Ok(Secret(text))
+    }
+}
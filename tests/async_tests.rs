@@ -7,9 +7,63 @@
 mod async_tests {
     use noml::{parse_async, parse_from_file_async, Config, Value};
     use std::env;
-    use std::io::Write;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
     use tempfile::NamedTempFile;
 
+    /// A local HTTP server answering every request with one body and an
+    /// `ETag`, or `304 Not Modified` when the request already has it
+    #[derive(Clone)]
+    struct StandIn {
+        url: String,
+        body: Arc<Mutex<String>>,
+        /// Number of requests and of `304` answers
+        counts: Arc<Mutex<(usize, usize)>>,
+    }
+
+    impl StandIn {
+        fn start(body: &str) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let server = Self {
+                url: format!("http://{}", listener.local_addr().unwrap()),
+                body: Arc::new(Mutex::new(body.to_string())),
+                counts: Arc::default(),
+            };
+            let handler = server.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut request = Vec::new();
+                    let mut buffer = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let read = stream.read(&mut buffer).unwrap();
+                        if read == 0 {
+                            break;
+                        }
+                        request.extend_from_slice(&buffer[..read]);
+                    }
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let body = handler.body.lock().unwrap().clone();
+                    let etag = format!("\"{}\"", body.len());
+                    let mut counts = handler.counts.lock().unwrap();
+                    counts.0 += 1;
+                    let response = if request.contains(&format!("if-none-match: {etag}")) {
+                        counts.1 += 1;
+                        "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                    } else {
+                        format!(
+                            "HTTP/1.1 200 OK\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                    };
+                    stream.write_all(response.as_bytes()).unwrap();
+                }
+            });
+            server
+        }
+    }
+
     #[tokio::test]
     async fn test_parse_async_basic() {
         let source = r#"
@@ -288,4 +342,56 @@ mod async_tests {
             "550e8400-e29b-41d4-a716-446655440000"
         );
     }
+
+    #[tokio::test]
+    async fn test_remote_includes_with_lockfile() {
+        let server = StandIn::start("workers = 4");
+        let url = format!("{}/base.noml", server.url);
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join(noml::LOCKFILE);
+        let document = noml::parse_string(&format!("base = include \"{url}\""), None).unwrap();
+
+        // A URL that isn't locked is refused
+        let lockfile = noml::Lockfile::new(&lock_path);
+        let mut config = noml::ResolverConfig {
+            http_cache: Some(noml::HttpCache::new(std::time::Duration::ZERO)),
+            lockfile: Some(lockfile.clone()),
+            ..Default::default()
+        };
+        let err = noml::Resolver::with_config(config.clone())
+            .resolve_document_async(&document)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("noml lock"), "{err}");
+
+        // Fetching with a locking lockfile locks it
+        config.lockfile = Some(lockfile.clone().with_locking(true));
+        let mut resolver = noml::Resolver::with_config(config.clone());
+        let value = resolver.resolve_document_async(&document).await.unwrap();
+        assert_eq!(value.get("base.workers"), Some(&Value::Integer(4)));
+        lockfile.save().unwrap();
+        assert_eq!(lockfile.urls(), std::slice::from_ref(&url));
+
+        // A stale cached copy is revalidated with its ETag
+        resolver.resolve_document_async(&document).await.unwrap();
+        assert_eq!(*server.counts.lock().unwrap(), (3, 2));
+
+        // Changed contents no longer match the lock, even while locking
+        *server.body.lock().unwrap() = "workers = 16".to_string();
+        let err = noml::Resolver::with_config(config)
+            .resolve_document_async(&document)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, noml::NomlError::Security { .. }), "{err}");
+
+        // Offline, the vendored copy is used without asking the server
+        let value = noml::Resolver::new()
+            .with_lockfile(noml::Lockfile::load(&lock_path).unwrap())
+            .with_offline(true)
+            .resolve_document_async(&document)
+            .await
+            .unwrap();
+        assert_eq!(value.get("base.workers"), Some(&Value::Integer(4)));
+        assert_eq!(server.counts.lock().unwrap().0, 4);
+    }
 }